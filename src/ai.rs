@@ -0,0 +1,374 @@
+//! AI providers for OpenSpec plan generation
+//!
+//! Each provider takes the generated prompt and returns the plan text. The
+//! provider is picked with `ai_provider` in `[openspec]`:
+//! - `claude-cli` (default): runs `ai_command_template`, the Claude CLI by default
+//! - `openai`: any OpenAI-compatible chat completions endpoint
+//! - `ollama`: a local Ollama server
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::api::{build_http_client, HttpClientOptions};
+use crate::config::OpenSpecConfig;
+
+const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const DEFAULT_OLLAMA_MODEL: &str = "llama3.1";
+
+/// Which backend generates OpenSpec plans
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AiProviderKind {
+    /// The Claude CLI (`claude -p`), configured by `ai_command_template`
+    #[default]
+    ClaudeCli,
+    /// An OpenAI-compatible chat completions API, authenticated with OPENAI_API_KEY
+    #[serde(rename = "openai")]
+    OpenAiApi,
+    /// A local Ollama server
+    #[serde(rename = "ollama")]
+    OllamaLocal,
+}
+
+impl AiProviderKind {
+    /// Name as written in config
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AiProviderKind::ClaudeCli => "claude-cli",
+            AiProviderKind::OpenAiApi => "openai",
+            AiProviderKind::OllamaLocal => "ollama",
+        }
+    }
+}
+
+/// The provider gave up after the configured timeout
+#[derive(Debug, thiserror::Error)]
+#[error("AI generation timed out after {}s", .0.as_secs())]
+pub struct AiTimeout(pub Duration);
+
+/// Something that turns a prompt into plan text
+#[async_trait]
+pub trait AiProvider: Send + Sync {
+    /// Short name used in log and error messages
+    fn name(&self) -> &str;
+
+    /// Send the prompt and return the generated plan text
+    ///
+    /// Fails with [`AiTimeout`] when no answer arrives within `timeout`.
+    async fn generate(&self, prompt: &str, timeout: Duration) -> Result<String>;
+}
+
+/// Build the provider selected in config
+///
+/// The HTTP providers use the same proxy and TLS settings as the Azure DevOps client.
+pub fn create_provider(config: &OpenSpecConfig, http_options: &HttpClientOptions) -> Result<Box<dyn AiProvider>> {
+    let provider: Box<dyn AiProvider> = match config.ai_provider {
+        AiProviderKind::ClaudeCli => Box::new(ClaudeCli {
+            command_template: config.ai_command_template.clone(),
+        }),
+        AiProviderKind::OpenAiApi => {
+            let api_key = std::env::var("OPENAI_API_KEY")
+                .ok()
+                .filter(|key| !key.trim().is_empty())
+                .ok_or_else(|| anyhow!("ai_provider is \"openai\" but the OPENAI_API_KEY environment variable is not set"))?;
+            Box::new(OpenAiApi {
+                client: build_http_client(http_options)?,
+                base_url: config.ai_api_url.clone().unwrap_or_else(|| DEFAULT_OPENAI_URL.to_string()),
+                model: config.ai_model.clone().unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string()),
+                api_key: api_key.trim().to_string(),
+            })
+        }
+        AiProviderKind::OllamaLocal => Box::new(OllamaLocal {
+            client: build_http_client(http_options)?,
+            base_url: config.ai_api_url.clone().unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string()),
+            model: config.ai_model.clone().unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string()),
+        }),
+    };
+
+    Ok(provider)
+}
+
+/// Runs `ai_command_template` in a shell
+///
+/// The prompt is written to a temp file and piped to the command's stdin, which
+/// has no length limit. A template with `{prompt}` gets a shell expression that
+/// reads the file back instead; that puts the prompt on the command line, where
+/// cmd.exe (8,191 characters) and the OS argument limit apply.
+pub struct ClaudeCli {
+    command_template: String,
+}
+
+#[async_trait]
+impl AiProvider for ClaudeCli {
+    fn name(&self) -> &str {
+        "Claude CLI"
+    }
+
+    async fn generate(&self, prompt: &str, timeout: Duration) -> Result<String> {
+        let output = self.run(prompt, timeout).await?;
+
+        let exit_code = output.status.code().unwrap_or(-1);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        debug!("Exit code: {}", exit_code);
+        debug!("Stdout length: {} bytes", stdout.len());
+        debug!("Stderr length: {} bytes", stderr.len());
+        debug!("Stdout content: {}", stdout);
+        debug!("Stderr content: {}", stderr);
+
+        if output.status.success() {
+            Ok(stdout.to_string())
+        } else {
+            Err(anyhow!("exit code {}: {}", exit_code, stderr.trim()))
+        }
+    }
+}
+
+impl ClaudeCli {
+    /// Run the command once and capture its output
+    async fn run(&self, prompt: &str, timeout: Duration) -> Result<std::process::Output> {
+        // Use temp file approach - best for long/multi-line prompts with special characters
+        let temp_dir = std::env::temp_dir();
+        let prompt_file = temp_dir.join(format!("bakery_prompt_{}.txt", std::process::id()));
+        std::fs::write(&prompt_file, prompt)
+            .map_err(|e| anyhow!("Failed to write prompt file: {}", e))?;
+        let prompt_path = prompt_file.to_string_lossy().to_string();
+
+        let prompt_on_stdin = !self.command_template.contains("{prompt}");
+
+        let (mut command, stdin, temp_files) = if cfg!(windows) {
+            // Windows: run the command from a PowerShell script
+            let script_file = temp_dir.join(format!("bakery_script_{}.ps1", std::process::id()));
+            let read_prompt = format!("Get-Content -Raw -LiteralPath '{}'", prompt_path.replace('\'', "''"));
+            let ps_script = if prompt_on_stdin {
+                format!("{} | {}", read_prompt, self.command_template)
+            } else {
+                render_command(&self.command_template, &format!("({})", read_prompt))
+            };
+
+            std::fs::write(&script_file, format!("{}\n", ps_script))
+                .map_err(|e| anyhow!("Failed to write PowerShell script: {}", e))?;
+
+            debug!("Executing PowerShell script {}: {}", script_file.display(), ps_script);
+
+            let mut command = tokio::process::Command::new("powershell.exe");
+            command.args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-ExecutionPolicy", "Bypass",
+                "-File", &script_file.to_string_lossy(),
+            ]);
+
+            (command, std::process::Stdio::null(), vec![prompt_file, script_file])
+        } else {
+            // Unix: run the command with sh, the prompt file on stdin or read back with cat
+            let (script, stdin) = if prompt_on_stdin {
+                let file = std::fs::File::open(&prompt_file)
+                    .map_err(|e| anyhow!("Failed to open prompt file: {}", e))?;
+                (self.command_template.clone(), std::process::Stdio::from(file))
+            } else {
+                let prompt_expr = format!("\"$(cat '{}')\"", prompt_path.replace('\'', r"'\''"));
+                (render_command(&self.command_template, &prompt_expr), std::process::Stdio::null())
+            };
+            debug!("Executing AI command: {}", script);
+
+            let mut command = tokio::process::Command::new("sh");
+            command.args(["-c", &script]);
+
+            // Run in its own process group so a timeout can take down `claude` too
+            #[cfg(unix)]
+            command.process_group(0);
+
+            (command, stdin, vec![prompt_file])
+        };
+
+        let child = command
+            .stdin(stdin)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn();
+
+        let result = match child {
+            Ok(child) => {
+                let pid = child.id();
+                match tokio::time::timeout(timeout, child.wait_with_output()).await {
+                    Ok(output) => output.map_err(|e| anyhow!("Failed to execute AI command: {}", e)),
+                    Err(_) => {
+                        warn!("AI command timed out after {}s; killing it", timeout.as_secs());
+                        if let Some(pid) = pid {
+                            kill_process_tree(pid);
+                        }
+                        Err(AiTimeout(timeout).into())
+                    }
+                }
+            }
+            Err(e) => Err(anyhow!("Failed to execute AI command: {}", e)),
+        };
+
+        // Clean up temp files
+        for file in temp_files {
+            let _ = std::fs::remove_file(file);
+        }
+
+        result
+    }
+}
+
+/// `template` with `{prompt}` replaced by `prompt_expr`, a shell expression that yields the prompt
+///
+/// Quotes around the placeholder are dropped: `prompt_expr` does its own quoting.
+fn render_command(template: &str, prompt_expr: &str) -> String {
+    template
+        .replace("\"{prompt}\"", "{prompt}")
+        .replace("'{prompt}'", "{prompt}")
+        .replace("{prompt}", prompt_expr)
+}
+
+/// Kill a timed-out AI command along with any processes it started
+fn kill_process_tree(pid: u32) {
+    let result = if cfg!(windows) {
+        Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .output()
+    } else {
+        // Negative PID targets the whole process group
+        Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", pid)])
+            .output()
+    };
+
+    if let Err(e) = result {
+        debug!("Failed to kill AI process tree {}: {}", pid, e);
+    }
+}
+
+/// Calls an OpenAI-compatible `/chat/completions` endpoint
+pub struct OpenAiApi {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: String,
+}
+
+#[async_trait]
+impl AiProvider for OpenAiApi {
+    fn name(&self) -> &str {
+        "OpenAI API"
+    }
+
+    async fn generate(&self, prompt: &str, timeout: Duration) -> Result<String> {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+        debug!("Sending prompt to {} (model {})", url, self.model);
+
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": [{ "role": "user", "content": prompt }],
+        });
+
+        let response = send_json(
+            self.client.post(&url).bearer_auth(&self.api_key).json(&body),
+            timeout,
+        ).await?;
+
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Response from {} had no choices[0].message.content", url))
+    }
+}
+
+/// Calls a local Ollama server's `/api/generate` endpoint
+pub struct OllamaLocal {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+#[async_trait]
+impl AiProvider for OllamaLocal {
+    fn name(&self) -> &str {
+        "Ollama"
+    }
+
+    async fn generate(&self, prompt: &str, timeout: Duration) -> Result<String> {
+        let url = format!("{}/api/generate", self.base_url.trim_end_matches('/'));
+        debug!("Sending prompt to {} (model {})", url, self.model);
+
+        let body = serde_json::json!({
+            "model": self.model,
+            "prompt": prompt,
+            "stream": false,
+        });
+
+        let response = send_json(self.client.post(&url).json(&body), timeout).await?;
+
+        response["response"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Response from {} had no \"response\" field", url))
+    }
+}
+
+/// Send a JSON request and parse the JSON reply, mapping timeouts to [`AiTimeout`]
+async fn send_json(request: reqwest::RequestBuilder, timeout: Duration) -> Result<serde_json::Value> {
+    let response = request.timeout(timeout).send().await.map_err(|e| {
+        if e.is_timeout() {
+            anyhow::Error::from(AiTimeout(timeout))
+        } else {
+            anyhow!("Failed to reach AI API: {}", e)
+        }
+    })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("HTTP {}: {}", status, error_text.trim()));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| anyhow!("Failed to parse AI API response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholder_quotes_are_replaced_with_the_expression() {
+        assert_eq!(render_command("claude -p \"{prompt}\"", "$(cat p)"), "claude -p $(cat p)");
+        assert_eq!(render_command("tool --input '{prompt}' -q", "$(cat p)"), "tool --input $(cat p) -q");
+        assert_eq!(render_command("tool {prompt}", "$(cat p)"), "tool $(cat p)");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn configured_template_is_run_with_the_prompt() {
+        let cli = ClaudeCli { command_template: "printf '[%s]' \"{prompt}\"".to_string() };
+        let prompt = "Say \"hi\" to 'Ada'\nthen print $HOME and `date`";
+
+        let output = cli.generate(prompt, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(output, format!("[{}]", prompt));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn prompt_over_the_argument_limit_is_piped_on_stdin() {
+        let cli = ClaudeCli { command_template: "cat".to_string() };
+        // Past Linux's 128 KiB limit for a single argument
+        let prompt = format!("Say \"hi\" to 'Ada' $HOME\n{}", "日本語のテキスト ".repeat(8_000));
+        assert!(prompt.len() > 128 * 1024);
+
+        let output = cli.generate(&prompt, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(output, prompt);
+    }
+}
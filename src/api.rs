@@ -1,246 +1,1119 @@
 use crate::models::*;
+use crate::filesystem::TicketPathTemplate;
+use crate::ui::Progress;
 use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use indicatif::ProgressBar;
 use reqwest::Client;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 
-const MAX_RETRIES: u32 = 3;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BASE_URL: &str = "https://dev.azure.com";
+const DEFAULT_TICKETS_DIR: &str = "Tickets";
 const RETRY_DELAY_MS: u64 = 500;
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_DOWNLOAD_TIMEOUT_SECS: u64 = 300;
+
+/// Maximum number of IDs Azure DevOps accepts in a single work item batch request
+pub const MAX_BATCH_SIZE: usize = 200;
+
+/// Longest we'll honor a server-requested throttling wait before retrying anyway
+const MAX_THROTTLE_WAIT_SECS: u64 = 300;
+
+/// Attachments at least this big get their own byte progress bar when downloaded alone
+const DOWNLOAD_PROGRESS_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Most ancestors `get_parent_chain` follows, guarding against very deep or cyclic hierarchies
+const MAX_PARENT_DEPTH: usize = 10;
+
+/// Work item fetch failures callers may want to explain rather than just print
+///
+/// `details` holds the raw status, response body and URL for verbose output.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Work item #{id} doesn't exist, or isn't visible with the current credentials")]
+    WorkItemNotFound { id: u32, details: String },
+    #[error("Access to work item #{id} was denied (HTTP {status})")]
+    AccessDenied { id: u32, status: u16, details: String },
+}
+
+impl ApiError {
+    pub fn details(&self) -> &str {
+        match self {
+            ApiError::WorkItemNotFound { details, .. } | ApiError::AccessDenied { details, .. } => details,
+        }
+    }
+}
+
+/// Token bucket settings: burst of requests allowed, then a steady refill rate
+const RATE_LIMIT_BURST: f64 = 10.0;
+const RATE_LIMIT_PER_SEC: f64 = 5.0;
+
+/// Simple token-bucket limiter shared by every request the client makes
+///
+/// Keeps bursts of attachment/image downloads from blowing past the
+/// organization's Azure DevOps consumption limits.
+struct RateLimiter {
+    state: tokio::sync::Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    tokens: f64,
+    last_refill: std::time::Instant,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        Self {
+            state: tokio::sync::Mutex::new(RateLimiterState {
+                tokens: RATE_LIMIT_BURST,
+                last_refill: std::time::Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until a request token is available, then consume it
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let now = std::time::Instant::now();
+                let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * RATE_LIMIT_PER_SEC).min(RATE_LIMIT_BURST);
+                state.last_refill = now;
+
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+
+                std::time::Duration::from_secs_f64((1.0 - state.tokens) / RATE_LIMIT_PER_SEC)
+            };
+
+            debug!("Rate limiter waiting {}ms for a request token", wait.as_millis());
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Settings for the underlying HTTP client
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientOptions {
+    /// Proxy for all requests; takes precedence over `HTTPS_PROXY`/`HTTP_PROXY`
+    pub proxy_url: Option<String>,
+    /// PEM file with an additional root certificate to trust
+    pub ca_cert_path: Option<String>,
+    /// Disable TLS certificate verification
+    pub accept_invalid_certs: bool,
+}
+
+/// Build the `reqwest` client used for API calls and attachment/image downloads
+///
+/// Proxy precedence: `proxy_url` from config, then `HTTPS_PROXY`, then
+/// `HTTP_PROXY` (upper or lower case). `NO_PROXY` exclusions apply to all of them.
+pub fn build_http_client(options: &HttpClientOptions) -> Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent("bakery/0.1.0")
+        .user_agent("Bakery Azure DevOps Scraper");
+
+    let env_var = |names: &[&str]| {
+        names.iter().find_map(|name| std::env::var(name).ok().filter(|value| !value.trim().is_empty()))
+    };
+
+    let proxy = match options.proxy_url.as_deref().filter(|url| !url.trim().is_empty()) {
+        Some(url) => Some(("proxy_url in config", url.to_string(), reqwest::Proxy::all(url))),
+        None => env_var(&["HTTPS_PROXY", "https_proxy"])
+            .map(|url| ("HTTPS_PROXY", url.clone(), reqwest::Proxy::https(&url)))
+            .or_else(|| {
+                env_var(&["HTTP_PROXY", "http_proxy"])
+                    .map(|url| ("HTTP_PROXY", url.clone(), reqwest::Proxy::http(&url)))
+            }),
+    };
+
+    if let Some((source, url, proxy)) = proxy {
+        let proxy = proxy.map_err(|e| anyhow!(
+            "Invalid proxy URL '{}' from {}: {}. Note: proxy_url in config takes precedence over the HTTPS_PROXY/HTTP_PROXY environment variables.",
+            url, source, e
+        ))?;
+        debug!("Using proxy {} (from {})", url, source);
+        builder = builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_env()));
+    }
+
+    if let Some(path) = options.ca_cert_path.as_deref().filter(|path| !path.trim().is_empty()) {
+        let path = crate::config::expand_path(path);
+        let pem = std::fs::read(&path)
+            .map_err(|e| anyhow!("Could not read ca_cert_path '{}': {}", path, e))?;
+        let certificate = reqwest::Certificate::from_pem(&pem)
+            .map_err(|e| anyhow!("ca_cert_path '{}' is not a valid PEM certificate: {}", path, e))?;
+        debug!("Trusting additional root certificate from {}", path);
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    if options.accept_invalid_certs {
+        warn!("TLS certificate verification is disabled (accept_invalid_certs = true); connections can be intercepted");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))
+}
+
+/// Credentials used to authenticate against Azure DevOps
+#[derive(Clone)]
+pub enum AzureAuth {
+    /// Personal Access Token, sent as HTTP Basic auth
+    Pat(String),
+    /// Microsoft Entra ID (OAuth) access token, sent as a Bearer token
+    Bearer(String),
+}
+
+/// An image file already written for the work item being fetched
+#[derive(Clone)]
+struct SavedImage {
+    local_path: String,
+    /// Path relative to the work item's `images` folder
+    relative_path: String,
+}
+
+/// Images saved so far for one work item, so a screenshot pasted into the
+/// description and several comments is only downloaded and stored once
+#[derive(Default)]
+struct SavedImages {
+    by_url: HashMap<String, SavedImage>,
+    /// Keyed by the SHA-256 of the image content
+    by_hash: HashMap<String, SavedImage>,
+}
+
+/// Start and finish dates of an iteration, either of which may be unset
+type IterationDates = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
 
 pub struct AzureDevOpsClient {
     client: Client,
     organization: String,
     project: String,
-    pat_token: String,
+    auth: AzureAuth,
+    api_version: String,
+    base_url: String,
+    custom_fields: Vec<String>,
+    max_attachment_size: Option<u64>,
+    max_image_size: Option<u64>,
+    allowed_content_types: Vec<String>,
+    blocked_content_types: Vec<String>,
+    max_retries: u32,
+    /// Per-request timeout for API calls; `None` waits indefinitely
+    request_timeout: Option<std::time::Duration>,
+    /// Per-request timeout for attachment and image downloads; `None` waits indefinitely
+    download_timeout: Option<std::time::Duration>,
+    rate_limiter: RateLimiter,
+    progress: Option<Progress>,
+    tickets_dir: String,
+    ticket_path_template: TicketPathTemplate,
+    download_attachments: bool,
+    download_images: bool,
+    fetch_comments: bool,
+    comments_since: Option<DateTime<Utc>>,
+    /// Write the untouched work item and comments JSON to `{ticket}/raw/`
+    save_raw_responses: bool,
+    /// Iteration dates already looked up, keyed by iteration path
+    iteration_dates: tokio::sync::Mutex<HashMap<String, IterationDates>>,
 }
 
 impl AzureDevOpsClient {
-    pub fn new(organization: String, project: String, pat_token: String) -> Self {
-        let client = Client::builder()
-            .user_agent("bakery/0.1.0")
-            .user_agent("Bakery Azure DevOps Scraper")
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+    pub fn new(organization: String, project: String, auth: AzureAuth, api_version: String) -> Result<Self> {
+        let client = build_http_client(&HttpClientOptions::default())?;
+        Self::with_client(client, organization, project, auth, api_version)
+    }
 
-        Self {
+    /// Create a client that sends its requests through an existing `reqwest::Client`
+    ///
+    /// Useful for a client pointed at a mock server, or one configured with
+    /// custom root certificates. Note that `with_http_options` replaces it.
+    pub fn with_client(client: Client, organization: String, project: String, auth: AzureAuth, api_version: String) -> Result<Self> {
+        if api_version.trim().is_empty() {
+            return Err(anyhow!("Azure DevOps api_version must not be empty (e.g. \"7.1\")"));
+        }
+
+        // Keep the token, and the Basic auth header built from it, out of every log
+        match &auth {
+            AzureAuth::Pat(token) => {
+                crate::redact::register_secret(token);
+                crate::redact::register_secret(&Self::encode_pat(token));
+            }
+            AzureAuth::Bearer(token) => crate::redact::register_secret(token),
+        }
+
+        Ok(Self {
             client,
             organization,
             project,
-            pat_token,
+            auth,
+            api_version: api_version.trim().to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            custom_fields: Vec::new(),
+            max_attachment_size: None,
+            max_image_size: None,
+            allowed_content_types: Vec::new(),
+            blocked_content_types: Vec::new(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            request_timeout: Some(std::time::Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS)),
+            download_timeout: Some(std::time::Duration::from_secs(DEFAULT_DOWNLOAD_TIMEOUT_SECS)),
+            rate_limiter: RateLimiter::new(),
+            progress: None,
+            tickets_dir: DEFAULT_TICKETS_DIR.to_string(),
+            ticket_path_template: TicketPathTemplate::default(),
+            download_attachments: true,
+            download_images: true,
+            fetch_comments: true,
+            comments_since: None,
+            save_raw_responses: false,
+            iteration_dates: tokio::sync::Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Rebuild the underlying HTTP client with the given options (proxy, ...)
+    pub fn with_http_options(mut self, options: &HttpClientOptions) -> Result<Self> {
+        self.client = build_http_client(options)?;
+        Ok(self)
+    }
+
+    /// Point the client at a different server, e.g. an on-premises Azure DevOps Server
+    /// (`https://tfs.mycompany.com/tfs`). Trailing slashes are ignored.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        let base_url = base_url.trim().trim_end_matches('/');
+        if !base_url.is_empty() {
+            self.base_url = base_url.to_string();
+        }
+        self
+    }
+
+    /// Set the custom field reference names to capture on each work item
+    pub fn with_custom_fields(mut self, custom_fields: Vec<String>) -> Self {
+        self.custom_fields = custom_fields;
+        self
+    }
+
+    /// Skip downloading attachments larger than `max_size` bytes
+    pub fn with_max_attachment_size(mut self, max_size: Option<u64>) -> Self {
+        self.max_attachment_size = max_size;
+        self
+    }
+
+    /// Skip downloading embedded images larger than `max_size` bytes, checked with a HEAD request
+    pub fn with_max_image_size(mut self, max_size: Option<u64>) -> Self {
+        self.max_image_size = max_size;
+        self
+    }
+
+    /// Restrict which attachment content types get downloaded
+    ///
+    /// An empty `allowed` list allows everything. Entries ending in `/` match
+    /// every subtype, e.g. `image/`.
+    pub fn with_content_type_filter(mut self, allowed: Vec<String>, blocked: Vec<String>) -> Self {
+        self.allowed_content_types = allowed;
+        self.blocked_content_types = blocked;
+        self
+    }
+
+    /// Report download progress (e.g. attachments) using the given progress builder
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Set how many times transient request failures are retried
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the timeouts in seconds for API calls and for attachment/image downloads (0 = no timeout)
+    pub fn with_timeouts(mut self, request_secs: u64, download_secs: u64) -> Self {
+        let timeout = |secs: u64| (secs > 0).then(|| std::time::Duration::from_secs(secs));
+        self.request_timeout = timeout(request_secs);
+        self.download_timeout = timeout(download_secs);
+        self
+    }
+
+    /// Choose whether attachment and image bytes are downloaded
+    ///
+    /// When disabled, the references (URL, filename) are still recorded.
+    pub fn with_downloads(mut self, attachments: bool, images: bool) -> Self {
+        self.download_attachments = attachments;
+        self.download_images = images;
+        self
+    }
+
+    /// Fetch work item comments; when disabled every work item comes back without comments
+    pub fn with_comments(mut self, enabled: bool) -> Self {
+        self.fetch_comments = enabled;
+        self
+    }
+
+    /// Also write the untouched API responses to `raw/workitem.json` and `raw/comments.json`
+    pub fn with_raw_responses(mut self, enabled: bool) -> Self {
+        self.save_raw_responses = enabled;
+        self
+    }
+
+    /// Drop comments created before `since`, so neither they nor their images are saved
+    pub fn with_comments_since(mut self, since: Option<DateTime<Utc>>) -> Self {
+        self.comments_since = since;
+        self
+    }
+
+    /// Download attachments and images under `{tickets_dir}/{ticket folder}/attachments|images`
+    pub fn with_tickets_dir(mut self, tickets_dir: &str) -> Self {
+        let tickets_dir = tickets_dir.trim().trim_end_matches(['/', '\\']);
+        if !tickets_dir.is_empty() {
+            self.tickets_dir = tickets_dir.to_string();
         }
+        self
+    }
+
+    /// Name ticket folders with `template`, matching where `FileSystemOrganizer` saves them
+    pub fn with_ticket_path_template(mut self, template: TicketPathTemplate) -> Self {
+        self.ticket_path_template = template;
+        self
+    }
+
+    /// Directory that downloads for the given work item are written into
+    fn ticket_dir(&self, work_item: &WorkItem) -> String {
+        format!("{}/{}", self.tickets_dir, self.ticket_path_template.resolve(work_item))
+    }
+
+    /// Fetch the configured project to check that the organization, project and credentials work
+    ///
+    /// Returns the HTTP status and the URL that was requested; only network failures are errors.
+    pub async fn test_connection(&self) -> Result<(reqwest::StatusCode, String)> {
+        let project: String = url::form_urlencoded::byte_serialize(self.project.as_bytes()).collect();
+        let url = self.api_url(&format!("projects/{}", project.replace('+', "%20")));
+
+        debug!("Testing connection with: {}", url);
+
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .get(&url)
+                    .header("Authorization", self.authorization_header())
+                    .header("Accept", "application/json")
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Azure DevOps API: {}. Check your network connection and organization URL.", e))?;
+
+        Ok((response.status(), url))
     }
 
     pub async fn get_work_item(&self, id: u32) -> Result<WorkItem> {
         info!("Fetching work item {} from Azure DevOps", id);
 
-        // First, try to get the work item without expand
-        let work_item = match self.get_work_item_raw(id, "").await {
+        let path = format!("wit/workitems/{}", id);
+
+        // First, try to get the work item with its relations (attachments and links)
+        let work_item = match self.get_work_item_raw(id, &path, "$expand=Relations").await {
             Ok(item) => item,
+            // Retrying can't fix a missing work item or a rejected token
+            Err(e) if e.downcast_ref::<ApiError>().is_some() => return Err(e),
             Err(_) => {
-                // If that fails, try with expand
-                self.get_work_item_raw(id, "$expand=Relations").await?
+                // If that fails, try without expand
+                self.get_work_item_raw(id, &path, "").await?
             }
         };
 
-        // Convert to our internal model
-        let mut result_work_item = WorkItem::from(work_item.clone());
+        self.hydrate_work_item(work_item).await
+    }
 
-        // Extract attachments from relations
-        if let Some(relations) = work_item.relations {
-            result_work_item.attachments = self.extract_attachments(relations).await?;
+    /// Fetch the chain of parents above `work_item`, nearest first
+    ///
+    /// Only fields are read: no attachments, images or comments. The walk stops
+    /// at the root, after `MAX_PARENT_DEPTH` levels, on a cycle, or at the first
+    /// parent that can't be fetched (with a warning) and returns what it has.
+    pub async fn get_parent_chain(&self, work_item: &WorkItem) -> Vec<ParentWorkItem> {
+        let mut parents = Vec::new();
+        let mut seen = HashSet::from([work_item.id]);
+        let mut next = work_item.links.iter().find(|link| link.is_parent()).map(|link| link.id);
+
+        while let Some(parent_id) = next {
+            if parents.len() >= MAX_PARENT_DEPTH {
+                warn!("Stopped following parents of #{} after {} levels", work_item.id, MAX_PARENT_DEPTH);
+                break;
+            }
+            if !seen.insert(parent_id) {
+                warn!("Parent links of #{} loop back to #{}; stopping there", work_item.id, parent_id);
+                break;
+            }
+
+            let path = format!("wit/workitems/{}", parent_id);
+            let response = match self.get_work_item_raw(parent_id, &path, "$expand=Relations").await {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("Could not fetch parent work item #{}: {}", parent_id, e);
+                    break;
+                }
+            };
+
+            next = response
+                .relations
+                .iter()
+                .flatten()
+                .filter_map(WorkItemLink::from_relation)
+                .find(|link| link.is_parent())
+                .map(|link| link.id);
+
+            let parent = WorkItem::from(response);
+            debug!("Fetched parent #{} ({}) of #{}", parent.id, parent.work_item_type, work_item.id);
+            parents.push(ParentWorkItem {
+                id: parent.id,
+                title: parent.title,
+                work_item_type: parent.work_item_type,
+                description: parent.description,
+            });
         }
 
-        // Extract and download images from description
-        result_work_item.images = self.extract_and_download_images(&result_work_item.description, id).await?;
+        parents
+    }
 
-        // Get comments
-        result_work_item.comments = self.get_work_item_comments(id).await?;
+    /// Add a comment to a work item
+    ///
+    /// Needs a PAT with Work Items (Read & write) scope; a rejected token comes
+    /// back as `ApiError::AccessDenied`. The request is sent once, since retrying
+    /// a POST could add the comment twice.
+    pub async fn add_comment(&self, id: u32, text: &str) -> Result<()> {
+        // The comments API is only available as a preview version
+        let api_version = if self.api_version.contains("preview") {
+            self.api_version.clone()
+        } else {
+            format!("{}-preview", self.api_version)
+        };
+        let url = format!("{}/{}/_apis/wit/workItems/{}/comments?api-version={}",
+            self.organization_url(), self.project, id, api_version);
+
+        debug!("Adding comment to work item {}: {}", id, url);
+
+        self.rate_limiter.acquire().await;
+        let mut request = self.client
+            .post(&url)
+            .header("Authorization", self.authorization_header())
+            .header("Accept", "application/json")
+            .json(&serde_json::json!({ "text": text }));
+        if let Some(timeout) = self.request_timeout {
+            request = request.timeout(timeout);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Azure DevOps API: {}", e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            info!("Added comment to work item {}", id);
+            return Ok(());
+        }
 
-        info!("Successfully fetched work item {} with {} attachments and {} comments",
-              id, result_work_item.attachments.len(), result_work_item.comments.len());
+        let body = response.text().await.unwrap_or_default();
+        let details = format!("HTTP {}: {} ({})", status, body, url);
+        Err(match status.as_u16() {
+            404 => ApiError::WorkItemNotFound { id, details }.into(),
+            401 | 403 => ApiError::AccessDenied { id, status: status.as_u16(), details }.into(),
+            _ => anyhow!("Failed to add comment: {}", details),
+        })
+    }
 
-        Ok(result_work_item)
+    /// Fetch a work item as it was at revision `rev`
+    ///
+    /// Fields and relations come from that revision; comments and embedded
+    /// images are still fetched as they are now.
+    pub async fn get_work_item_revision(&self, id: u32, rev: u32) -> Result<WorkItem> {
+        info!("Fetching revision {} of work item {} from Azure DevOps", rev, id);
+
+        let work_item = self
+            .get_work_item_raw(id, &format!("wit/workItems/{}/revisions/{}", id, rev), "$expand=Relations")
+            .await?;
+
+        self.hydrate_work_item(work_item).await
     }
 
-    async fn get_work_item_raw(&self, id: u32, expand: &str) -> Result<AzureWorkItemResponse> {
-        let url = if expand.is_empty() {
-            format!(
-                "https://dev.azure.com/{}/_apis/wit/workitems/{}?api-version=7.1",
-                self.organization, id
-            )
+    /// Fetch multiple work items, using the batch endpoint where possible
+    ///
+    /// Each requested ID is paired with its own result so that one bad ID
+    /// doesn't abort the whole run. IDs missing from the batch response (or
+    /// all IDs, if the batch request itself fails) fall back to sequential fetches.
+    pub async fn get_work_items(&self, ids: &[u32]) -> Vec<(u32, Result<WorkItem>)> {
+        info!("Fetching {} work items from Azure DevOps", ids.len());
+
+        let mut raw_items: HashMap<u32, AzureWorkItemResponse> = if ids.len() > 1 {
+            match self.get_work_items_batch_raw(ids).await {
+                Ok(items) => items.into_iter().map(|item| (item.id, item)).collect(),
+                Err(e) => {
+                    debug!("Batch fetch failed, falling back to sequential fetches: {}", e);
+                    HashMap::new()
+                }
+            }
         } else {
-            format!(
-                "https://dev.azure.com/{}/_apis/wit/workitems/{}?api-version=7.1&{}",
-                self.organization, id, expand
-            )
+            HashMap::new()
         };
 
-        debug!("Making request to: {}", url);
+        let mut results = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let result = match raw_items.remove(&id) {
+                Some(raw_item) => self.hydrate_work_item(raw_item).await,
+                None => self.get_work_item(id).await,
+            };
 
-        for attempt in 1..=MAX_RETRIES {
-            let response = match self
-                .client
-                .get(&url)
-                .header("Authorization", format!("Basic {}", self.encode_pat()))
-                .header("Accept", "application/json")
-                .send()
-                .await {
-                    Ok(resp) => resp,
-                    Err(e) => {
-                        debug!("Attempt {}/{} failed to connect to Azure DevOps API: {}", attempt, MAX_RETRIES, e);
-                        if attempt < MAX_RETRIES {
-                            tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                            continue;
-                        }
-                        error!("Failed to connect to Azure DevOps API after {} attempts: {}", MAX_RETRIES, e);
-                        return Err(anyhow!("Failed to connect to Azure DevOps API: {}. Check your network connection and organization URL.", e));
-                    }
-                };
+            if let Err(e) = &result {
+                error!("Failed to fetch work item {}: {}", id, e);
+            }
+            results.push((id, result));
+        }
+
+        results
+    }
+
+    /// Run an ad-hoc WIQL query and return the matching work item IDs
+    pub async fn run_query(&self, wiql: &str) -> Result<Vec<u32>> {
+        let url = self.project_api_url("wit/wiql");
+
+        debug!("Running WIQL query against: {}", url);
+
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .post(&url)
+                    .header("Authorization", self.authorization_header())
+                    .header("Accept", "application/json")
+                    .json(&serde_json::json!({ "query": wiql }))
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Azure DevOps API: {}", e))?;
+
+        Self::parse_query_response(response, &url).await
+    }
+
+    /// Run a saved query by its ID (GUID) and return the matching work item IDs
+    pub async fn run_saved_query(&self, query_id: &str) -> Result<Vec<u32>> {
+        let url = self.project_api_url(&format!("wit/wiql/{}", query_id));
+
+        debug!("Running saved query against: {}", url);
+
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .get(&url)
+                    .header("Authorization", self.authorization_header())
+                    .header("Accept", "application/json")
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Azure DevOps API: {}", e))?;
+
+        Self::parse_query_response(response, &url).await
+    }
+
+    async fn parse_query_response(response: reqwest::Response, url: &str) -> Result<Vec<u32>> {
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Query failed: HTTP {} - {} (URL: {})", status, error_text, url));
+        }
+
+        let query: AzureWiqlResponse = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse WIQL query JSON: {}", e))?;
+
+        // Link queries return source/target pairs; keep each ID once, in result order
+        let mut seen = std::collections::HashSet::new();
+        let mut ids: Vec<u32> = Vec::new();
+        let linked_ids = query
+            .work_item_relations
+            .into_iter()
+            .flat_map(|relation| [relation.source, relation.target])
+            .flatten();
+
+        for reference in query.work_items.into_iter().chain(linked_ids) {
+            if seen.insert(reference.id) {
+                ids.push(reference.id);
+            }
+        }
+
+        info!("Query returned {} work items", ids.len());
+        Ok(ids)
+    }
+
+    async fn get_work_items_batch_raw(&self, ids: &[u32]) -> Result<Vec<AzureWorkItemResponse>> {
+        let url = self.api_url("wit/workitemsbatch");
+
+        debug!("Making batch request for {} work items to: {}", ids.len(), url);
+
+        // "omit" returns null entries for IDs that can't be read instead of failing the batch
+        let body = serde_json::json!({
+            "ids": ids,
+            "$expand": "Relations",
+            "errorPolicy": "omit"
+        });
+
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .post(&url)
+                    .header("Authorization", self.authorization_header())
+                    .header("Accept", "application/json")
+                    .json(&body)
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Azure DevOps API: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("Batch request failed: HTTP {} (URL: {})", status, url));
+        }
+
+        let batch: AzureWorkItemBatchResponse = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse work item batch JSON: {}", e))?;
+
+        batch.value
+            .into_iter()
+            .flatten()
+            .map(|value| self.parse_work_item(value))
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| anyhow!("Failed to parse work item batch JSON: {}", e))
+    }
+
+    /// Parse a work item response, keeping the raw JSON when raw responses are saved
+    fn parse_work_item(&self, value: serde_json::Value) -> Result<AzureWorkItemResponse> {
+        let raw = self.save_raw_responses.then(|| value.clone());
+        let mut work_item: AzureWorkItemResponse = serde_json::from_value(value)?;
+        work_item.raw = raw;
+        Ok(work_item)
+    }
+
+    /// Write an untouched API response to `{ticket_dir}/raw/{name}`; failures only warn
+    fn save_raw_response(&self, ticket_dir: &str, name: &str, value: &serde_json::Value) {
+        let raw_dir = format!("{}/raw", ticket_dir);
+        let result = std::fs::create_dir_all(&raw_dir)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(serde_json::to_string_pretty(value)?))
+            .and_then(|json| Ok(std::fs::write(format!("{}/{}", raw_dir, name), json)?));
+
+        match result {
+            Ok(()) => debug!("Saved raw response to {}/{}", raw_dir, name),
+            Err(e) => warn!("Could not save raw response {}/{}: {}", raw_dir, name, e),
+        }
+    }
+
+    /// Current revision number of each work item, without fetching fields or relations
+    ///
+    /// Cheap enough to call before a full fetch to see whether a saved copy is
+    /// still current. Work items that can't be read are missing from the result.
+    pub async fn get_revisions(&self, ids: &[u32]) -> Result<HashMap<u32, u32>> {
+        let url = self.api_url("wit/workitemsbatch");
+        let mut revisions = HashMap::new();
+
+        for chunk in ids.chunks(MAX_BATCH_SIZE) {
+            let body = serde_json::json!({
+                "ids": chunk,
+                "fields": ["System.Id"],
+                "errorPolicy": "omit"
+            });
+
+            let response = self
+                .send_with_retry(|| {
+                    self.client
+                        .post(&url)
+                        .header("Authorization", self.authorization_header())
+                        .header("Accept", "application/json")
+                        .json(&body)
+                })
+                .await
+                .map_err(|e| anyhow!("Failed to connect to Azure DevOps API: {}", e))?;
 
             let status = response.status();
             if !status.is_success() {
-                let error_text = response.text().await.unwrap_or_else(|_| "Unable to read error response".to_string());
-                let error_message = if error_text.is_empty() {
-                    format!("HTTP {} {} (URL: {})", status.as_u16(), status.canonical_reason().unwrap_or("Unknown Error"), url)
-                } else {
-                    format!("HTTP {} - {} (URL: {})", status, error_text, url)
-                };
+                return Err(anyhow!("Revision check failed: HTTP {} (URL: {})", status, url));
+            }
 
-                debug!("Attempt {}/{} got error response: {}", attempt, MAX_RETRIES, error_message);
-                if attempt < MAX_RETRIES {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                    continue;
-                }
+            let batch: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| anyhow!("Failed to parse work item batch JSON: {}", e))?;
 
-                error!("Azure DevOps API error after {} attempts: {}", MAX_RETRIES, error_message);
-                return Err(anyhow!("Failed to fetch work item: {}", error_message));
+            for item in batch["value"].as_array().into_iter().flatten() {
+                if let (Some(id), Some(rev)) = (item["id"].as_u64(), item["rev"].as_u64()) {
+                    revisions.insert(id as u32, rev as u32);
+                }
             }
+        }
+
+        Ok(revisions)
+    }
+
+    /// Convert a raw API response into a `WorkItem`, downloading attachments,
+    /// images and comments along the way
+    async fn hydrate_work_item(&self, work_item: AzureWorkItemResponse) -> Result<WorkItem> {
+        let id = work_item.id;
+
+        // Convert to our internal model
+        let mut result_work_item = WorkItem::from(work_item.clone());
 
-            match response.json().await {
-                Ok(work_item) => return Ok(work_item),
+        // Capture configured custom fields, silently skipping any the work item doesn't have
+        result_work_item.custom_fields = self.custom_fields
+            .iter()
+            .filter_map(|name| work_item.fields.get(name).map(|value| (name.clone(), value.clone())))
+            .collect();
+
+        (result_work_item.iteration_start, result_work_item.iteration_end) =
+            self.get_iteration_dates(&result_work_item.iteration_path).await;
+
+        let ticket_dir = self.ticket_dir(&result_work_item);
+        if let Some(raw) = &work_item.raw {
+            self.save_raw_response(&ticket_dir, "workitem.json", raw);
+        }
+
+        // Extract work item links and attachments from relations
+        if let Some(relations) = work_item.relations {
+            result_work_item.links = relations.iter().filter_map(WorkItemLink::from_relation).collect();
+            result_work_item.pull_requests = relations.iter().filter_map(PullRequestRef::from_relation).collect();
+            result_work_item.attachments = self.extract_attachments(relations, &ticket_dir).await?;
+        }
+
+        // Extract and download images from description
+        let mut saved_images = SavedImages::default();
+        result_work_item.images = self
+            .extract_and_download_images(&result_work_item.description, &ticket_dir, &mut saved_images)
+            .await?;
+
+        // Get comments
+        result_work_item.comments = self.get_work_item_comments(id, &ticket_dir, &mut saved_images).await?;
+
+        info!("Successfully fetched work item {} with {} attachments, {} comments, {} links and {} pull requests",
+              id, result_work_item.attachments.len(), result_work_item.comments.len(), result_work_item.links.len(),
+              result_work_item.pull_requests.len());
+
+        Ok(result_work_item)
+    }
+
+    /// Start and finish dates of an iteration path such as `Project\Release 1\Sprint 5`
+    ///
+    /// Read from the iteration's classification node and cached per path. The
+    /// project's root iteration, iterations without dates and failed lookups
+    /// (logged) all come back as `(None, None)`, so they never fail the fetch.
+    async fn get_iteration_dates(&self, iteration_path: &str) -> IterationDates {
+        if let Some(dates) = self.iteration_dates.lock().await.get(iteration_path) {
+            return *dates;
+        }
+
+        // The first segment is the project itself, which has no dates
+        let node_path: Vec<String> = iteration_path
+            .split('\\')
+            .skip(1)
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| url::form_urlencoded::byte_serialize(segment.as_bytes()).collect::<String>().replace('+', "%20"))
+            .collect();
+        if node_path.is_empty() {
+            return (None, None);
+        }
+
+        let url = self.project_api_url(&format!("wit/classificationnodes/Iterations/{}", node_path.join("/")));
+        debug!("Fetching iteration dates from: {}", url);
+
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .get(&url)
+                    .header("Authorization", self.authorization_header())
+                    .header("Accept", "application/json")
+            })
+            .await;
+        let dates = match response {
+            Ok(response) if response.status().is_success() => match response.json::<AzureClassificationNode>().await {
+                Ok(node) => node
+                    .attributes
+                    .map(|attributes| (attributes.start_date, attributes.finish_date))
+                    .unwrap_or_default(),
                 Err(e) => {
-                    debug!("Attempt {}/{} failed to parse JSON: {}", attempt, MAX_RETRIES, e);
-                    if attempt < MAX_RETRIES {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                        continue;
-                    }
-                    error!("Failed to parse work item JSON after {} attempts: {}", MAX_RETRIES, e);
-                    return Err(anyhow!("Failed to parse work item JSON: {}", e));
+                    warn!("Could not parse iteration '{}': {}", iteration_path, e);
+                    (None, None)
                 }
+            },
+            Ok(response) => {
+                warn!("Could not look up iteration '{}': HTTP {}", iteration_path, response.status());
+                (None, None)
             }
+            Err(e) => {
+                warn!("Could not look up iteration '{}': {}", iteration_path, e);
+                (None, None)
+            }
+        };
+
+        self.iteration_dates.lock().await.insert(iteration_path.to_string(), dates);
+        dates
+    }
+
+    async fn get_work_item_raw(&self, id: u32, path: &str, expand: &str) -> Result<AzureWorkItemResponse> {
+        let url = if expand.is_empty() {
+            self.api_url(path)
+        } else {
+            format!("{}&{}", self.api_url(path), expand)
+        };
+
+        debug!("Making request to: {}", url);
+
+        let response = self
+            .send_with_retry(|| {
+                self.client
+                    .get(&url)
+                    .header("Authorization", self.authorization_header())
+                    .header("Accept", "application/json")
+            })
+            .await
+            .map_err(|e| {
+                error!("Failed to connect to Azure DevOps API: {}", e);
+                anyhow!("Failed to connect to Azure DevOps API: {}. Check your network connection and organization URL.", e)
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unable to read error response".to_string());
+            let error_message = if error_text.is_empty() {
+                format!("HTTP {} {} (URL: {})", status.as_u16(), status.canonical_reason().unwrap_or("Unknown Error"), url)
+            } else {
+                format!("HTTP {} - {} (URL: {})", status, error_text, url)
+            };
+
+            debug!("Azure DevOps API error: {}", error_message);
+            return Err(match status.as_u16() {
+                404 => ApiError::WorkItemNotFound { id, details: error_message }.into(),
+                401 | 403 => ApiError::AccessDenied { id, status: status.as_u16(), details: error_message }.into(),
+                _ => {
+                    error!("Azure DevOps API error: {}", error_message);
+                    anyhow!("Failed to fetch work item: {}", error_message)
+                }
+            });
         }
 
-        unreachable!()
+        let value: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse work item JSON: {}", e))?;
+        self.parse_work_item(value)
+            .map_err(|e| anyhow!("Failed to parse work item JSON: {}", e))
     }
 
-    async fn extract_attachments(&self, relations: Vec<AzureRelation>) -> Result<Vec<Attachment>> {
+    async fn extract_attachments(&self, relations: Vec<AzureRelation>, ticket_dir: &str) -> Result<Vec<Attachment>> {
         let mut attachments = Vec::new();
 
-        for relation in relations {
-            if relation.rel == "AttachedFile" {
-                if let Some(attributes) = relation.attributes {
-                    if let Some(filename) = attributes.name {
-                        match self.download_attachment(&relation.url, &filename).await {
-                            Ok(attachment) => attachments.push(attachment),
-                            Err(e) => {
-                                error!("Failed to download attachment {}: {}", filename, e);
-                                // Continue with other attachments even if one fails
-                            }
-                        }
-                    }
+        let files: Vec<(String, String)> = relations
+            .into_iter()
+            .filter(|relation| relation.rel == "AttachedFile")
+            .filter_map(|relation| {
+                let filename = relation.attributes?.name?;
+                Some((relation.url, filename))
+            })
+            .collect();
+
+        // Show a determinate progress bar when there's more than one file to fetch
+        let progress_bar = match &self.progress {
+            Some(progress) if files.len() > 1 => Some(progress.bar(files.len() as u64, "Downloading attachments")),
+            _ => None,
+        };
+
+        for (url, filename) in files {
+            if let Some(bar) = &progress_bar {
+                bar.set_message(filename.clone());
+            }
+
+            match self.download_attachment(&url, &filename, ticket_dir, progress_bar.as_ref()).await {
+                Ok(attachment) => attachments.push(attachment),
+                Err(e) => {
+                    error!("Failed to download attachment {}: {}", filename, e);
+                    // Continue with other attachments even if one fails
                 }
             }
+
+            if let Some(bar) = &progress_bar {
+                bar.inc(1);
+            }
+        }
+
+        if let Some(bar) = progress_bar {
+            bar.finish_and_clear();
         }
 
         Ok(attachments)
     }
 
-    async fn download_attachment(&self, url: &str, filename: &str) -> Result<Attachment> {
+    /// Download one attachment into `ticket_dir/attachments`, streaming it to disk
+    ///
+    /// The body is written chunk by chunk, so memory use doesn't grow with the
+    /// file size. Progress goes to `batch_bar` when several files are being
+    /// fetched, otherwise large files get a byte progress bar of their own.
+    async fn download_attachment(&self, url: &str, filename: &str, ticket_dir: &str, batch_bar: Option<&ProgressBar>) -> Result<Attachment> {
+        if !self.download_attachments {
+            debug!("Attachment downloads disabled; recording {} only", filename);
+            return Ok(Attachment {
+                id: rand::random::<u32>(),
+                filename: filename.to_string(),
+                original_filename: filename.to_string(),
+                url: url.to_string(),
+                local_path: String::new(),
+                content_type: "application/octet-stream".to_string(),
+                size: 0,
+                created_date: chrono::Utc::now(),
+                skipped: true,
+                skip_reason: Some("attachment downloads disabled".to_string()),
+            });
+        }
+
         debug!("Downloading attachment: {} from {}", filename, url);
 
-        for attempt in 1..=MAX_RETRIES {
-            let response = match self
-                .client
-                .get(url)
-                .header("Authorization", format!("Basic {}", self.encode_pat()))
-                .send()
-                .await {
-                    Ok(resp) => resp,
-                    Err(e) => {
-                        debug!("Attempt {}/{} failed to download attachment {}: {}", attempt, MAX_RETRIES, filename, e);
-                        if attempt < MAX_RETRIES {
-                            tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                            continue;
-                        }
-                        return Err(anyhow!("Failed to download attachment: {}", e));
-                    }
-                };
+        let response = self
+            .download_with_retry(|| {
+                self.client
+                    .get(url)
+                    .header("Authorization", self.authorization_header())
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to download attachment: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to download attachment: {}", response.status()));
+        }
+
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        // Azure often stores attachments without an extension; add one from the content type
+        let saved_filename = with_extension_for_content_type(filename, &content_type);
+        if saved_filename != filename {
+            debug!("Saving attachment {} as {} ({})", filename, saved_filename, content_type);
+        }
+
+        let size = response
+            .headers()
+            .get("content-length")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0);
 
-            if !response.status().is_success() {
-                debug!("Attempt {}/{} got error status {} for attachment {}", attempt, MAX_RETRIES, response.status(), filename);
-                if attempt < MAX_RETRIES {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                    continue;
+        // Skip filtered or oversized files up front, before reading the body
+        let skip_reason = if !self.is_content_type_allowed(&content_type) {
+            Some(format!("content type {} is not allowed", content_type))
+        } else {
+            match self.max_attachment_size {
+                Some(max_size) if size > max_size => {
+                    Some(format!("exceeds max_attachment_size_bytes ({} bytes)", max_size))
                 }
-                return Err(anyhow!("Failed to download attachment: {}", response.status()));
+                _ => None,
             }
+        };
 
-            let content_type = response
-                .headers()
-                .get("content-type")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("application/octet-stream")
-                .to_string();
-
-            let size = response
-                .headers()
-                .get("content-length")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|v| v.parse::<u64>().ok())
-                .unwrap_or(0);
-
-            // Create local file path
-            let local_path = format!("X:/.OTCX/Tickets/temp/attachments/{}", filename);
-
-            // Ensure directory exists
-            std::fs::create_dir_all("X:/.OTCX/Tickets/temp/attachments")?;
-
-            // Download the file content
-            match response.bytes().await {
-                Ok(content) => {
-                    std::fs::write(&local_path, content)?;
-                    return Ok(Attachment {
-                        id: rand::random::<u32>(),
-                        filename: filename.to_string(),
-                        url: url.to_string(),
-                        local_path,
-                        content_type,
-                        size,
-                        created_date: chrono::Utc::now(),
-                    });
-                }
-                Err(e) => {
-                    debug!("Attempt {}/{} failed to read bytes for attachment {}: {}", attempt, MAX_RETRIES, filename, e);
-                    if attempt < MAX_RETRIES {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                        continue;
-                    }
-                    return Err(anyhow!("Failed to read attachment bytes: {}", e));
-                }
+        if let Some(reason) = skip_reason {
+            info!("Skipping attachment {} ({}); recorded in manifest only", filename, reason);
+            return Ok(Attachment {
+                id: rand::random::<u32>(),
+                filename: saved_filename,
+                original_filename: filename.to_string(),
+                url: url.to_string(),
+                local_path: String::new(),
+                content_type,
+                size,
+                created_date: chrono::Utc::now(),
+                skipped: true,
+                skip_reason: Some(reason),
+            });
+        }
+
+        // Create local file path
+        let attachments_dir = format!("{}/attachments", ticket_dir);
+        let local_path = format!("{}/{}", attachments_dir, saved_filename);
+
+        // Ensure directory exists
+        std::fs::create_dir_all(&attachments_dir)?;
+
+        let file_bar = match &self.progress {
+            Some(progress) if batch_bar.is_none() && size >= DOWNLOAD_PROGRESS_MIN_BYTES => {
+                Some(progress.download_bar(size, &saved_filename))
+            }
+            _ => None,
+        };
+
+        let written = match self.stream_to_file(response, &local_path, |written| {
+            if let Some(bar) = &file_bar {
+                bar.set_position(written);
+            } else if let Some(bar) = batch_bar {
+                bar.set_message(format!("{} ({})", saved_filename, crate::ui::format_file_size(written)));
+            }
+        }).await {
+            Ok(written) => written,
+            Err(e) => {
+                // Don't leave a truncated file behind looking like a finished download
+                let _ = std::fs::remove_file(&local_path);
+                return Err(e);
             }
+        };
+        if let Some(bar) = file_bar {
+            bar.finish_and_clear();
+        }
+
+        Ok(Attachment {
+            id: rand::random::<u32>(),
+            filename: saved_filename,
+            original_filename: filename.to_string(),
+            url: url.to_string(),
+            local_path,
+            content_type,
+            size: if size > 0 { size } else { written },
+            created_date: chrono::Utc::now(),
+            skipped: false,
+            skip_reason: None,
+        })
+    }
+
+    /// Write a response body to `path` as it arrives, calling `on_progress` with the bytes written so far
+    ///
+    /// Returns the total number of bytes written.
+    async fn stream_to_file(&self, response: reqwest::Response, path: &str, on_progress: impl Fn(u64)) -> Result<u64> {
+        use std::io::Write;
+
+        let mut file = std::fs::File::create(path)
+            .map_err(|e| anyhow!("Failed to create {}: {}", path, e))?;
+        let mut stream = response.bytes_stream();
+        let mut written = 0u64;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| anyhow!("Failed to read attachment bytes: {}", e))?;
+            file.write_all(&chunk)
+                .map_err(|e| anyhow!("Failed to write {}: {}", path, e))?;
+            written += chunk.len() as u64;
+            on_progress(written);
         }
 
-        unreachable!()
+        file.flush()?;
+        Ok(written)
     }
 
-    async fn extract_and_download_images(&self, description: &str, work_item_id: u32) -> Result<Vec<ImageReference>> {
+    async fn extract_and_download_images(
+        &self,
+        description: &str,
+        ticket_dir: &str,
+        saved_images: &mut SavedImages,
+    ) -> Result<Vec<ImageReference>> {
         let mut images = Vec::new();
         let img_regex = regex::Regex::new(r#"<img[^>]+src="([^"]+)"[^>]*(?:alt="([^"]*)")?[^>]*>"#)?;
 
         // Create images directory
-        let images_dir = format!("X:/.OTCX/Tickets/{}/images", work_item_id);
-        std::fs::create_dir_all(&images_dir)?;
+        let images_dir = format!("{}/images", ticket_dir);
+        if self.download_images {
+            std::fs::create_dir_all(&images_dir)?;
+        }
 
         let mut image_counter = 1;
 
@@ -250,19 +1123,36 @@ impl AzureDevOpsClient {
                 let alt_text = alt_text.map(|m| m.as_str().to_string());
 
                 // Only process Azure DevOps URLs
-                if img_url.contains("dev.azure.com") || img_url.contains("visualstudio.com") {
+                if self.is_azure_devops_url(img_url) {
                     let placeholder = format!("image{:03}.png", image_counter);
                     let local_path = format!("{}/{}", images_dir, placeholder);
 
-                    match self.download_image(img_url, &local_path).await {
-                        Ok(_) => {
+                    if !self.download_images || self.image_exceeds_max_size(img_url).await {
+                        // Record the reference only; the description keeps the original URL
+                        images.push(ImageReference {
+                            placeholder,
+                            original_url: img_url.to_string(),
+                            local_path: String::new(),
+                            width: None,
+                            height: None,
+                            alt_text,
+                            alias_of: None,
+                        });
+                        image_counter += 1;
+                        continue;
+                    }
+
+                    match self.download_image_once(img_url, &local_path, &placeholder, saved_images).await {
+                        Ok((local_path, alias_of)) => {
+                            let (width, height) = Self::read_image_dimensions(&local_path);
                             images.push(ImageReference {
                                 placeholder: placeholder.clone(),
                                 original_url: img_url.to_string(),
                                 local_path,
-                                width: None,
-                                height: None,
+                                width,
+                                height,
                                 alt_text,
+                                alias_of,
                             });
                             image_counter += 1;
                         }
@@ -277,157 +1167,260 @@ impl AzureDevOpsClient {
         Ok(images)
     }
 
-    async fn download_image(&self, url: &str, local_path: &str) -> Result<()> {
-        debug!("Downloading image: {} to {}", url, local_path);
+    /// Download an image unless the same one was already saved for this work item
+    ///
+    /// Repeated URLs are not fetched again, and a download whose content matches
+    /// an earlier image is discarded. Returns the local path to reference and,
+    /// for duplicates, the path (relative to the `images` folder) of the saved
+    /// image this one aliases.
+    async fn download_image_once(
+        &self,
+        url: &str,
+        local_path: &str,
+        relative_path: &str,
+        saved_images: &mut SavedImages,
+    ) -> Result<(String, Option<String>)> {
+        if let Some(saved) = saved_images.by_url.get(url) {
+            debug!("Image {} already saved as {}", url, saved.relative_path);
+            return Ok((saved.local_path.clone(), Some(saved.relative_path.clone())));
+        }
 
-        for attempt in 1..=MAX_RETRIES {
-            let response = match self
-                .client
-                .get(url)
-                .header("Authorization", format!("Basic {}", self.encode_pat()))
-                .send()
-                .await {
-                    Ok(resp) => resp,
-                    Err(e) => {
-                        debug!("Attempt {}/{} failed to download image from {}: {}", attempt, MAX_RETRIES, url, e);
-                        if attempt < MAX_RETRIES {
-                            tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                            continue;
-                        }
-                        return Err(anyhow!("Failed to download image: {}", e));
-                    }
+        let content = self.download_image(url).await?;
+        let hash = format!("{:x}", Sha256::digest(&content));
+
+        let (saved, alias_of) = match saved_images.by_hash.get(&hash) {
+            Some(saved) => {
+                debug!("Image {} is identical to {}, not saving a copy", url, saved.relative_path);
+                (saved.clone(), Some(saved.relative_path.clone()))
+            }
+            None => {
+                std::fs::write(local_path, &content)?;
+                let saved = SavedImage {
+                    local_path: local_path.to_string(),
+                    relative_path: relative_path.to_string(),
                 };
+                saved_images.by_hash.insert(hash, saved.clone());
+                (saved, None)
+            }
+        };
 
-            if !response.status().is_success() {
-                debug!("Attempt {}/{} got error status {} for image {}", attempt, MAX_RETRIES, response.status(), url);
-                if attempt < MAX_RETRIES {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                    continue;
-                }
-                return Err(anyhow!("Failed to download image: {}", response.status()));
+        saved_images.by_url.insert(url.to_string(), saved.clone());
+        Ok((saved.local_path, alias_of))
+    }
+
+    /// Whether a HEAD request reports the image as larger than `max_image_size_bytes`
+    ///
+    /// Any doubt means download it: no limit set, HEAD not supported, or no `content-length`.
+    async fn image_exceeds_max_size(&self, url: &str) -> bool {
+        let Some(max_size) = self.max_image_size else {
+            return false;
+        };
+
+        let response = match self
+            .send_with_retry(|| {
+                self.client
+                    .head(url)
+                    .header("Authorization", self.authorization_header())
+            })
+            .await
+        {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                debug!("HEAD {} returned {}; downloading the image anyway", url, response.status());
+                return false;
+            }
+            Err(e) => {
+                debug!("HEAD {} failed ({}); downloading the image anyway", url, e);
+                return false;
             }
+        };
 
-            match response.bytes().await {
-                Ok(content) => {
-                    std::fs::write(local_path, content)?;
-                    return Ok(());
-                }
-                Err(e) => {
-                    debug!("Attempt {}/{} failed to read bytes for image {}: {}", attempt, MAX_RETRIES, url, e);
-                    if attempt < MAX_RETRIES {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                        continue;
-                    }
-                    return Err(anyhow!("Failed to read image bytes: {}", e));
-                }
+        let size = response
+            .headers()
+            .get("content-length")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+
+        match size {
+            Some(size) if size > max_size => {
+                info!("Skipping image {} ({} bytes exceeds max_image_size_bytes = {}); keeping the original URL",
+                    url, size, max_size);
+                true
             }
+            _ => false,
         }
+    }
+
+    async fn download_image(&self, url: &str) -> Result<Vec<u8>> {
+        debug!("Downloading image: {}", url);
+
+        let response = self
+            .download_with_retry(|| {
+                self.client
+                    .get(url)
+                    .header("Authorization", self.authorization_header())
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to download image: {}", e))?;
 
-        unreachable!()
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to download image: {}", response.status()));
+        }
+
+        let content = response
+            .bytes()
+            .await
+            .map_err(|e| anyhow!("Failed to read image bytes: {}", e))?;
+
+        Ok(content.to_vec())
     }
 
-    async fn get_work_item_comments(&self, work_item_id: u32) -> Result<Vec<Comment>> {
+    /// Decode just the image header to get its dimensions; `None` for formats we can't read
+    fn read_image_dimensions(path: &str) -> (Option<u32>, Option<u32>) {
+        match image::image_dimensions(path) {
+            Ok((width, height)) => (Some(width), Some(height)),
+            Err(e) => {
+                debug!("Could not read image dimensions for {}: {}", path, e);
+                (None, None)
+            }
+        }
+    }
+
+    async fn get_work_item_comments(&self, work_item_id: u32, ticket_dir: &str, saved_images: &mut SavedImages) -> Result<Vec<Comment>> {
+        if !self.fetch_comments {
+            debug!("Comment fetching disabled, skipping comments for work item {}", work_item_id);
+            return Ok(Vec::new());
+        }
+
         info!("Fetching comments for work item {}", work_item_id);
 
-        let url = format!(
-            "https://dev.azure.com/{}/_apis/wit/workItems/{}/comments?api-version=7.1",
-            self.organization, work_item_id
-        );
+        let base_url = self.api_url(&format!("wit/workItems/{}/comments", work_item_id));
+
+        // Follow continuation tokens until every page of comments has been fetched
+        let mut azure_comments = Vec::new();
+        let mut raw_pages = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let url = match &continuation_token {
+                Some(token) => format!("{}&continuationToken={}",
+                    base_url, url::form_urlencoded::byte_serialize(token.as_bytes()).collect::<String>()),
+                None => base_url.clone(),
+            };
 
-        for attempt in 1..=MAX_RETRIES {
             let response = match self
-                .client
-                .get(&url)
-                .header("Authorization", format!("Basic {}", self.encode_pat()))
-                .header("Accept", "application/json")
-                .send()
+                .send_with_retry(|| {
+                    self.client
+                        .get(&url)
+                        .header("Authorization", self.authorization_header())
+                        .header("Accept", "application/json")
+                })
                 .await {
-                    Ok(resp) => resp,
-                    Err(e) => {
-                        debug!("Attempt {}/{} failed to fetch comments for work item {}: {}", attempt, MAX_RETRIES, work_item_id, e);
-                        if attempt < MAX_RETRIES {
-                            tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                            continue;
-                        }
+                    Ok(resp) if resp.status().is_success() => resp,
+                    Ok(resp) => {
                         // Comments might not be available for all work items
-                        debug!("No comments available for work item {} or insufficient permissions", work_item_id);
-                        return Ok(Vec::new());
+                        debug!("Got error status {} for comments on work item {} - insufficient permissions?", resp.status(), work_item_id);
+                        break;
+                    }
+                    Err(e) => {
+                        debug!("Failed to fetch comments for work item {}: {}", work_item_id, e);
+                        break;
                     }
                 };
 
-            if !response.status().is_success() {
-                debug!("Attempt {}/{} got error status {} for comments", attempt, MAX_RETRIES, response.status());
-                if attempt < MAX_RETRIES {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                    continue;
-                }
-                // Comments might not be available for all work items
-                debug!("No comments available for work item {} or insufficient permissions", work_item_id);
-                return Ok(Vec::new());
-            }
-
-            match response.json::<AzureCommentsResponse>().await {
-                Ok(comments_response) => {
-                    let mut comments = Vec::new();
-
-                    for azure_comment in comments_response.value {
-                        let created_date = azure_comment.created_date
-                            .parse::<DateTime<Utc>>()
-                            .unwrap_or_else(|_| Utc::now());
-
-                        let updated_date = azure_comment.updated_date
-                            .and_then(|date| date.parse::<DateTime<Utc>>().ok());
-
-                        let author = User {
-                            display_name: azure_comment.author.displayName.clone(),
-                            email: azure_comment.author.url.clone(), // This might need extraction
-                            url: azure_comment.author.url,
-                        };
-
-                        // Extract images from comment text
-                        let comment_images = self.extract_and_download_images_from_text(
-                            &azure_comment.text,
-                            work_item_id,
-                            &format!("comment_{}", azure_comment.id)
-                        ).await.unwrap_or_default();
-
-                        comments.push(Comment {
-                            id: azure_comment.id,
-                            author,
-                            created_date,
-                            updated_date,
-                            text: azure_comment.text,
-                            images: comment_images,
-                        });
+            let page = match response.json::<serde_json::Value>().await {
+                Ok(value) => {
+                    if self.save_raw_responses {
+                        raw_pages.push(value.clone());
                     }
-
-                    return Ok(comments);
+                    serde_json::from_value::<AzureCommentsResponse>(value).map_err(anyhow::Error::from)
                 }
+                Err(e) => Err(anyhow::Error::from(e)),
+            };
+            let page = match page {
+                Ok(page) => page,
                 Err(e) => {
-                    debug!("Attempt {}/{} failed to parse comments JSON: {}", attempt, MAX_RETRIES, e);
-                    if attempt < MAX_RETRIES {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(RETRY_DELAY_MS * attempt as u64)).await;
-                        continue;
-                    }
-                    return Ok(Vec::new());
+                    debug!("Failed to parse comments JSON: {}", e);
+                    break;
                 }
+            };
+
+            azure_comments.extend(page.value);
+
+            match page.continuation_token.filter(|token| !token.is_empty()) {
+                Some(token) => continuation_token = Some(token),
+                None => break,
             }
         }
 
-        unreachable!()
+        debug!("Fetched {} comments in total for work item {}", azure_comments.len(), work_item_id);
+
+        // One entry per page of comments, exactly as returned
+        if !raw_pages.is_empty() {
+            self.save_raw_response(ticket_dir, "comments.json", &serde_json::Value::Array(raw_pages));
+        }
+
+        let mut comments = Vec::new();
+
+        for azure_comment in azure_comments {
+            let created_date = azure_comment.created_date
+                .parse::<DateTime<Utc>>()
+                .unwrap_or_else(|_| Utc::now());
+
+            if self.comments_since.is_some_and(|since| created_date < since) {
+                debug!("Skipping comment {} from {} (before --since)", azure_comment.id, created_date);
+                continue;
+            }
+
+            let updated_date = azure_comment.updated_date
+                .and_then(|date| date.parse::<DateTime<Utc>>().ok());
+
+            // Only sign-in names that look like an address are emails; leave it empty otherwise
+            let email = azure_comment.author.unique_name
+                .filter(|name| name.contains('@'))
+                .unwrap_or_default();
+
+            let author = User {
+                display_name: azure_comment.author.displayName.clone(),
+                email,
+                url: azure_comment.author.url,
+            };
+
+            // Extract images from comment text
+            let comment_images = self.extract_and_download_images_from_text(
+                &azure_comment.text,
+                ticket_dir,
+                &format!("comment_{}", azure_comment.id),
+                saved_images,
+            ).await.unwrap_or_default();
+
+            comments.push(Comment {
+                id: azure_comment.id,
+                author,
+                created_date,
+                updated_date,
+                text: azure_comment.text,
+                images: comment_images,
+            });
+        }
+
+        Ok(comments)
     }
 
     async fn extract_and_download_images_from_text(
         &self,
         text: &str,
-        work_item_id: u32,
-        context: &str
+        ticket_dir: &str,
+        context: &str,
+        saved_images: &mut SavedImages,
     ) -> Result<Vec<ImageReference>> {
         let mut images = Vec::new();
         let img_regex = regex::Regex::new(r#"<img[^>]+src="([^"]+)"[^>]*(?:alt="([^"]*)")?[^>]*>"#)?;
 
-        let images_dir = format!("X:/.OTCX/Tickets/{}/images/{}", work_item_id, context);
-        std::fs::create_dir_all(&images_dir)?;
+        let images_dir = format!("{}/images/{}", ticket_dir, context);
+        if self.download_images {
+            std::fs::create_dir_all(&images_dir)?;
+        }
 
         let mut image_counter = 1;
 
@@ -436,19 +1429,36 @@ impl AzureDevOpsClient {
                 let img_url = img_url_match.as_str();
                 let alt_text = alt_text.map(|m| m.as_str().to_string());
 
-                if img_url.contains("dev.azure.com") || img_url.contains("visualstudio.com") {
+                if self.is_azure_devops_url(img_url) {
                     let placeholder = format!("image{:03}.png", image_counter);
                     let local_path = format!("{}/{}", images_dir, placeholder);
 
-                    match self.download_image(img_url, &local_path).await {
-                        Ok(_) => {
+                    if !self.download_images || self.image_exceeds_max_size(img_url).await {
+                        // Record the reference only; the description keeps the original URL
+                        images.push(ImageReference {
+                            placeholder,
+                            original_url: img_url.to_string(),
+                            local_path: String::new(),
+                            width: None,
+                            height: None,
+                            alt_text,
+                            alias_of: None,
+                        });
+                        image_counter += 1;
+                        continue;
+                    }
+
+                    match self.download_image_once(img_url, &local_path, &format!("{}/{}", context, placeholder), saved_images).await {
+                        Ok((local_path, alias_of)) => {
+                            let (width, height) = Self::read_image_dimensions(&local_path);
                             images.push(ImageReference {
                                 placeholder: placeholder.clone(),
                                 original_url: img_url.to_string(),
                                 local_path,
-                                width: None,
-                                height: None,
+                                width,
+                                height,
                                 alt_text,
+                                alias_of,
                             });
                             image_counter += 1;
                         }
@@ -463,8 +1473,279 @@ impl AzureDevOpsClient {
         Ok(images)
     }
 
-    fn encode_pat(&self) -> String {
+    fn is_content_type_allowed(&self, content_type: &str) -> bool {
+        // Ignore parameters such as "; charset=utf-8"
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+
+        let matches = |pattern: &String| {
+            let pattern = pattern.trim().to_lowercase();
+            let pattern = pattern.trim_end_matches('*');
+            if pattern.ends_with('/') {
+                mime.starts_with(pattern)
+            } else {
+                mime == pattern
+            }
+        };
+
+        (self.allowed_content_types.is_empty() || self.allowed_content_types.iter().any(matches))
+            && !self.blocked_content_types.iter().any(matches)
+    }
+
+    /// Root URL of the organization (or on-prem collection)
+    ///
+    /// The organization segment is only appended when `base_url` doesn't already
+    /// end with it, so both `https://tfs.example.com/tfs` and
+    /// `https://tfs.example.com/tfs/DefaultCollection` work for a collection
+    /// named `DefaultCollection`.
+    fn organization_url(&self) -> String {
+        let last_segment = self.base_url.rsplit('/').next().unwrap_or("");
+        if self.organization.is_empty() || last_segment.eq_ignore_ascii_case(&self.organization) {
+            self.base_url.clone()
+        } else {
+            format!("{}/{}", self.base_url, self.organization)
+        }
+    }
+
+    /// Build an organization-scoped REST URL, e.g. `api_url("wit/workitems/42")`
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/_apis/{}?api-version={}",
+            self.organization_url(), path.trim_start_matches('/'), self.api_version)
+    }
+
+    /// Build a project-scoped REST URL, e.g. `project_api_url("wit/wiql")`
+    fn project_api_url(&self, path: &str) -> String {
+        format!("{}/{}/_apis/{}?api-version={}",
+            self.organization_url(), self.project, path.trim_start_matches('/'), self.api_version)
+    }
+
+    /// Whether a URL points at Azure DevOps (cloud or the configured server)
+    fn is_azure_devops_url(&self, url: &str) -> bool {
+        let server_host = url::Url::parse(&self.base_url)
+            .ok()
+            .and_then(|base| base.host_str().map(str::to_string));
+
+        url.contains("dev.azure.com")
+            || url.contains("visualstudio.com")
+            || server_host.is_some_and(|host| {
+                url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)) == Some(host)
+            })
+    }
+
+    /// Send a request, retrying transient failures with exponential backoff and jitter
+    ///
+    /// Connection errors, 429 and 502/503/504 are retried up to `max_retries`
+    /// times. Throttled responses wait for the duration requested via
+    /// `Retry-After` / `X-RateLimit-Reset` instead of the usual backoff. Any other
+    /// response is handed back as-is so callers can report non-retryable
+    /// statuses (401, 404, ...) immediately.
+    async fn send_with_retry<F>(&self, build_request: F) -> std::result::Result<reqwest::Response, reqwest::Error>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.send_with_retry_within(self.request_timeout, build_request).await
+    }
+
+    /// Like `send_with_retry`, but with the longer timeout used for attachment and image downloads
+    async fn download_with_retry<F>(&self, build_request: F) -> std::result::Result<reqwest::Response, reqwest::Error>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.send_with_retry_within(self.download_timeout, build_request).await
+    }
+
+    async fn send_with_retry_within<F>(&self, timeout: Option<std::time::Duration>, build_request: F) -> std::result::Result<reqwest::Response, reqwest::Error>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 0;
+
+        loop {
+            self.rate_limiter.acquire().await;
+
+            let request = match timeout {
+                Some(timeout) => build_request().timeout(timeout),
+                None => build_request(),
+            };
+            let delay = match request.send().await {
+                Ok(response) => {
+                    if attempt >= self.max_retries || !Self::is_retryable_response(&response) {
+                        return Ok(response);
+                    }
+
+                    match Self::throttle_delay(&response) {
+                        Some(delay) => {
+                            debug!("Throttled by Azure DevOps (HTTP {}), waiting {}s before retrying {}",
+                                response.status().as_u16(), delay.as_secs(), response.url());
+                            delay
+                        }
+                        None => {
+                            debug!("Attempt {}/{} got status {} from {}, retrying",
+                                attempt + 1, self.max_retries + 1, response.status(), response.url());
+                            Self::backoff_delay(attempt)
+                        }
+                    }
+                }
+                Err(e) => {
+                    let retryable = e.is_connect() || e.is_timeout() || e.is_request();
+                    if attempt >= self.max_retries || !retryable {
+                        return Err(e);
+                    }
+                    debug!("Attempt {}/{} failed: {}, retrying", attempt + 1, self.max_retries + 1, e);
+                    Self::backoff_delay(attempt)
+                }
+            };
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn is_retryable_response(response: &reqwest::Response) -> bool {
+        matches!(response.status().as_u16(), 429 | 502..=504)
+    }
+
+    /// How long the server asked us to wait, from `Retry-After` (seconds or
+    /// HTTP date) or `X-RateLimit-Reset` (Unix timestamp)
+    fn throttle_delay(response: &reqwest::Response) -> Option<std::time::Duration> {
+        let headers = response.headers();
+        let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+        let seconds = header_str("retry-after")
+            .and_then(|value| {
+                value.parse::<u64>().ok().or_else(|| {
+                    DateTime::parse_from_rfc2822(value)
+                        .ok()
+                        .map(|date| (date.with_timezone(&Utc) - Utc::now()).num_seconds().max(0) as u64)
+                })
+            })
+            .or_else(|| {
+                header_str("x-ratelimit-reset")
+                    .and_then(|value| value.parse::<i64>().ok())
+                    .map(|reset| (reset - Utc::now().timestamp()).max(0) as u64)
+            })?;
+
+        Some(std::time::Duration::from_secs(seconds.min(MAX_THROTTLE_WAIT_SECS)))
+    }
+
+    /// Exponential backoff (500ms, 1s, 2s, ...) plus up to one base delay of random jitter
+    fn backoff_delay(attempt: u32) -> std::time::Duration {
+        let exponential = RETRY_DELAY_MS.saturating_mul(1u64 << attempt.min(10));
+        let jitter = rand::random::<u64>() % RETRY_DELAY_MS;
+        std::time::Duration::from_millis(exponential + jitter)
+    }
+
+    /// Value for the `Authorization` header of every request
+    fn authorization_header(&self) -> String {
+        match &self.auth {
+            AzureAuth::Pat(token) => format!("Basic {}", Self::encode_pat(token)),
+            AzureAuth::Bearer(token) => format!("Bearer {}", token),
+        }
+    }
+
+    fn encode_pat(pat_token: &str) -> String {
         use base64::{Engine as _, engine::general_purpose};
-        general_purpose::STANDARD.encode(format!(":{}", self.pat_token))
+        general_purpose::STANDARD.encode(format!(":{}", pat_token))
+    }
+}
+
+/// Append an extension matching `content_type` when `filename` has none
+///
+/// Names that already end in a short alphanumeric extension are kept as-is, as
+/// are files whose content type has no well-known extension.
+fn with_extension_for_content_type(filename: &str, content_type: &str) -> String {
+    let has_extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| (1..=5).contains(&ext.len()) && ext.chars().all(|c| c.is_ascii_alphanumeric()));
+    if has_extension {
+        return filename.to_string();
     }
-}
\ No newline at end of file
+
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    let extension = match mime.as_str() {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/bmp" => "bmp",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "image/tiff" => "tiff",
+        "application/pdf" => "pdf",
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "application/zip" | "application/x-zip-compressed" => "zip",
+        "application/msword" => "doc",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => "docx",
+        "application/vnd.ms-excel" => "xls",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => "xlsx",
+        "application/vnd.ms-powerpoint" => "ppt",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation" => "pptx",
+        "text/plain" => "txt",
+        "text/csv" => "csv",
+        "text/html" => "html",
+        "text/markdown" => "md",
+        "video/mp4" => "mp4",
+        "audio/mpeg" => "mp3",
+        _ => return filename.to_string(),
+    };
+
+    format!("{}.{}", filename.trim_end_matches('.'), extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn downloads_land_under_the_tickets_dir() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/org/_apis/wit/workitems/7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 7,
+                "rev": 1,
+                "url": "",
+                "_links": {},
+                "fields": {
+                    "System.Title": "Crash on save",
+                    "System.Description": format!(r#"<p><img src="{}/org/_apis/wit/attachments/img"></p>"#, server.uri())
+                },
+                "relations": [{
+                    "rel": "AttachedFile",
+                    "url": format!("{}/org/_apis/wit/attachments/log", server.uri()),
+                    "attributes": { "name": "crash.log" }
+                }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/org/_apis/wit/attachments/log"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("stack trace"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/org/_apis/wit/attachments/img"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"not really a png".to_vec()))
+            .mount(&server)
+            .await;
+
+        let tickets_dir = tempfile::tempdir().unwrap();
+        let tickets_path = tickets_dir.path().to_string_lossy().to_string();
+        let client = AzureDevOpsClient::new("org".into(), "Proj".into(), AzureAuth::Pat("test-pat-token".into()), "7.1".into())
+            .unwrap()
+            .with_base_url(&server.uri())
+            .with_tickets_dir(&tickets_path);
+
+        let work_item = client.get_work_item(7).await.unwrap();
+
+        let attachment = &work_item.attachments[0];
+        assert_eq!(attachment.local_path, format!("{}/7/attachments/crash.log", tickets_path));
+        assert_eq!(std::fs::read_to_string(&attachment.local_path).unwrap(), "stack trace");
+
+        let image = &work_item.images[0];
+        assert_eq!(image.local_path, format!("{}/7/images/image001.png", tickets_path));
+        assert!(std::path::Path::new(&image.local_path).is_file());
+    }
+}
@@ -6,6 +6,8 @@
 
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
+use crate::ai::AiProviderKind;
+use crate::ui::ThemePalette;
 
 /// Main configuration structure for Bakery
 ///
@@ -29,15 +31,62 @@ pub struct BakeryConfig {
 /// required to connect to Azure DevOps and retrieve work items.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AzureDevOpsConfig {
+    /// Server root URL (default: "https://dev.azure.com")
+    /// For on-premises Azure DevOps Server use e.g. "https://tfs.mycompany.com/tfs"
+    /// and set `organization` to the collection name
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
     /// Azure DevOps organization name (e.g., "myorg")
     pub organization: String,
     /// Azure DevOps project name (e.g., "MyProject")
     pub project: String,
+    /// How to authenticate: "pat" (default) or "bearer"
+    /// Bearer uses a Microsoft Entra ID access token from the AZURE_DEVOPS_BEARER environment variable
+    #[serde(default)]
+    pub auth_method: AuthMethod,
     /// Personal Access Token for API authentication
     /// Should be treated as sensitive information
     pub pat_token: String,
     /// Azure DevOps REST API version (default: "7.1")
     pub api_version: String,
+    /// Extra field reference names to capture into metadata.json
+    /// (e.g. "Custom.Severity", "Microsoft.VSTS.Common.Priority"); missing fields are skipped
+    #[serde(default)]
+    pub custom_fields: Vec<String>,
+    /// HTTP(S) proxy for all requests, e.g. "http://proxy.corp.local:8080"
+    /// Takes precedence over the HTTPS_PROXY/HTTP_PROXY environment variables; NO_PROXY is still honored
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// PEM file with an extra root certificate to trust, e.g. a private CA for Azure DevOps Server
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// Skip TLS certificate verification entirely (default: false). Insecure; prefer ca_cert_path
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    /// How many times transient request failures are retried (default: 3)
+    /// Connection errors, 502/503/504 and throttling responses are retried with exponential backoff
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Most work items a single `--tickets` range may cover (default: 500); 0 means no limit
+    #[serde(default = "default_max_ticket_range")]
+    pub max_ticket_range: u32,
+    /// Seconds to wait for each API call (default: 30); 0 means no timeout
+    #[serde(default = "default_request_timeout_seconds")]
+    pub request_timeout_seconds: u64,
+    /// Seconds to wait for each attachment or image download (default: 300); 0 means no timeout
+    #[serde(default = "default_download_timeout_seconds")]
+    pub download_timeout_seconds: u64,
+}
+
+/// Authentication method used for Azure DevOps requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMethod {
+    /// Personal Access Token (HTTP Basic auth)
+    #[default]
+    Pat,
+    /// Microsoft Entra ID / OAuth access token (HTTP Bearer auth)
+    Bearer,
 }
 
 /// Configuration for storage and file organization
@@ -54,6 +103,70 @@ pub struct StorageConfig {
     /// Subdirectory name for storing OpenSpec plans
     pub openspec_subdir: String,
 
+    /// Folder for each ticket below the tickets directory (default: "{id}")
+    /// Tokens: {id}, {year}, {month}, {day} (created date) and {type}, e.g. "{year}/{month}/{id}"
+    #[serde(default = "default_ticket_path_template")]
+    pub ticket_path_template: String,
+
+    /// Attachments larger than this many bytes are recorded in the manifest but not downloaded
+    /// Leave unset to download attachments of any size
+    #[serde(default)]
+    pub max_attachment_size_bytes: Option<u64>,
+
+    /// Embedded images larger than this many bytes are listed in the manifest but not downloaded
+    /// The size is checked with a HEAD request; images of unknown size are still downloaded
+    #[serde(default)]
+    pub max_image_size_bytes: Option<u64>,
+
+    /// Content types of attachments to download; empty allows everything
+    /// Entries ending in "/" (e.g. "image/") match every subtype
+    #[serde(default)]
+    pub allowed_content_types: Vec<String>,
+
+    /// Content types of attachments never to download, checked after `allowed_content_types`
+    /// Entries ending in "/" (e.g. "video/") match every subtype
+    #[serde(default)]
+    pub blocked_content_types: Vec<String>,
+
+    /// Download attachment files (default: true)
+    /// When false, attachments are still listed in the manifest (URL, filename) but not fetched
+    #[serde(default = "default_true")]
+    pub download_attachments: bool,
+
+    /// Download embedded images (default: true)
+    /// When false, images are still listed in the manifest and keep their original URLs
+    #[serde(default = "default_true")]
+    pub download_images: bool,
+
+    /// Fetch work item comments (default: true)
+    /// When false, comments are skipped entirely and comments/no-comments.md is written instead
+    #[serde(default = "default_true")]
+    pub fetch_comments: bool,
+
+    /// Also save the untouched HTML as description.html and comments/comment_NNN.html (default: false)
+    /// Useful for debugging how the HTML was cleaned without re-fetching the work item
+    #[serde(default)]
+    pub keep_raw_html: bool,
+
+    /// Also save the untouched API responses as raw/workitem.json and raw/comments.json (default: false)
+    /// Useful for debugging field mappings, e.g. a custom field that doesn't show up
+    #[serde(default)]
+    pub save_raw_response: bool,
+
+    /// Which files to write for each work item (default: all of them)
+    /// Names: description, acceptance_criteria, comments, work_item,
+    /// attachment_manifest, image_manifest, links, pull_requests; metadata.json is always written
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+
+    /// Also write work-item.md with the description, acceptance criteria and comments in one file (default: true)
+    #[serde(default = "default_true")]
+    pub combined_markdown: bool,
+
+    /// File to also write a DEBUG-level log to, rotated daily (optional)
+    #[serde(default)]
+    pub log_file: Option<String>,
+
     /// Local baking mode - creates ticket and openspec folders in current working directory
     /// When enabled, Bakery will create folders in the directory where the command is run
     /// instead of using the base_directory. This is useful for per-project ticket organization.
@@ -66,31 +179,137 @@ pub struct StorageConfig {
 /// Supports multiple AI platforms and customizable command templates.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenSpecConfig {
-    /// Command template for AI plan generation
-    /// Use {prompt} as placeholder for the generated prompt
-    /// Example: "claude -p \"{prompt}\""
+    /// Backend used for plan generation: "claude-cli" (default), "openai" or "ollama"
+    #[serde(default)]
+    pub ai_provider: AiProviderKind,
+    /// Command template for AI plan generation with the "claude-cli" provider
+    /// The prompt is piped to its stdin; a {prompt} placeholder passes it as an argument instead
+    /// Example: "claude -p"
     pub ai_command_template: String,
+    /// Model name for the "openai" and "ollama" providers (defaults: gpt-4o, llama3.1)
+    #[serde(default)]
+    pub ai_model: Option<String>,
+    /// Base URL for the "openai" and "ollama" providers
+    /// (defaults: https://api.openai.com/v1, http://localhost:11434)
+    #[serde(default)]
+    pub ai_api_url: Option<String>,
     /// Whether to automatically generate OpenSpec plans after scraping
     /// Set to false to disable automatic plan generation
     pub auto_generate: bool,
+    /// Work item types to generate plans for, e.g. ["Feature", "User Story"] (case-insensitive)
+    /// Unset generates for every type
+    #[serde(default)]
+    pub auto_generate_types: Option<Vec<String>>,
+    /// Also treat `#1234` mentions in the description as dependencies (default: false)
+    /// Linked Related/Dependency work items are always included
+    #[serde(default)]
+    pub dependencies_from_description: bool,
+    /// Number of most recent comments included in the prompt as discussion context (default: 5)
+    /// Set to 0 to leave comments out of the prompt
+    #[serde(default = "default_prompt_comments")]
+    pub prompt_comments: usize,
+    /// Character budget for the description, acceptance criteria and comments in the prompt (default: 60000)
+    /// Comments are dropped first, then the description is shortened; 0 disables the limit
+    #[serde(default = "default_max_prompt_chars")]
+    pub max_prompt_chars: usize,
+    /// How many times the AI command is re-run when it fails or returns no usable plan (default: 2)
+    #[serde(default = "default_ai_max_retries")]
+    pub ai_max_retries: u32,
+    /// Seconds to wait for the AI command before killing it (default: 600)
+    #[serde(default = "default_ai_timeout_seconds")]
+    pub ai_timeout_seconds: u64,
+    /// Shell command run after each plan is created, with BAKERY_TICKET_ID,
+    /// BAKERY_CHANGE_PATH and BAKERY_TITLE set; a failing hook only warns
+    #[serde(default)]
+    pub post_generate_hook: Option<String>,
+    /// File with a custom prompt to send to the AI instead of the built-in one
+    #[serde(default)]
+    pub prompt_template_path: Option<String>,
     /// Enable rich output mode with maximum visual features by default
     /// Can be overridden with --rich, --compact, or --no-color flags
     #[serde(default = "default_rich_output")]
     pub rich_output: bool,
+    /// Color palette: "dark" (default), "light" or "high-contrast"
+    /// Can be overridden with --theme
+    #[serde(default)]
+    pub theme: ThemePalette,
+}
+
+impl OpenSpecConfig {
+    /// Whether `auto_generate_types` lets plans be generated for this work item type
+    pub fn generates_for_type(&self, work_item_type: &str) -> bool {
+        self.auto_generate_types.as_ref().is_none_or(|types| {
+            types.iter().any(|allowed| allowed.trim().eq_ignore_ascii_case(work_item_type.trim()))
+        })
+    }
 }
 
 fn default_rich_output() -> bool {
     true
 }
 
+fn default_ticket_path_template() -> String {
+    "{id}".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_prompt_comments() -> usize {
+    5
+}
+
+fn default_max_prompt_chars() -> usize {
+    60_000
+}
+
+fn default_ai_max_retries() -> u32 {
+    2
+}
+
+fn default_ai_timeout_seconds() -> u64 {
+    600
+}
+
+fn default_base_url() -> String {
+    "https://dev.azure.com".to_string()
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_max_ticket_range() -> u32 {
+    500
+}
+
+fn default_request_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_download_timeout_seconds() -> u64 {
+    300
+}
+
 impl Default for BakeryConfig {
     fn default() -> Self {
         Self {
             azure_devops: AzureDevOpsConfig {
+                base_url: default_base_url(),
                 organization: "your-organization".to_string(),
                 project: "your-project".to_string(),
+                auth_method: AuthMethod::Pat,
                 pat_token: "your-pat-token-here".to_string(),
                 api_version: "7.1".to_string(),
+                custom_fields: Vec::new(),
+                proxy_url: None,
+                ca_cert_path: None,
+                accept_invalid_certs: false,
+                max_retries: default_max_retries(),
+                max_ticket_range: default_max_ticket_range(),
+                request_timeout_seconds: default_request_timeout_seconds(),
+                download_timeout_seconds: default_download_timeout_seconds(),
             },
             storage: StorageConfig {
                 base_directory: if cfg!(windows) {
@@ -100,12 +319,37 @@ impl Default for BakeryConfig {
                 },
                 tickets_subdir: "Tickets".to_string(),
                 openspec_subdir: "openspec".to_string(),
+                ticket_path_template: default_ticket_path_template(),
+                max_attachment_size_bytes: None,
+                max_image_size_bytes: None,
+                allowed_content_types: Vec::new(),
+                blocked_content_types: Vec::new(),
+                download_attachments: true,
+                download_images: true,
+                fetch_comments: true,
+                keep_raw_html: false,
+                combined_markdown: true,
+                save_raw_response: false,
+                artifacts: Vec::new(),
+                log_file: None,
                 local_baking: false,
             },
             openspec: OpenSpecConfig {
-                ai_command_template: "claude -p \"{prompt}\"".to_string(),
+                ai_provider: AiProviderKind::default(),
+                ai_command_template: "claude -p".to_string(),
+                ai_model: None,
+                ai_api_url: None,
                 auto_generate: true,
+                auto_generate_types: None,
+                dependencies_from_description: false,
+                prompt_comments: default_prompt_comments(),
+                max_prompt_chars: default_max_prompt_chars(),
+                ai_max_retries: default_ai_max_retries(),
+                ai_timeout_seconds: default_ai_timeout_seconds(),
+                post_generate_hook: None,
+                prompt_template_path: None,
                 rich_output: true,
+                theme: ThemePalette::default(),
             },
         }
     }
@@ -113,41 +357,104 @@ impl Default for BakeryConfig {
 
 impl BakeryConfig {
     pub fn get_config_dir() -> Result<String> {
-        let home_dir = if cfg!(windows) {
-            std::env::var("USERPROFILE").map_err(|_| anyhow::anyhow!("USERPROFILE environment variable not found"))?
+        let home_dir = home_dir().ok_or_else(|| if cfg!(windows) {
+            anyhow::anyhow!("USERPROFILE environment variable not found")
         } else {
-            std::env::var("HOME").map_err(|_| anyhow::anyhow!("HOME environment variable not found"))?
+            anyhow::anyhow!("HOME environment variable not found")
+        })?;
+
+        let bakery_dir = std::path::Path::new(&home_dir).join(".bakery");
+        Ok(bakery_dir.to_string_lossy().to_string())
+    }
+
+    /// Path of the config file for `profile`, or the default config when `None`
+    ///
+    /// Profiles live at `~/.bakery/profiles/<name>.toml`.
+    pub fn get_config_path(profile: Option<&str>) -> Result<String> {
+        let config_dir = std::path::PathBuf::from(Self::get_config_dir()?);
+        let config_path = match profile {
+            Some(name) => {
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                    return Err(anyhow::anyhow!("Invalid profile name '{}': use letters, digits, '-' or '_'", name));
+                }
+                Self::get_profiles_dir(&config_dir).join(format!("{}.toml", name))
+            }
+            None => config_dir.join("bakery-config.toml"),
         };
+        Ok(config_path.to_string_lossy().to_string())
+    }
 
-        let bakery_dir = format!("{}\\.bakery", home_dir);
-        Ok(bakery_dir)
+    fn get_profiles_dir(config_dir: &std::path::Path) -> std::path::PathBuf {
+        config_dir.join("profiles")
     }
 
-    pub fn get_config_path() -> Result<String> {
-        let config_dir = Self::get_config_dir()?;
-        Ok(format!("{}\\bakery-config.toml", config_dir))
+    /// Names of all profiles under `~/.bakery/profiles`, sorted
+    pub fn list_profiles() -> Result<Vec<String>> {
+        let profiles_dir = Self::get_profiles_dir(std::path::Path::new(&Self::get_config_dir()?));
+        if !profiles_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut profiles: Vec<String> = std::fs::read_dir(&profiles_dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .collect();
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    /// The profile to use: the `--profile` flag, then the BAKERY_PROFILE environment variable
+    pub fn resolve_profile(cli_profile: Option<String>) -> Option<String> {
+        cli_profile
+            .or_else(|| std::env::var("BAKERY_PROFILE").ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
     }
 
     pub fn get_example_config_path() -> String {
-        if cfg!(windows) {
-            format!("{}\\bakery-config.example.toml", env!("CARGO_MANIFEST_DIR"))
-        } else {
-            format!("{}/bakery-config.example.toml", env!("CARGO_MANIFEST_DIR"))
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("bakery-config.example.toml")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Load the config for `profile`, or the default config when `None`
+    ///
+    /// The default config is created from the example on first run; a missing
+    /// profile is an error so typos don't silently start from a blank config.
+    pub fn load(profile: Option<&str>) -> Result<Self> {
+        let config_path = Self::get_config_path(profile)?;
+
+        if profile.is_none() {
+            Self::ensure_config_file(None)?;
+        } else if !std::path::Path::new(&config_path).exists() {
+            let available = Self::list_profiles().unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Profile '{}' not found at {}. Available profiles: {}. Create it with 'bakery --profile {} config'",
+                profile.unwrap_or_default(),
+                config_path,
+                if available.is_empty() { "none".to_string() } else { available.join(", ") },
+                profile.unwrap_or_default()
+            ));
         }
+
+        let config_content = std::fs::read_to_string(&config_path)?;
+        let config: BakeryConfig = toml::from_str(&config_content)?;
+        Ok(config)
     }
 
-    pub fn load() -> Result<Self> {
-        let config_path = Self::get_config_path()?;
-        let config_dir = Self::get_config_dir()?;
+    /// Create the config file for `profile` from the example (or defaults) if it doesn't exist yet
+    pub fn ensure_config_file(profile: Option<&str>) -> Result<String> {
+        let config_path = Self::get_config_path(profile)?;
 
-        // Ensure .bakery directory exists
-        std::fs::create_dir_all(&config_dir)?;
+        // Ensure .bakery (and profiles) directory exists
+        if let Some(config_dir) = std::path::Path::new(&config_path).parent() {
+            std::fs::create_dir_all(config_dir)?;
+        }
 
-        if std::path::Path::new(&config_path).exists() {
-            let config_content = std::fs::read_to_string(&config_path)?;
-            let config: BakeryConfig = toml::from_str(&config_content)?;
-            Ok(config)
-        } else {
+        if !std::path::Path::new(&config_path).exists() {
             // Copy example config to user config directory
             let example_path = Self::get_example_config_path();
             if std::path::Path::new(&example_path).exists() {
@@ -160,20 +467,138 @@ impl BakeryConfig {
                 let config_toml = toml::to_string_pretty(&default_config)?;
                 std::fs::write(&config_path, config_toml)?;
             }
+        }
 
-            // Load the created config
-            let config_content = std::fs::read_to_string(&config_path)?;
-            let config: BakeryConfig = toml::from_str(&config_content)?;
-            Ok(config)
+        Ok(config_path)
+    }
+
+    /// Write this config to the file for `profile`, replacing any existing file
+    pub fn save(&self, profile: Option<&str>) -> Result<String> {
+        let config_path = Self::get_config_path(profile)?;
+
+        if let Some(config_dir) = std::path::Path::new(&config_path).parent() {
+            std::fs::create_dir_all(config_dir)?;
+        }
+
+        std::fs::write(&config_path, toml::to_string_pretty(self)?)?;
+        Ok(config_path)
+    }
+
+    /// Rewrite the `pat_token` line of the user's config file in place, keeping comments intact
+    pub fn set_pat_token_in_file(profile: Option<&str>, value: &str) -> Result<()> {
+        let config_path = Self::get_config_path(profile)?;
+        let config_content = std::fs::read_to_string(&config_path)?;
+
+        let mut replaced = false;
+        let mut lines: Vec<String> = config_content
+            .lines()
+            .map(|line| {
+                let key = line.split('=').next().unwrap_or("").trim();
+                if !replaced && key == "pat_token" {
+                    replaced = true;
+                    format!("pat_token = \"{}\"", value)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+
+        if !replaced {
+            return Err(anyhow::anyhow!("No pat_token entry found in {}", config_path));
+        }
+
+        lines.push(String::new());
+        std::fs::write(&config_path, lines.join("\n"))?;
+        Ok(())
+    }
+
+    /// Check for settings that would otherwise fail later with a confusing API error
+    ///
+    /// Every problem found is listed in the returned error, not just the first.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        let is_placeholder = |value: &str, placeholders: &[&str]| {
+            let value = value.trim();
+            value.is_empty() || placeholders.contains(&value)
+        };
+
+        if is_placeholder(&self.azure_devops.organization, &["your-organization"]) {
+            problems.push("azure_devops.organization is empty or still the example placeholder".to_string());
+        }
+        if is_placeholder(&self.azure_devops.project, &["YourProject", "your-project"]) {
+            problems.push("azure_devops.project is empty or still the example placeholder".to_string());
+        }
+
+        // The PAT may also come from --pat-token, the keyring sentinel or AZURE_DEVOPS_PAT
+        let pat_token = self.azure_devops.pat_token.trim();
+        let env_token_set = std::env::var("AZURE_DEVOPS_PAT").is_ok_and(|token| !token.trim().is_empty());
+        if self.azure_devops.auth_method == AuthMethod::Pat
+            && pat_token != crate::credentials::KEYRING_SENTINEL
+            && is_placeholder(pat_token, &["your-pat-token-here"])
+            && !env_token_set
+        {
+            problems.push("azure_devops.pat_token is empty or still the placeholder and AZURE_DEVOPS_PAT is not set".to_string());
+        }
+
+        if self.openspec.ai_provider == AiProviderKind::ClaudeCli
+            && self.openspec.ai_command_template.trim().is_empty()
+        {
+            problems.push("openspec.ai_command_template is empty".to_string());
+        }
+
+        if let Some(path) = &self.openspec.prompt_template_path {
+            if let Err(e) = crate::models::PromptTemplate::load(path) {
+                problems.push(format!("openspec.prompt_template_path {}", e));
+            }
+        }
+
+        for artifact in &self.storage.artifacts {
+            if !crate::filesystem::ARTIFACTS.contains(&artifact.as_str()) {
+                problems.push(format!(
+                    "storage.artifacts has unknown artifact \"{}\" (use {})",
+                    artifact,
+                    crate::filesystem::ARTIFACTS.join(", ")
+                ));
+            }
+        }
+
+        if let Err(e) = crate::filesystem::TicketPathTemplate::parse(&self.storage.ticket_path_template) {
+            problems.push(format!("storage.ticket_path_template {}", e));
+        }
+
+        if !self.storage.local_baking {
+            let base_directory = self.get_base_directory();
+            let parent = std::path::Path::new(&base_directory)
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty());
+            if let Some(parent) = parent {
+                if !parent.exists() {
+                    problems.push(format!(
+                        "storage.base_directory \"{}\" is inside {}, which does not exist",
+                        base_directory,
+                        parent.display()
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Configuration has {} problem(s): {}",
+                problems.len(),
+                problems.join("; ")
+            ))
         }
     }
 
     pub fn get_tickets_directory(&self) -> String {
-        format!("{}/{}", self.storage.base_directory, self.storage.tickets_subdir)
+        format!("{}/{}", self.get_base_directory(), self.storage.tickets_subdir)
     }
 
     pub fn get_openspec_directory(&self) -> String {
-        format!("{}/{}", self.storage.base_directory, self.storage.openspec_subdir)
+        format!("{}/{}", self.get_base_directory(), self.storage.openspec_subdir)
     }
 
     pub fn get_ticket_directory(&self, ticket_id: u32) -> String {
@@ -181,7 +606,7 @@ impl BakeryConfig {
     }
 
     /// Gets the base directory to use for storage operations
-    /// Returns the current working directory if local_baking is enabled, otherwise the configured base_directory
+    /// Returns the current working directory if local_baking is enabled, otherwise the expanded base_directory
     pub fn get_effective_base_directory(&self) -> String {
         if self.storage.local_baking {
             match std::env::current_dir() {
@@ -189,14 +614,19 @@ impl BakeryConfig {
                 Err(_) => {
                     // Fallback to configured base_directory if we can't get current directory
                     eprintln!("⚠️ Warning: Could not determine current directory, using configured base_directory");
-                    self.storage.base_directory.clone()
+                    self.get_base_directory()
                 }
             }
         } else {
-            self.storage.base_directory.clone()
+            self.get_base_directory()
         }
     }
 
+    /// Gets the configured base_directory with `~` and environment variables expanded
+    pub fn get_base_directory(&self) -> String {
+        expand_path(&self.storage.base_directory)
+    }
+
     /// Gets the effective tickets directory based on local_baking setting
     pub fn get_effective_tickets_directory(&self) -> String {
         let base_dir = self.get_effective_base_directory();
@@ -208,4 +638,66 @@ impl BakeryConfig {
         let base_dir = self.get_effective_base_directory();
         format!("{}/{}", base_dir, self.storage.openspec_subdir)
     }
-}
\ No newline at end of file
+}
+
+/// The current user's home directory (USERPROFILE on Windows, HOME elsewhere)
+fn home_dir() -> Option<String> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var(var).ok().filter(|dir| !dir.is_empty())
+}
+
+/// Expand a leading `~` to the home directory and `$VAR`, `${VAR}` or `%VAR%`
+/// references to their environment values. Unset variables are left untouched.
+pub fn expand_path(path: &str) -> String {
+    let path = path.trim();
+
+    let path = match (path.strip_prefix('~'), home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+            format!("{}{}", home.trim_end_matches(['/', '\\']), rest)
+        }
+        _ => path.to_string(),
+    };
+
+    let var_regex = regex::Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}|\$([A-Za-z_][A-Za-z0-9_]*)|%([A-Za-z_][A-Za-z0-9_]*)%")
+        .expect("valid env var regex");
+
+    var_regex
+        .replace_all(&path, |caps: &regex::Captures| {
+            let name = caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3)).map_or("", |m| m.as_str());
+            std::env::var(name).unwrap_or_else(|_| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilde_expands_to_the_home_dir() {
+        let home = home_dir().expect("HOME or USERPROFILE is set");
+        let home = home.trim_end_matches(['/', '\\']);
+
+        assert_eq!(expand_path("~"), home);
+        assert_eq!(expand_path("~/devops-data"), format!("{}/devops-data", home));
+        assert_eq!(expand_path("~\\devops-data"), format!("{}\\devops-data", home));
+        // Only a leading `~` on its own is the home dir
+        assert_eq!(expand_path("~other/data"), "~other/data");
+        assert_eq!(expand_path("/data/~/x"), "/data/~/x");
+    }
+
+    #[test]
+    fn env_vars_are_expanded_in_every_style() {
+        std::env::set_var("BAKERY_TEST_DATA_ROOT", "/srv/bakery");
+
+        assert_eq!(expand_path("$BAKERY_TEST_DATA_ROOT/tickets"), "/srv/bakery/tickets");
+        assert_eq!(expand_path("${BAKERY_TEST_DATA_ROOT}-old"), "/srv/bakery-old");
+        assert_eq!(expand_path("%BAKERY_TEST_DATA_ROOT%\\tickets"), "/srv/bakery\\tickets");
+    }
+
+    #[test]
+    fn unset_env_vars_are_left_as_is() {
+        assert_eq!(expand_path("$BAKERY_TEST_UNSET_VAR/x"), "$BAKERY_TEST_UNSET_VAR/x");
+        assert_eq!(expand_path("%BAKERY_TEST_UNSET_VAR%/x"), "%BAKERY_TEST_UNSET_VAR%/x");
+    }
+}
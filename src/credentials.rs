@@ -0,0 +1,47 @@
+//! OS credential store integration for Bakery
+//!
+//! Lets users keep their Azure DevOps PAT out of the plaintext config file.
+//! The token is stored per organization under the `bakery` service, and the
+//! config file holds the `pat_token = "keyring"` sentinel instead.
+
+use anyhow::{anyhow, Result};
+use tracing::debug;
+
+/// `pat_token` value meaning "look the token up in the OS keyring"
+pub const KEYRING_SENTINEL: &str = "keyring";
+
+/// Keyring service name all Bakery credentials are stored under
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "bakery";
+
+/// Store the PAT for `organization` in the OS keyring
+#[cfg(feature = "keyring")]
+pub fn store_pat(organization: &str, token: &str) -> Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, organization)
+        .and_then(|entry| entry.set_password(token))
+        .map_err(|e| anyhow!("Failed to store token in the OS keyring: {}", e))
+}
+
+/// Look up the PAT for `organization`; `None` if there is no entry or no keyring available
+#[cfg(feature = "keyring")]
+pub fn load_pat(organization: &str) -> Option<String> {
+    match keyring::Entry::new(KEYRING_SERVICE, organization).and_then(|entry| entry.get_password()) {
+        Ok(token) if !token.trim().is_empty() => Some(token.trim().to_string()),
+        Ok(_) | Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            debug!("Keyring lookup for {} failed: {}", organization, e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn store_pat(_organization: &str, _token: &str) -> Result<()> {
+    Err(anyhow!("This build of Bakery was compiled without keyring support (enable the \"keyring\" feature)"))
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn load_pat(organization: &str) -> Option<String> {
+    debug!("Keyring support not compiled in; skipping lookup for {}", organization);
+    None
+}
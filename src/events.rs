@@ -0,0 +1,66 @@
+//! Line-delimited JSON progress events for `--events`
+//!
+//! Each lifecycle step is written to stdout as one JSON object per line,
+//! tagged by `event`, so a wrapper process can follow a run as it happens.
+
+use serde::Serialize;
+
+/// A step in the scrape/generate pipeline
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    FetchStarted {
+        work_item_ids: &'a [u32],
+    },
+    WorkItemFetched {
+        work_item_id: u32,
+        title: &'a str,
+        work_item_type: &'a str,
+        state: &'a str,
+    },
+    WorkItemFailed {
+        work_item_id: u32,
+        error: String,
+    },
+    /// The saved copy already matches the current revision, so nothing was fetched
+    WorkItemUpToDate {
+        work_item_id: u32,
+        revision: u32,
+    },
+    AttachmentDownloaded {
+        work_item_id: u32,
+        filename: &'a str,
+        path: &'a str,
+        size: u64,
+    },
+    PlanGenerated {
+        work_item_id: u32,
+        change_id: &'a str,
+        change_path: &'a str,
+        prompt_path: &'a str,
+    },
+    PlanFailed {
+        work_item_id: u32,
+        error: String,
+        prompt_path: &'a str,
+    },
+    ValidationResult {
+        change_id: &'a str,
+        /// "passed", "failed" or "skipped" (openspec CLI not installed)
+        status: &'a str,
+        issues: &'a [String],
+    },
+    Done {
+        total: usize,
+        succeeded: usize,
+        failed: usize,
+    },
+}
+
+/// Write one event as a single JSON line on stdout
+pub fn emit(event: &Event) {
+    match serde_json::to_string(event) {
+        Ok(line) => println!("{}", line),
+        Err(e) => tracing::warn!("Failed to serialize event: {}", e),
+    }
+}
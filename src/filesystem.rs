@@ -1,12 +1,157 @@
 use crate::models::*;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
-use tracing::{debug, info};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
 
+#[derive(Clone)]
 pub struct FileSystemOrganizer {
     base_path: String,
     tickets_path: String,
     openspec_path: String,
+    /// `openspec_path` relative to `base_path`
+    openspec_subdir: String,
+    /// Include `#1234` description mentions in plan dependencies
+    description_dependencies: bool,
+    /// Save the original HTML next to the cleaned Markdown
+    keep_raw_html: bool,
+    /// Also write everything into a single `work-item.md`
+    combined_markdown: bool,
+    /// Which of `ARTIFACTS` to write; empty writes them all
+    artifacts: Vec<String>,
+    /// How many of the latest comments go into the prompt
+    prompt_comments: usize,
+    /// Descriptions, criteria and comments are already Markdown (loaded from a
+    /// saved ticket), so plan data must not run them through the HTML cleaner
+    markdown_content: bool,
+    /// Where each ticket folder goes under `tickets_path`
+    ticket_path_template: TicketPathTemplate,
+}
+
+/// Upper bound on the comment text put into a prompt, so long threads can't crowd out the ticket itself
+const MAX_DISCUSSION_CHARS: usize = 8_000;
+
+/// Longest description summary kept for each parent work item in the prompt
+const MAX_PARENT_SUMMARY_CHARS: usize = 1_000;
+
+/// The fields of `metadata.json` needed to rebuild a `WorkItem`
+#[derive(Deserialize)]
+struct SavedMetadata {
+    id: u32,
+    #[serde(default)]
+    revision: u32,
+    title: String,
+    state: String,
+    work_item_type: String,
+    #[serde(default)]
+    area_path: String,
+    #[serde(default)]
+    iteration_path: String,
+    #[serde(default)]
+    iteration_start: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    iteration_end: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    custom_fields: HashMap<String, serde_json::Value>,
+    created_date: chrono::DateTime<chrono::Utc>,
+    updated_date: chrono::DateTime<chrono::Utc>,
+    created_by: User,
+    assigned_to: Option<User>,
+    #[serde(default)]
+    parents: Vec<ParentWorkItem>,
+}
+
+/// Lines that changed in `description.md` since the previous scrape
+#[derive(Debug, Default)]
+pub struct DescriptionChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Files `save_work_item` can write, as named in `storage.artifacts`
+///
+/// `metadata.json` isn't listed: it is always written, as the up-to-date check,
+/// index, view, replan and clean commands all find tickets through it.
+pub const ARTIFACTS: &[&str] = &[
+    "description",
+    "acceptance_criteria",
+    "comments",
+    "work_item",
+    "attachment_manifest",
+    "image_manifest",
+    "links",
+    "pull_requests",
+];
+
+/// How deep below `Tickets/` to look for ticket folders (enough for `{type}/{year}/{month}/{id}`)
+const MAX_TICKET_DEPTH: usize = 5;
+
+/// Layout of a ticket folder below `Tickets/`, e.g. `{year}/{month}/{id}`
+///
+/// Tokens: `{id}`, `{year}`, `{month}` and `{day}` (from the created date) and
+/// `{type}` (the work item type in kebab-case, e.g. `user-story`).
+#[derive(Debug, Clone)]
+pub struct TicketPathTemplate(String);
+
+impl TicketPathTemplate {
+    const TOKENS: &'static [&'static str] = &["id", "year", "month", "day", "type"];
+
+    /// Check a template: a relative path that includes `{id}`, only known tokens and no `.`/`..` segments
+    pub fn parse(template: &str) -> Result<Self> {
+        let template = template.trim().replace('\\', "/");
+
+        if template.starts_with('/') || template.contains(':') {
+            return Err(anyhow!("\"{}\" must be a relative path", template));
+        }
+        if !template.contains("{id}") {
+            return Err(anyhow!("\"{}\" must include {{id}} so every ticket gets its own folder", template));
+        }
+        for segment in template.split('/') {
+            if segment.is_empty() || segment == "." || segment == ".." {
+                return Err(anyhow!("\"{}\" contains an empty, '.' or '..' path segment", template));
+            }
+        }
+
+        let token = regex::Regex::new(r"\{([^{}]*)\}").unwrap();
+        if let Some(unknown) = token.captures_iter(&template).find(|caps| !Self::TOKENS.contains(&&caps[1])) {
+            return Err(anyhow!("unknown token {} in \"{}\" (use {})", &unknown[0], template,
+                Self::TOKENS.iter().map(|t| format!("{{{}}}", t)).collect::<Vec<_>>().join(", ")));
+        }
+        if token.replace_all(&template, "").contains(['{', '}']) {
+            return Err(anyhow!("\"{}\" has an unmatched brace", template));
+        }
+
+        Ok(Self(template))
+    }
+
+    /// Folder for a work item, relative to `Tickets/`
+    pub fn resolve(&self, work_item: &WorkItem) -> String {
+        let work_item_type = work_item.work_item_type
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join("-");
+
+        self.0
+            .replace("{id}", &work_item.id.to_string())
+            .replace("{year}", &work_item.created_date.format("%Y").to_string())
+            .replace("{month}", &work_item.created_date.format("%m").to_string())
+            .replace("{day}", &work_item.created_date.format("%d").to_string())
+            .replace("{type}", if work_item_type.is_empty() { "unknown" } else { &work_item_type })
+    }
+}
+
+impl Default for TicketPathTemplate {
+    /// One folder per ticket directly under `Tickets/`
+    fn default() -> Self {
+        Self("{id}".to_string())
+    }
 }
 
 impl FileSystemOrganizer {
@@ -15,10 +160,78 @@ impl FileSystemOrganizer {
         Self {
             tickets_path: format!("{}/Tickets", base_path),
             openspec_path: format!("{}/openspec", base_path),
+            openspec_subdir: "openspec".to_string(),
             base_path,
+            description_dependencies: false,
+            keep_raw_html: false,
+            combined_markdown: true,
+            artifacts: Vec::new(),
+            prompt_comments: 5,
+            markdown_content: false,
+            ticket_path_template: TicketPathTemplate::default(),
         }
     }
 
+    /// Keep OpenSpec files in `{base}/{subdir}` instead of `{base}/openspec`
+    pub fn with_openspec_subdir(mut self, subdir: &str) -> Self {
+        self.openspec_subdir = subdir.trim_matches(['/', '\\']).to_string();
+        self.openspec_path = format!("{}/{}", self.base_path, self.openspec_subdir);
+        self
+    }
+
+    /// Lay out ticket folders with `template` instead of `Tickets/{id}`
+    pub fn with_ticket_path_template(mut self, template: TicketPathTemplate) -> Self {
+        self.ticket_path_template = template;
+        self
+    }
+
+    /// Include the latest `count` comments in the prompt (0 leaves comments out)
+    pub fn with_prompt_comments(mut self, count: usize) -> Self {
+        self.prompt_comments = count;
+        self
+    }
+
+    /// Treat work item text as Markdown rather than HTML, for work items read back
+    /// with `load_work_item`; HTML cleaning would mangle text such as `a < b`
+    pub fn with_markdown_content(mut self, markdown_content: bool) -> Self {
+        self.markdown_content = markdown_content;
+        self
+    }
+
+    /// Also write the original description and comment HTML (`description.html`, `comment_NNN.html`)
+    pub fn with_raw_html(mut self, enabled: bool) -> Self {
+        self.keep_raw_html = enabled;
+        self
+    }
+
+    /// Only write the listed artifacts (see `ARTIFACTS`); an empty list writes everything
+    pub fn with_artifacts(mut self, artifacts: Vec<String>) -> Self {
+        self.artifacts = artifacts;
+        self
+    }
+
+    /// Whether `artifact` should be written for each saved work item
+    fn writes(&self, artifact: &str) -> bool {
+        self.artifacts.is_empty() || self.artifacts.iter().any(|name| name == artifact)
+    }
+
+    /// Also write `work-item.md`: description, acceptance criteria and comments in one file
+    pub fn with_combined_markdown(mut self, enabled: bool) -> Self {
+        self.combined_markdown = enabled;
+        self
+    }
+
+    /// Also list `#1234` mentions from the description as dependencies, not just linked work items
+    pub fn with_description_dependencies(mut self, enabled: bool) -> Self {
+        self.description_dependencies = enabled;
+        self
+    }
+
+    /// Directory holding one folder per work item (`{base}/Tickets`)
+    pub fn tickets_path(&self) -> &str {
+        &self.tickets_path
+    }
+
     pub fn ensure_base_structure(&self) -> Result<()> {
         // Create base directories
         fs::create_dir_all(&self.base_path)?;
@@ -30,13 +243,19 @@ impl FileSystemOrganizer {
     }
 
     pub async fn save_work_item(&self, work_item: &WorkItem) -> Result<String> {
-        let ticket_path = format!("{}/{}", self.tickets_path, work_item.id);
+        let ticket_path = format!("{}/{}", self.tickets_path, self.ticket_path_template.resolve(work_item));
 
-        // Create ticket-specific directories
+        // Create ticket-specific directories (and any intermediate ones from the template)
         fs::create_dir_all(&ticket_path)?;
-        fs::create_dir_all(format!("{}/attachments", ticket_path))?;
-        fs::create_dir_all(format!("{}/images", ticket_path))?;
-        fs::create_dir_all(format!("{}/comments", ticket_path))?;
+        if self.writes("attachment_manifest") {
+            fs::create_dir_all(format!("{}/attachments", ticket_path))?;
+        }
+        if self.writes("image_manifest") {
+            fs::create_dir_all(format!("{}/images", ticket_path))?;
+        }
+        if self.writes("comments") {
+            fs::create_dir_all(format!("{}/comments", ticket_path))?;
+        }
 
         info!("Saving work item {} to {}", work_item.id, ticket_path);
 
@@ -44,19 +263,44 @@ impl FileSystemOrganizer {
         self.save_metadata(work_item, &ticket_path)?;
 
         // Save description with image placeholders
-        self.save_description(work_item, &ticket_path)?;
+        if self.writes("description") {
+            self.save_description(work_item, &ticket_path)?;
+        }
 
         // Save acceptance criteria
-        self.save_acceptance_criteria(work_item, &ticket_path)?;
+        if self.writes("acceptance_criteria") {
+            self.save_acceptance_criteria(work_item, &ticket_path)?;
+        }
 
         // Save comments
-        self.save_comments(work_item, &ticket_path)?;
+        if self.writes("comments") {
+            self.save_comments(work_item, &ticket_path)?;
+        }
+
+        // Save everything above as one document for reviewers
+        if self.combined_markdown && self.writes("work_item") {
+            self.save_combined_markdown(work_item, &ticket_path)?;
+        }
 
         // Save attachment manifest
-        self.save_attachment_manifest(work_item, &ticket_path)?;
+        if self.writes("attachment_manifest") {
+            self.save_attachment_manifest(work_item, &ticket_path)?;
+        }
 
         // Save image manifest
-        self.save_image_manifest(work_item, &ticket_path)?;
+        if self.writes("image_manifest") {
+            self.save_image_manifest(work_item, &ticket_path)?;
+        }
+
+        // Save links to related work items
+        if self.writes("links") {
+            self.save_links(work_item, &ticket_path)?;
+        }
+
+        // Save linked pull requests
+        if self.writes("pull_requests") {
+            self.save_pull_requests(work_item, &ticket_path)?;
+        }
 
         info!("Successfully saved work item {} to {}", work_item.id, ticket_path);
         Ok(ticket_path)
@@ -67,11 +311,16 @@ impl FileSystemOrganizer {
 
         let metadata = serde_json::json!({
             "id": work_item.id,
+            "revision": work_item.revision,
             "title": work_item.title,
             "state": work_item.state,
             "work_item_type": work_item.work_item_type,
             "area_path": work_item.area_path,
             "iteration_path": work_item.iteration_path,
+            "iteration_start": work_item.iteration_start,
+            "iteration_end": work_item.iteration_end,
+            "tags": work_item.tags,
+            "custom_fields": work_item.custom_fields,
             "created_date": work_item.created_date,
             "updated_date": work_item.updated_date,
             "created_by": {
@@ -82,10 +331,18 @@ impl FileSystemOrganizer {
                 "display_name": user.display_name,
                 "email": user.email
             })),
+            "parent_id": work_item.links.iter().find(|link| link.is_parent()).map(|link| link.id),
+            "child_ids": work_item.links.iter().filter(|link| link.is_child()).map(|link| link.id).collect::<Vec<_>>(),
+            "parents": work_item.parents.iter().map(|parent| serde_json::json!({
+                "id": parent.id,
+                "title": parent.title,
+                "work_item_type": parent.work_item_type
+            })).collect::<Vec<_>>(),
             "stats": {
                 "attachments_count": work_item.attachments.len(),
                 "comments_count": work_item.comments.len(),
                 "images_count": work_item.images.len(),
+                "links_count": work_item.links.len(),
                 "acceptance_criteria_count": work_item.acceptance_criteria.len()
             }
         });
@@ -98,11 +355,46 @@ impl FileSystemOrganizer {
     fn save_description(&self, work_item: &WorkItem, ticket_path: &str) -> Result<()> {
         let description_path = format!("{}/description.md", ticket_path);
 
-        // Clean HTML content and replace image URLs with placeholders
-        let cleaned_description = clean_html_content(&work_item.description);
-        let processed_description = self.replace_image_placeholders(&cleaned_description, &work_item.images);
+        if self.keep_raw_html {
+            fs::write(format!("{}/description.html", ticket_path), &work_item.description)?;
+        }
+
+        fs::write(&description_path, self.description_markdown(work_item))?;
+        debug!("Saved description to {}", description_path);
+        Ok(())
+    }
+
+    /// Compare the `description.md` about to be written with the one saved by an earlier scrape
+    ///
+    /// Returns `None` when there is no earlier copy or nothing changed.
+    pub fn description_changes(&self, work_item: &WorkItem) -> Option<DescriptionChanges> {
+        let description_path = format!("{}/{}/description.md", self.tickets_path, self.ticket_path_template.resolve(work_item));
+        let previous = fs::read_to_string(description_path).ok()?;
+        let current = self.description_markdown(work_item);
+
+        let mut changes = DescriptionChanges::default();
+        for change in similar::TextDiff::from_lines(&previous, &current).iter_all_changes() {
+            let line = change.value().trim_end().to_string();
+            match change.tag() {
+                similar::ChangeTag::Insert => changes.added.push(line),
+                similar::ChangeTag::Delete => changes.removed.push(line),
+                similar::ChangeTag::Equal => {}
+            }
+        }
+
+        if changes.added.is_empty() && changes.removed.is_empty() {
+            None
+        } else {
+            Some(changes)
+        }
+    }
+
+    /// Contents of `description.md`: a header with the key fields, then the cleaned description
+    fn description_markdown(&self, work_item: &WorkItem) -> String {
+        // Replace image URLs with placeholders while the img tags are still there, then clean the HTML
+        let processed_description = clean_html_content(&self.replace_image_placeholders(&work_item.description, &work_item.images));
 
-        let content = format!("# {}\n\n**Work Item ID**: {}\n\n**State**: {}\n\n**Type**: {}\n\n**Created**: {}\n\n**Created By**: {}\n\n---\n\n## Description\n\n{}",
+        format!("# {}\n\n**Work Item ID**: {}\n\n**State**: {}\n\n**Type**: {}\n\n**Created**: {}\n\n**Created By**: {}\n\n---\n\n## Description\n\n{}",
             work_item.title,
             work_item.id,
             work_item.state,
@@ -110,11 +402,7 @@ impl FileSystemOrganizer {
             work_item.created_date.format("%Y-%m-%d %H:%M:%S UTC"),
             work_item.created_by.display_name,
             processed_description
-        );
-
-        fs::write(&description_path, content)?;
-        debug!("Saved description to {}", description_path);
-        Ok(())
+        )
     }
 
     fn save_acceptance_criteria(&self, work_item: &WorkItem, ticket_path: &str) -> Result<()> {
@@ -166,12 +454,19 @@ impl FileSystemOrganizer {
                         "placeholder": img.placeholder,
                         "original_url": img.original_url,
                         "local_path": img.local_path,
-                        "alt_text": img.alt_text
+                        "width": img.width,
+                        "height": img.height,
+                        "alt_text": img.alt_text,
+                        "alias_of": img.alias_of
                     })).collect::<Vec<_>>()
                 });
 
                 fs::write(&comment_path, serde_json::to_string_pretty(&comment_data)?)?;
 
+                if self.keep_raw_html {
+                    fs::write(format!("{}/comment_{:03}.html", comments_dir, index + 1), &comment.text)?;
+                }
+
                 // Also save as markdown for readability
                 let markdown_filename = format!("comment_{:03}.md", index + 1);
                 let markdown_path = format!("{}/{}", comments_dir, markdown_filename);
@@ -192,6 +487,62 @@ impl FileSystemOrganizer {
         Ok(())
     }
 
+    /// Write `work-item.md`: the header, description, numbered acceptance criteria
+    /// and every comment (oldest first) in a single file
+    pub fn save_combined_markdown(&self, work_item: &WorkItem, ticket_path: &str) -> Result<()> {
+        let combined_path = format!("{}/work-item.md", ticket_path);
+
+        // Links are relative to the ticket folder, which also works for comment images in images/comment_N/
+        let image_link = |image: &ImageReference| match &image.alias_of {
+            Some(alias_of) => format!("images/{}", alias_of),
+            None => image.local_path
+                .strip_prefix(ticket_path)
+                .unwrap_or(&image.local_path)
+                .trim_start_matches(['/', '\\'])
+                .replace('\\', "/"),
+        };
+
+        // Images are linked while their img tags are still there, before the HTML is cleaned
+        let description = clean_html_content(&self.replace_image_links(&work_item.description, &work_item.images, image_link));
+        let mut content = format!("# {}\n\n**Work Item ID**: {}\n\n**State**: {}\n\n**Type**: {}\n\n**Created**: {}\n\n**Created By**: {}\n\n---\n\n## Description\n\n{}\n",
+            work_item.title,
+            work_item.id,
+            work_item.state,
+            work_item.work_item_type,
+            work_item.created_date.format("%Y-%m-%d %H:%M:%S UTC"),
+            work_item.created_by.display_name,
+            description
+        );
+
+        content.push_str("\n## Acceptance Criteria\n\n");
+        if work_item.acceptance_criteria.is_empty() {
+            content.push_str("No explicit acceptance criteria specified in the work item.\n");
+        } else {
+            for (i, ac) in clean_text_content_list(&work_item.acceptance_criteria).iter().enumerate() {
+                content.push_str(&format!("{}. {}\n\n", i + 1, ac));
+            }
+        }
+
+        let mut comments: Vec<&Comment> = work_item.comments.iter().collect();
+        comments.sort_by_key(|comment| comment.created_date);
+
+        content.push_str(&format!("\n## Comments ({})\n", comments.len()));
+        if comments.is_empty() {
+            content.push_str("\nNo comments found for this work item.\n");
+        }
+        for comment in comments {
+            content.push_str(&format!("\n### {} — {}\n\n{}\n",
+                comment.author.display_name,
+                comment.created_date.format("%Y-%m-%d %H:%M:%S UTC"),
+                clean_html_content(&self.replace_image_links(&comment.text, &comment.images, image_link))
+            ));
+        }
+
+        fs::write(&combined_path, content)?;
+        debug!("Saved combined markdown to {}", combined_path);
+        Ok(())
+    }
+
     fn save_attachment_manifest(&self, work_item: &WorkItem, ticket_path: &str) -> Result<()> {
         let manifest_path = format!("{}/attachments/manifest.json", ticket_path);
 
@@ -199,11 +550,14 @@ impl FileSystemOrganizer {
             "attachments": work_item.attachments.iter().map(|att| serde_json::json!({
                 "id": att.id,
                 "filename": att.filename,
+                "original_filename": att.original_filename,
                 "original_url": att.url,
                 "local_path": att.local_path,
                 "content_type": att.content_type,
                 "size_bytes": att.size,
-                "created_date": att.created_date
+                "created_date": att.created_date,
+                "skipped": att.skipped,
+                "skip_reason": att.skip_reason
             })).collect::<Vec<_>>()
         });
 
@@ -222,7 +576,8 @@ impl FileSystemOrganizer {
                 "local_path": img.local_path,
                 "width": img.width,
                 "height": img.height,
-                "alt_text": img.alt_text
+                "alt_text": img.alt_text,
+                "alias_of": img.alias_of
             })).collect::<Vec<_>>()
         });
 
@@ -231,21 +586,270 @@ impl FileSystemOrganizer {
         Ok(())
     }
 
+    fn save_links(&self, work_item: &WorkItem, ticket_path: &str) -> Result<()> {
+        let links_path = format!("{}/links.json", ticket_path);
+
+        let links = serde_json::json!({
+            "links": work_item.links.iter().map(|link| serde_json::json!({
+                "id": link.id,
+                "link_type": link.link_type,
+                "name": link.name,
+                "url": link.url
+            })).collect::<Vec<_>>()
+        });
+
+        fs::write(&links_path, serde_json::to_string_pretty(&links)?)?;
+        debug!("Saved {} links to {}", work_item.links.len(), links_path);
+        Ok(())
+    }
+
+    fn save_pull_requests(&self, work_item: &WorkItem, ticket_path: &str) -> Result<()> {
+        let pull_requests_path = format!("{}/pull-requests.json", ticket_path);
+
+        let pull_requests = serde_json::json!({
+            "pull_requests": work_item.pull_requests.iter().map(|pr| serde_json::json!({
+                "pull_request_id": pr.pull_request_id,
+                "repository_id": pr.repository_id,
+                "project_id": pr.project_id,
+                "name": pr.name,
+                "url": pr.url
+            })).collect::<Vec<_>>()
+        });
+
+        fs::write(&pull_requests_path, serde_json::to_string_pretty(&pull_requests)?)?;
+        debug!("Saved {} pull requests to {}", work_item.pull_requests.len(), pull_requests_path);
+        Ok(())
+    }
+
+    /// Revision recorded in a previously saved ticket's `metadata.json`, if any
+    pub fn saved_revision(&self, work_item_id: u32) -> Option<u32> {
+        let metadata_path = self.find_ticket_dir(work_item_id)?.join("metadata.json");
+        let metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(metadata_path).ok()?).ok()?;
+        metadata["revision"].as_u64().map(|rev| rev as u32)
+    }
+
+    /// Folder a work item was saved to, whatever `ticket_path_template` was in use then
+    pub fn find_ticket_dir(&self, work_item_id: u32) -> Option<PathBuf> {
+        let flat = Path::new(&self.tickets_path).join(work_item_id.to_string());
+        if flat.join("metadata.json").is_file() {
+            return Some(flat);
+        }
+
+        self.ticket_dirs().into_iter().find(|dir| {
+            fs::read_to_string(dir.join("metadata.json"))
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                .is_some_and(|metadata| metadata["id"].as_u64() == Some(work_item_id as u64))
+        })
+    }
+
+    /// Rebuild a saved work item from its ticket folder, without touching the network
+    ///
+    /// Fields come from `metadata.json`; the description, acceptance criteria,
+    /// comments, manifests and links are read when their files exist. The
+    /// description and comment text are the cleaned Markdown, not the original HTML.
+    pub fn load_work_item(&self, work_item_id: u32) -> Result<WorkItem> {
+        let ticket_dir = self
+            .find_ticket_dir(work_item_id)
+            .ok_or_else(|| anyhow!("No saved copy of work item #{} in {}", work_item_id, self.tickets_path))?;
+
+        let metadata_path = ticket_dir.join("metadata.json");
+        let metadata: SavedMetadata = serde_json::from_str(&fs::read_to_string(&metadata_path)?)
+            .map_err(|e| anyhow!("Could not read {}: {}", metadata_path.display(), e))?;
+
+        // Everything after the header written by `description_markdown`
+        let description = fs::read_to_string(ticket_dir.join("description.md"))
+            .ok()
+            .and_then(|content| content.split_once("## Description\n\n").map(|(_, body)| body.to_string()))
+            .unwrap_or_default();
+
+        let acceptance_criteria = fs::read_to_string(ticket_dir.join("acceptance-criteria.md"))
+            .map(|content| {
+                let item_start = regex::Regex::new(r"(?m)^\d+\. ").unwrap();
+                item_start
+                    .split(&content)
+                    .skip(1)
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut comment_files: Vec<PathBuf> = fs::read_dir(ticket_dir.join("comments"))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        comment_files.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+        comment_files.sort();
+        let comments = comment_files
+            .iter()
+            .filter_map(|path| serde_json::from_str::<Comment>(&fs::read_to_string(path).ok()?).ok())
+            .collect();
+
+        Ok(WorkItem {
+            id: metadata.id,
+            revision: metadata.revision,
+            title: metadata.title,
+            description,
+            acceptance_criteria,
+            comments,
+            attachments: read_saved_list(&ticket_dir.join("attachments/manifest.json"), "attachments"),
+            images: read_saved_list(&ticket_dir.join("images/manifest.json"), "images"),
+            links: read_saved_list(&ticket_dir.join("links.json"), "links"),
+            pull_requests: read_saved_list(&ticket_dir.join("pull-requests.json"), "pull_requests"),
+            parents: metadata.parents,
+            created_date: metadata.created_date,
+            updated_date: metadata.updated_date,
+            created_by: metadata.created_by,
+            assigned_to: metadata.assigned_to,
+            state: metadata.state,
+            work_item_type: metadata.work_item_type,
+            area_path: metadata.area_path,
+            iteration_path: metadata.iteration_path,
+            iteration_start: metadata.iteration_start,
+            iteration_end: metadata.iteration_end,
+            tags: metadata.tags,
+            // Only the captured custom fields were saved; estimated effort needs them
+            fields: metadata.custom_fields.clone(),
+            custom_fields: metadata.custom_fields,
+        })
+    }
+
+    /// Every saved ticket folder: directories below `Tickets/` that hold a `metadata.json`
+    fn ticket_dirs(&self) -> Vec<PathBuf> {
+        let mut ticket_dirs = Vec::new();
+        let mut pending = vec![(PathBuf::from(&self.tickets_path), 0)];
+
+        while let Some((dir, depth)) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for path in entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()) {
+                if path.join("metadata.json").is_file() {
+                    ticket_dirs.push(path);
+                } else if depth + 1 < MAX_TICKET_DEPTH {
+                    pending.push((path, depth + 1));
+                }
+            }
+        }
+
+        ticket_dirs
+    }
+
+    /// Write `Tickets/index.md`: a table of every scraped work item, sorted by ID
+    ///
+    /// Built from each ticket folder's `metadata.json`; ones that can't be parsed
+    /// or have no ID are skipped with a warning. Returns the index path and the
+    /// number of work items listed.
+    pub fn write_index(&self) -> Result<(String, usize)> {
+        let mut rows: Vec<(u32, String, serde_json::Value)> = Vec::new();
+
+        for path in self.ticket_dirs() {
+            let metadata_path = path.join("metadata.json");
+            let metadata = fs::read_to_string(&metadata_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<serde_json::Value>(&content)?));
+
+            // Links in the index are relative to Tickets/
+            let folder = path
+                .strip_prefix(&self.tickets_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            match metadata {
+                Ok(metadata) if !metadata.is_object() => warn!("Skipping {} in index: not a JSON object", metadata_path.display()),
+                Ok(metadata) => match metadata["id"].as_u64() {
+                    Some(id) => rows.push((id as u32, folder, metadata)),
+                    None => warn!("Skipping {} in index: no work item ID", metadata_path.display()),
+                },
+                Err(e) => warn!("Skipping {} in index: {}", metadata_path.display(), e),
+            }
+        }
+
+        rows.sort_by_key(|(id, _, _)| *id);
+
+        // Titles may contain pipes or newlines, which would break the table
+        let cell = |value: &serde_json::Value| {
+            value.as_str().unwrap_or("").replace('|', "\\|").split_whitespace().collect::<Vec<_>>().join(" ")
+        };
+        let count = |metadata: &serde_json::Value, key: &str| {
+            metadata["stats"][key].as_u64().map(|n| n.to_string()).unwrap_or_default()
+        };
+
+        let mut index = String::from("# Tickets\n\n");
+        index.push_str("| ID | Title | State | Type | Attachments | Comments | Images |\n");
+        index.push_str("|---:|---|---|---|---:|---:|---:|\n");
+        for (id, folder, metadata) in &rows {
+            index.push_str(&format!("| [{id}]({folder}/description.md) | {} | {} | {} | {} | {} | {} |\n",
+                cell(&metadata["title"]),
+                cell(&metadata["state"]),
+                cell(&metadata["work_item_type"]),
+                count(metadata, "attachments_count"),
+                count(metadata, "comments_count"),
+                count(metadata, "images_count"),
+            ));
+        }
+
+        let index_path = format!("{}/index.md", self.tickets_path);
+        fs::write(&index_path, index)?;
+        debug!("Wrote index of {} tickets to {}", rows.len(), index_path);
+        Ok((index_path, rows.len()))
+    }
+
+    /// Save the OpenSpec prompt next to the ticket data so a failed generation can be re-run by hand
+    pub fn save_prompt(&self, ticket_path: &str, prompt: &str) -> Result<String> {
+        let prompt_path = format!("{}/openspec-prompt.txt", ticket_path);
+        fs::write(&prompt_path, prompt)?;
+        debug!("Saved {} char prompt to {}", prompt.len(), prompt_path);
+        Ok(prompt_path)
+    }
+
     fn replace_image_placeholders(&self, text: &str, images: &[ImageReference]) -> String {
+        // Duplicates point at the copy that was actually saved
+        self.replace_image_links(text, images, |image| {
+            format!("images/{}", image.alias_of.as_deref().unwrap_or(&image.placeholder))
+        })
+    }
+
+    /// Point downloaded images at their local copy, with `link` giving the path for each
+    ///
+    /// Runs on the original HTML: `<img>` tags become Markdown images, with the
+    /// dimensions as a title hint when known, and any other use of the URL is
+    /// replaced by the local path.
+    fn replace_image_links(&self, text: &str, images: &[ImageReference], link: impl Fn(&ImageReference) -> String) -> String {
         let mut processed_text = text.to_string();
 
         for image in images {
-            // Replace the original image URL with the placeholder
-            processed_text = processed_text.replace(&image.original_url, &format!("images/{}", image.placeholder));
+            // Images that weren't downloaded keep pointing at their original URL
+            if image.local_path.is_empty() {
+                continue;
+            }
 
-            // Also replace any remaining HTML img tags with markdown
-            processed_text = regex::Regex::new(&format!(r#"<img[^>]*src="{}"[^>]*>"#, regex::escape(&image.original_url)))
+            let file = link(image);
+
+            // Include the dimensions as a title hint when we know them
+            let size_hint = match (image.width, image.height) {
+                (Some(width), Some(height)) => format!(" \"{}x{}\"", width, height),
+                _ => String::new(),
+            };
+
+            // In HTML the URL's `&` is usually written as `&amp;`
+            let escaped_url = image.original_url.replace('&', "&amp;");
+
+            // Turn the img tags into markdown first, while their src still holds the URL
+            let img_tag = format!(r#"<img[^>]*src=["'](?:{}|{})["'][^>]*>"#,
+                regex::escape(&image.original_url), regex::escape(&escaped_url));
+            processed_text = regex::Regex::new(&img_tag)
                 .unwrap()
-                .replace_all(&processed_text, format!("![{}]({})",
+                .replace_all(&processed_text, regex::NoExpand(&format!("![{}]({}{})",
                     image.alt_text.as_deref().unwrap_or("image"),
-                    format!("images/{}", image.placeholder)
-                ))
+                    file,
+                    size_hint
+                )))
                 .to_string();
+
+            // Then any other mention of the URL, e.g. a link to the full-size image
+            processed_text = processed_text.replace(&escaped_url, &file).replace(&image.original_url, &file);
         }
 
         processed_text
@@ -254,27 +858,96 @@ impl FileSystemOrganizer {
     pub fn generate_openspec_plan_data(&self, work_item: &WorkItem) -> OpenSpecPlanData {
         // Debug log the raw description from API
         info!("Raw API description length: {} chars", work_item.description.len());
-        info!("Raw API description preview: {}", &work_item.description[..work_item.description.len().min(100)]);
+        info!("Raw API description preview: {}", work_item.description.chars().take(100).collect::<String>());
 
-        let cleaned_description = clean_html_content(&work_item.description);
+        let cleaned_description = self.clean_text(&work_item.description);
         info!("Cleaned description length: {} chars", cleaned_description.len());
-        info!("Cleaned description preview: {}", &cleaned_description[..cleaned_description.len().min(100)]);
+        info!("Cleaned description preview: {}", cleaned_description.chars().take(100).collect::<String>());
 
         OpenSpecPlanData {
             ticket_number: work_item.id,
             ticket_title: work_item.title.clone(),
             ticket_description: cleaned_description,
-            acceptance_criteria: work_item.acceptance_criteria.clone(),
+            acceptance_criteria: work_item.acceptance_criteria.iter().map(|ac| self.clean_text(ac)).collect(),
             priority: self.extract_priority(&work_item.area_path),
             complexity: self.estimate_complexity(work_item),
-            dependencies: self.extract_dependencies(&work_item.description),
-            estimated_effort: None, // Would need additional logic or API call
+            dependencies: self.extract_dependencies(work_item),
+            estimated_effort: self.extract_estimated_effort(work_item),
+            iteration: iteration_summary(work_item),
             attachments_count: work_item.attachments.len(),
             comments_count: work_item.comments.len(),
+            recent_comments: self.recent_comments(work_item),
+            parent_context: self.parent_context(work_item),
             has_images: !work_item.images.is_empty(),
+            openspec_dir: self.openspec_subdir.clone(),
+        }
+    }
+
+    /// Work item text as plain Markdown: HTML is cleaned, saved Markdown is kept as is
+    fn clean_text(&self, text: &str) -> String {
+        if self.markdown_content {
+            text.trim().to_string()
+        } else {
+            clean_html_content(text)
         }
     }
 
+    /// One prompt line per ancestor, nearest first, with its description cut to `MAX_PARENT_SUMMARY_CHARS`
+    fn parent_context(&self, work_item: &WorkItem) -> Vec<String> {
+        work_item.parents
+            .iter()
+            .map(|parent| {
+                let summary = clean_html_content(&parent.description).split_whitespace().collect::<Vec<_>>().join(" ");
+                if summary.is_empty() {
+                    format!("{} #{}: {}", parent.work_item_type, parent.id, parent.title)
+                } else {
+                    format!("{} #{}: {} - {}", parent.work_item_type, parent.id, parent.title,
+                        crate::ui::truncate_text(&summary, MAX_PARENT_SUMMARY_CHARS))
+                }
+            })
+            .collect()
+    }
+
+    /// The latest comments as prompt lines, oldest first
+    ///
+    /// Newer comments win when the total would exceed `MAX_DISCUSSION_CHARS`;
+    /// a single oversized comment is cut to fit.
+    fn recent_comments(&self, work_item: &WorkItem) -> Vec<String> {
+        let mut comments: Vec<&Comment> = work_item.comments.iter().collect();
+        comments.sort_by_key(|comment| comment.created_date);
+
+        let mut selected = Vec::new();
+        let mut total_chars = 0;
+
+        for comment in comments.iter().rev().take(self.prompt_comments) {
+            let text = self.clean_text(&comment.text).split_whitespace().collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                continue;
+            }
+
+            let line = format!("{} ({}): {}",
+                comment.author.display_name,
+                comment.created_date.format("%Y-%m-%d"),
+                text
+            );
+            let remaining = MAX_DISCUSSION_CHARS - total_chars;
+            let line_chars = line.chars().count();
+
+            if line_chars <= remaining {
+                total_chars += line_chars;
+                selected.push(line);
+            } else {
+                if selected.is_empty() {
+                    selected.push(crate::ui::truncate_text(&line, remaining));
+                }
+                break;
+            }
+        }
+
+        selected.reverse();
+        selected
+    }
+
     fn strip_html(&self, text: &str) -> String {
         // Remove HTML tags while preserving some formatting
         let clean_text = regex::Regex::new(r#"<[^>]*>"#)
@@ -303,6 +976,34 @@ impl FileSystemOrganizer {
         }
     }
 
+    fn extract_estimated_effort(&self, work_item: &WorkItem) -> Option<String> {
+        let field_number = |name: &str| -> Option<f64> {
+            let value = work_item.fields.get(name)?;
+            value.as_f64().or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+        };
+
+        // Drop the decimal part for whole numbers ("5" rather than "5.0")
+        let format_number = |value: f64| -> String {
+            if value.fract() == 0.0 {
+                format!("{}", value as i64)
+            } else {
+                format!("{}", value)
+            }
+        };
+
+        let story_points = field_number("Microsoft.VSTS.Scheduling.StoryPoints");
+        let effort = field_number("Microsoft.VSTS.Scheduling.Effort");
+
+        match (story_points, effort) {
+            (Some(points), Some(effort)) => Some(format!("{} story points (effort: {})",
+                format_number(points), format_number(effort))),
+            (Some(points), None) => Some(format!("{} story point{}",
+                format_number(points), if points == 1.0 { "" } else { "s" })),
+            (None, Some(effort)) => Some(format!("{} effort", format_number(effort))),
+            (None, None) => None,
+        }
+    }
+
     fn estimate_complexity(&self, work_item: &WorkItem) -> String {
         let description_length = work_item.description.len();
         let acceptance_criteria_count = work_item.acceptance_criteria.len();
@@ -320,17 +1021,217 @@ impl FileSystemOrganizer {
         }
     }
 
-    fn extract_dependencies(&self, description: &str) -> Vec<String> {
-        let mut dependencies = Vec::new();
+    /// Work items this one depends on: Related and Dependency links, plus
+    /// `#1234` description mentions when enabled
+    fn extract_dependencies(&self, work_item: &WorkItem) -> Vec<String> {
+        let mut ids: Vec<u32> = work_item
+            .links
+            .iter()
+            .filter(|link| {
+                link.link_type == "System.LinkTypes.Related"
+                    || link.link_type.starts_with("System.LinkTypes.Dependency")
+            })
+            .map(|link| link.id)
+            .collect();
 
-        // Look for work item references in the description
-        let work_item_regex = regex::Regex::new(r"#(\d+)").unwrap();
-        for cap in work_item_regex.captures_iter(description) {
-            if let Some(id_match) = cap.get(1) {
-                dependencies.push(format!("Work Item #{}", id_match.as_str()));
-            }
+        if self.description_dependencies {
+            let work_item_regex = regex::Regex::new(r"#(\d+)").unwrap();
+            ids.extend(
+                work_item_regex
+                    .captures_iter(&work_item.description)
+                    .filter_map(|cap| cap.get(1)?.as_str().parse::<u32>().ok())
+                    .filter(|id| *id != work_item.id)
+            );
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        ids.into_iter()
+            .filter(|id| seen.insert(*id))
+            .map(|id| format!("Work Item #{}", id))
+            .collect()
+    }
+}
+
+/// The iteration's last path segment with its dates, or `None` when the iteration has no dates
+fn iteration_summary(work_item: &WorkItem) -> Option<String> {
+    let name = work_item.iteration_path.rsplit('\\').next().unwrap_or_default();
+    let format_date = |date: &chrono::DateTime<chrono::Utc>| date.format("%Y-%m-%d").to_string();
+    let dates = match (&work_item.iteration_start, &work_item.iteration_end) {
+        (Some(start), Some(end)) => format!("{} to {}", format_date(start), format_date(end)),
+        (Some(start), None) => format!("starts {}", format_date(start)),
+        (None, Some(end)) => format!("ends {}", format_date(end)),
+        (None, None) => return None,
+    };
+    Some(format!("{} ({})", name, dates))
+}
+
+/// The `key` array of a saved JSON file, or nothing when the file is missing or unreadable
+fn read_saved_list<T: DeserializeOwned>(path: &Path, key: &str) -> Vec<T> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|mut saved| serde_json::from_value(saved[key].take()).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn work_item(description: &str) -> WorkItem {
+        let date = chrono::DateTime::parse_from_rfc3339("2024-06-03T10:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        WorkItem {
+            id: 42,
+            revision: 1,
+            title: "Speed up login".to_string(),
+            description: description.to_string(),
+            acceptance_criteria: Vec::new(),
+            comments: Vec::new(),
+            attachments: Vec::new(),
+            images: Vec::new(),
+            links: Vec::new(),
+            pull_requests: Vec::new(),
+            parents: Vec::new(),
+            created_date: date,
+            updated_date: date,
+            created_by: User { display_name: "Ada".to_string(), email: "ada@example.com".to_string(), url: String::new() },
+            assigned_to: None,
+            state: "Active".to_string(),
+            work_item_type: "User Story".to_string(),
+            area_path: "Project".to_string(),
+            iteration_path: "Project".to_string(),
+            iteration_start: None,
+            iteration_end: None,
+            tags: Vec::new(),
+            custom_fields: HashMap::new(),
+            fields: HashMap::new(),
         }
+    }
 
-        dependencies
+    fn image(url: &str) -> ImageReference {
+        ImageReference {
+            placeholder: "image_001.png".to_string(),
+            original_url: url.to_string(),
+            local_path: "Tickets/42/images/image_001.png".to_string(),
+            width: Some(800),
+            height: Some(600),
+            alt_text: Some("Mockup".to_string()),
+            alias_of: None,
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn img_tag_becomes_markdown_with_size_hint() {
+        let url = "https://dev.azure.com/org/_apis/wit/attachments/1?fileName=mock.png&download=false";
+        let html = r#"<p>See <img src="https://dev.azure.com/org/_apis/wit/attachments/1?fileName=mock.png&amp;download=false" alt="Mockup"></p>"#;
+
+        let organizer = FileSystemOrganizer::new("unused");
+        assert_eq!(
+            organizer.replace_image_placeholders(html, &[image(url)]),
+            r#"<p>See ![Mockup](images/image_001.png "800x600")</p>"#
+        );
+    }
+
+    #[test]
+    fn description_md_links_images_with_size_hint() {
+        let url = "https://dev.azure.com/org/_apis/wit/attachments/1?fileName=mock.png";
+        let mut item = work_item(&format!(r#"<div><p>Layout:</p><p><img src="{}" alt="Mockup"></p></div>"#, url));
+        item.images = vec![image(url)];
+
+        let markdown = FileSystemOrganizer::new("unused").description_markdown(&item);
+        assert!(markdown.ends_with("## Description\n\nLayout:\n![Mockup](images/image_001.png \"800x600\")"), "{}", markdown);
+    }
+
+    #[test]
+    fn work_item_md_links_description_and_comment_images() {
+        let dir = std::env::temp_dir().join(format!("bakery_work_item_md_{}", std::process::id()));
+        let ticket_path = dir.to_string_lossy().to_string();
+        fs::create_dir_all(&dir).unwrap();
+
+        let description_url = "https://dev.azure.com/org/_apis/wit/attachments/1?fileName=mock.png";
+        let comment_url = "https://dev.azure.com/org/_apis/wit/attachments/2?fileName=log.png";
+        let mut item = work_item(&format!(r#"<p><img src="{}" alt="Mockup"></p>"#, description_url));
+        item.images = vec![ImageReference {
+            local_path: format!("{}/images/image_001.png", ticket_path),
+            ..image(description_url)
+        }];
+        item.comments = vec![Comment {
+            id: 1,
+            text: format!(r#"<div>Error: <img src="{}"></div>"#, comment_url),
+            author: item.created_by.clone(),
+            created_date: item.created_date,
+            updated_date: None,
+            images: vec![ImageReference {
+                local_path: format!("{}/images/comment_1/image_001.png", ticket_path),
+                width: None,
+                height: None,
+                alt_text: None,
+                ..image(comment_url)
+            }],
+        }];
+
+        FileSystemOrganizer::new("unused").save_combined_markdown(&item, &ticket_path).unwrap();
+        let content = fs::read_to_string(dir.join("work-item.md")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(content.contains("## Description\n\n![Mockup](images/image_001.png \"800x600\")\n"), "{}", content);
+        assert!(content.contains("Error: ![image](images/comment_1/image_001.png)\n"), "{}", content);
+        assert!(!content.contains("dev.azure.com"), "{}", content);
+    }
+
+    #[tokio::test]
+    async fn metadata_is_written_whatever_the_artifacts() {
+        let dir = std::env::temp_dir().join(format!("bakery_artifacts_{}", std::process::id()));
+        let organizer = FileSystemOrganizer::new(&dir.to_string_lossy()).with_artifacts(vec!["description".to_string()]);
+
+        let ticket_path = organizer.save_work_item(&work_item("<p>Hello</p>")).await.unwrap();
+        let saved_revision = organizer.saved_revision(42);
+        let loaded = organizer.load_work_item(42).map(|item| item.title);
+        let written = Path::new(&ticket_path).join("acceptance-criteria.md").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(saved_revision, Some(1));
+        assert_eq!(loaded.unwrap(), "Speed up login");
+        assert!(!written);
+    }
+
+    #[test]
+    fn openspec_subdir_is_honored_end_to_end() {
+        let dir = std::env::temp_dir().join(format!("bakery_subdir_{}", std::process::id()));
+        let base = dir.to_string_lossy().to_string();
+
+        let organizer = FileSystemOrganizer::new(&base).with_openspec_subdir("specs/");
+        organizer.ensure_base_structure().unwrap();
+        let prompt = organizer.generate_openspec_plan_data(&work_item("<p>Hi</p>")).generate_prompt(0, None);
+
+        let manager = crate::openspec::OpenSpecManager::new(&base, "specs");
+        let plan = "## Why\nLogin is slow.\n\n## What Changes\n- Cache sessions\n\n## Tasks\n- [ ] 1.1 Add cache\n";
+        let change_dir = manager.create_feature_plan_file("add", 42, "Speed up login", plan).unwrap();
+        let changes = manager.changes_for_ticket(42);
+        let default_dir_exists = dir.join("openspec").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(change_dir, format!("{}/specs/changes/add-42-speed-up-login", base));
+        assert_eq!(changes, vec![PathBuf::from(&change_dir)]);
+        assert!(!default_dir_exists);
+        assert!(prompt.contains("Scaffold all files under specs/changes/[change-id]/"));
+        assert!(!prompt.contains("openspec/changes/"));
+    }
+
+    #[test]
+    fn saved_markdown_is_not_cleaned_as_html() {
+        let mut item = work_item("Use Vec<u32> when a < b");
+        item.acceptance_criteria = vec!["Returns Option<T>".to_string()];
+
+        let plan = FileSystemOrganizer::new("unused").with_markdown_content(true).generate_openspec_plan_data(&item);
+        assert_eq!(plan.ticket_description, "Use Vec<u32> when a < b");
+        assert_eq!(plan.acceptance_criteria, vec!["Returns Option<T>"]);
+    }
+
+    #[test]
+    fn fetched_html_is_cleaned() {
+        let item = work_item("<div><p>Use Vec&lt;u32&gt;</p></div>");
+        let plan = FileSystemOrganizer::new("unused").generate_openspec_plan_data(&item);
+        assert_eq!(plan.ticket_description, "Use Vec<u32>");
+    }
+}
@@ -13,23 +13,32 @@
 
 use anyhow::Result;
 use clap::Parser;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 use colored::Colorize;
+use std::collections::HashMap;
 
 // Module declarations
+mod ai;
 mod api;
 mod config;
+mod credentials;
+mod events;
 mod filesystem;
 mod models;
 mod openspec;
+mod redact;
 mod ui;
 
 // Re-exports for cleaner imports
-use api::AzureDevOpsClient;
-use config::BakeryConfig;
-use filesystem::FileSystemOrganizer;
-use openspec::OpenSpecManager;
-use ui::{Terminal, Theme, OutputMode, Dashboard, Card, Badge, Progress};
+use api::{AzureAuth, AzureDevOpsClient, HttpClientOptions};
+use config::{AuthMethod, BakeryConfig};
+use filesystem::{FileSystemOrganizer, TicketPathTemplate};
+use events::Event;
+use openspec::{OpenSpecManager, ValidationOutcome};
+use ui::{Terminal, Theme, ThemePalette, OutputMode, Dashboard, Card, Table, Badge, Progress};
+
+/// Most changed description lines shown in verbose output; the rest are only counted
+const MAX_DIFF_LINES: usize = 20;
 
 #[derive(Parser)]
 #[command(name = "bakery")]
@@ -40,84 +49,1869 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// The Azure DevOps work item ID to scrape
-    #[arg(short = 't', long)]
-    ticket_id: Option<u32>,
+    /// The Azure DevOps work item ID(s) to scrape (repeatable or comma-separated)
+    #[arg(short = 't', long = "ticket-id", visible_alias = "ticket-ids", value_delimiter = ',')]
+    ticket_ids: Vec<u32>,
+
+    /// Work item IDs and ranges to scrape, e.g. 100-110,120,130-132
+    #[arg(long, value_name = "LIST")]
+    tickets: Option<String>,
+
+    /// WIQL query whose results should be scraped (e.g. "SELECT [System.Id] FROM WorkItems WHERE ...")
+    #[arg(long, conflicts_with = "saved_query")]
+    query: Option<String>,
+
+    /// ID of a saved Azure DevOps query whose results should be scraped
+    #[arg(long)]
+    saved_query: Option<String>,
+
+    /// Fetch this revision of the work item instead of the latest (single ticket ID only)
+    #[arg(long, value_name = "N", conflicts_with_all = ["query", "saved_query"])]
+    revision: Option<u32>,
+
+    /// Azure DevOps organization name (overrides config)
+    #[arg(long)]
+    organization: Option<String>,
+
+    /// Azure DevOps project name (overrides config)
+    #[arg(long)]
+    project: Option<String>,
+
+    /// Personal Access Token for authentication (overrides config)
+    #[arg(long)]
+    pat_token: Option<String>,
+
+    /// Seconds to wait for each API call, 0 for no timeout (overrides config)
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    /// Base directory for storing tickets (overrides config)
+    #[arg(long)]
+    base_directory: Option<String>,
+
+    /// Write this run's tickets and openspec folders here, ignoring base_directory and local_baking
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<String>,
+
+    /// Record attachments in the manifest without downloading them
+    #[arg(long)]
+    no_attachments: bool,
+
+    /// Record embedded images in the manifest without downloading them
+    #[arg(long)]
+    no_images: bool,
+
+    /// Don't fetch comments (faster, and keeps them out of the AI prompt)
+    #[arg(long)]
+    no_comments: bool,
+
+    /// Only keep comments created on or after this date (2024-01-01) or this long ago (7d, 12h, 2w)
+    #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Skip OpenSpec plan generation
+    #[arg(long)]
+    no_openspec: bool,
+
+    /// Fetch and save the work item and build the prompt, but don't call the AI or create the change
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Also fetch the work item's parents up to the root and add their titles and descriptions to the prompt
+    #[arg(long)]
+    include_parents: bool,
+
+    /// Re-fetch work items even when the saved copy is already at the current revision
+    #[arg(long)]
+    force: bool,
+
+    /// Open the created OpenSpec change directory in the file explorer
+    #[arg(long)]
+    open: bool,
+
+    /// Comment on the work item with the path of the created OpenSpec change (needs a PAT with write scope)
+    #[arg(long)]
+    append_comment: bool,
+
+    /// Verb for the change ID (e.g. fix, update, refactor); by default taken from the AI's plan or the work item type
+    #[arg(long, value_name = "VERB", value_parser = parse_change_verb)]
+    change_verb: Option<String>,
+
+    /// Also write the OpenSpec prompt to this file ({id} is replaced with the work item ID)
+    #[arg(long, value_name = "PATH")]
+    dump_prompt: Option<String>,
+
+    /// Enable verbose logging
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Print machine-readable output and exit (for LLM integration)
+    #[arg(short, long)]
+    print: bool,
+
+    /// Write one JSON object per line to stdout for each pipeline step (for wrapper processes)
+    #[arg(long, conflicts_with_all = ["print", "format", "verbose"])]
+    events: bool,
+
+    /// Write the fetched work item(s) to stdout as JSON or YAML instead of saving them
+    #[arg(long, value_enum, conflicts_with_all = ["print", "dry_run"])]
+    format: Option<OutputFormat>,
+
+    /// Enable rich output mode with maximum visual features
+    #[arg(long)]
+    rich: bool,
+
+    /// Enable compact output mode with minimal information
+    #[arg(long)]
+    compact: bool,
+
+    /// Disable colors in output
+    #[arg(long)]
+    no_color: bool,
+
+    /// Color palette: dark (default), light or high-contrast (overrides config)
+    #[arg(long, value_enum, value_name = "PALETTE")]
+    theme: Option<ThemePalette>,
+
+    /// Also write a debug log to this file, rotated daily (overrides config)
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<String>,
+
+    /// Config profile to use from ~/.bakery/profiles (or set BAKERY_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+}
+
+/// Serialization for `--format`
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum OutputFormat {
+    Json,
+    Yaml,
+}
+
+impl Cli {
+    /// Whether decorative terminal output should be suppressed
+    fn quiet(&self) -> bool {
+        self.print || self.events
+    }
+
+    /// Emit a progress event when running with `--events`
+    fn emit(&self, event: Event) {
+        if self.events {
+            events::emit(&event);
+        }
+    }
+}
+
+#[derive(Parser)]
+enum Commands {
+    /// Set up a config file by answering a few questions
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Open Bakery configuration file
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+
+        /// List available config profiles instead of opening the editor
+        #[arg(long)]
+        list_profiles: bool,
+
+        /// Show the fully resolved configuration (after CLI, env and profile overrides)
+        #[arg(long)]
+        show: bool,
+    },
+    /// Check that the organization, project and credentials work
+    TestConnection,
+    /// List active OpenSpec changes
+    List,
+    /// Regenerate Tickets/index.md from the scraped tickets
+    Index,
+    /// Move a finished OpenSpec change to openspec/changes/archive/<date>-<change-id>
+    Archive {
+        /// Change ID to archive (e.g. add-12345-feature-name)
+        change_id: String,
+    },
+    /// Show a previously scraped ticket from its saved files (no network access)
+    View {
+        /// Work item ID to show
+        ticket_id: u32,
+    },
+    /// Regenerate the OpenSpec plan for a previously scraped ticket (no Azure DevOps access)
+    Replan {
+        /// Work item ID to plan again
+        ticket_id: u32,
+    },
+    /// Delete a scraped ticket folder, and optionally its OpenSpec change
+    Clean {
+        /// Work item ID whose folder should be removed
+        ticket_id: u32,
+
+        /// Also remove the active OpenSpec change(s) created for the ticket
+        #[arg(long)]
+        with_change: bool,
+
+        /// Don't ask for confirmation
+        #[arg(long)]
+        force: bool,
+
+        /// Only list what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print a shell completion script to stdout, to be sourced or saved to your shell's completions directory
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate the script for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Parser)]
+enum ConfigAction {
+    /// Store the PAT in the OS keyring instead of the config file
+    SetToken {
+        /// Organization the token belongs to (defaults to the configured one)
+        #[arg(long)]
+        organization: Option<String>,
+    },
+}
+
+/// Stage of a run that failed, which decides the process exit code
+///
+/// | Code | Meaning |
+/// |------|---------|
+/// | 0 | Success |
+/// | 1 | Any other error (bad arguments, file system, ...) |
+/// | 2 | Configuration or authentication error |
+/// | 3 | A work item could not be fetched |
+/// | 4 | AI / OpenSpec plan generation failed |
+/// | 5 | The generated OpenSpec change failed validation |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum FailureKind {
+    Config = 2,
+    Fetch = 3,
+    Generation = 4,
+    Validation = 5,
+}
+
+impl FailureKind {
+    /// Tag an error with this stage so `main` exits with the matching code
+    fn wrap(self, error: anyhow::Error) -> anyhow::Error {
+        Failure { kind: self, error }.into()
+    }
+}
+
+/// An error tagged with the stage that produced it; displays as the inner error
+#[derive(Debug)]
+struct Failure {
+    kind: FailureKind,
+    error: anyhow::Error,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for Failure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Stage an error belongs to, if it was tagged; a rejected token always counts as an auth error
+fn failure_kind(error: &anyhow::Error) -> Option<FailureKind> {
+    let (kind, inner) = match error.downcast_ref::<Failure>() {
+        Some(failure) => (Some(failure.kind), &failure.error),
+        None => (None, error),
+    };
+    match inner.downcast_ref::<api::ApiError>() {
+        Some(api::ApiError::AccessDenied { .. }) => Some(FailureKind::Config),
+        Some(api::ApiError::WorkItemNotFound { .. }) => Some(FailureKind::Fetch),
+        None => kind,
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    if let Err(e) = run(cli).await {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(failure_kind(&e).map_or(1, |kind| kind as i32));
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let profile = BakeryConfig::resolve_profile(cli.profile.clone());
+
+    // Initialize logging; the guard flushes the log file when run() returns
+    let log_file = cli.log_file.clone().or_else(|| configured_log_file(profile.as_deref()));
+    let _log_guard = init_logging(cli.verbose, cli.format.is_some() || cli.events, log_file.as_deref());
+
+    // Handle subcommands early (before loading config for better UX)
+    if let Some(command) = &cli.command {
+        match command {
+            Commands::Init { force } => {
+                return handle_init_command(&cli, profile.as_deref(), *force).await;
+            }
+            Commands::Config { list_profiles: true, .. } => {
+                return handle_list_profiles_command(profile.as_deref());
+            }
+            Commands::Config { show: true, .. } => {
+                return handle_show_config_command(&cli, profile.as_deref());
+            }
+            Commands::Config { action: None, .. } => {
+                return handle_config_command(profile.as_deref());
+            }
+            Commands::Config { action: Some(ConfigAction::SetToken { organization }), .. } => {
+                return handle_set_token_command(profile.as_deref(), organization.clone());
+            }
+            Commands::TestConnection => {
+                return handle_test_connection_command(&cli, profile.as_deref()).await;
+            }
+            Commands::List => {
+                return handle_list_command(&cli, profile.as_deref());
+            }
+            Commands::Index => {
+                return handle_index_command(&cli, profile.as_deref());
+            }
+            Commands::Archive { change_id } => {
+                return handle_archive_command(&cli, profile.as_deref(), change_id);
+            }
+            Commands::View { ticket_id } => {
+                return handle_view_command(&cli, profile.as_deref(), *ticket_id);
+            }
+            Commands::Replan { ticket_id } => {
+                return handle_replan_command(&cli, profile.as_deref(), *ticket_id).await;
+            }
+            Commands::Clean { ticket_id, with_change, force, dry_run } => {
+                return handle_clean_command(&cli, profile.as_deref(), *ticket_id, *with_change, *force, *dry_run);
+            }
+            Commands::Completions { shell } => {
+                clap_complete::generate(*shell, &mut <Cli as clap::CommandFactory>::command(), "bakery", &mut std::io::stdout());
+                return Ok(());
+            }
+        }
+    }
+
+    // Require at least one ticket ID or query for main functionality
+    if cli.ticket_ids.is_empty() && cli.tickets.is_none() && cli.query.is_none() && cli.saved_query.is_none() {
+        return Err(anyhow::anyhow!("{}: Ticket ID is required. Use {}, {}, {} or run '{}' to open configuration",
+            "Error".red().bold(),
+            "-t <TICKET_ID>".yellow(),
+            "--tickets <IDS>".yellow(),
+            "--query <WIQL>".yellow(),
+            "bakery config".yellow()));
+    }
+
+    // Load configuration
+    let mut config = load_config(profile.as_deref())?;
+
+    let mut ticket_ids = cli.ticket_ids.clone();
+    if let Some(spec) = &cli.tickets {
+        let ranged_ids = parse_ticket_ranges(spec, config.azure_devops.max_ticket_range)
+            .map_err(|e| FailureKind::Config.wrap(anyhow::anyhow!("{}: Invalid {}: {}",
+                "Error".red().bold(),
+                "--tickets".yellow(),
+                e)))?;
+        for id in ranged_ids {
+            if !ticket_ids.contains(&id) {
+                ticket_ids.push(id);
+            }
+        }
+    }
+    if cli.revision.is_some() && ticket_ids.len() != 1 {
+        return Err(anyhow::anyhow!("{}: {} needs exactly one ticket ID",
+            "Error".red().bold(),
+            "--revision".yellow()));
+    }
+
+    let output_mode = resolve_output_mode(&cli, &config);
+
+    // Initialize UI components
+    let terminal = Terminal::detect();
+    let theme = Theme::new(output_mode, terminal.clone())
+        .with_palette(resolve_palette(&cli, &config));
+    let dashboard = Dashboard::new(theme.clone(), terminal.clone());
+    let card = Card::new(theme.clone(), terminal.clone());
+    let badge = Badge::new(theme.clone());
+    let progress = Progress::new(theme.clone());
+
+    apply_cli_overrides(&cli, &mut config);
+
+    // Catch placeholder/missing settings before the first API call
+    if let Err(e) = config.validate() {
+        dashboard.render_error(
+            "Invalid configuration",
+            &e.to_string(),
+            Some("Run 'bakery config' to fix these settings")
+        );
+        return Err(FailureKind::Config.wrap(e));
+    }
+
+    let prompt_template = config
+        .openspec
+        .prompt_template_path
+        .as_deref()
+        .map(models::PromptTemplate::load)
+        .transpose()
+        .map_err(|e| FailureKind::Config.wrap(e))?;
+
+    // Initialize components
+    let output_directory = resolve_output_directory(&cli, &config);
+    let filesystem = FileSystemOrganizer::new(&output_directory)
+        .with_openspec_subdir(&config.storage.openspec_subdir)
+        .with_description_dependencies(config.openspec.dependencies_from_description)
+        .with_raw_html(config.storage.keep_raw_html)
+        .with_combined_markdown(config.storage.combined_markdown)
+        .with_artifacts(config.storage.artifacts.clone())
+        .with_prompt_comments(config.openspec.prompt_comments)
+        .with_ticket_path_template(TicketPathTemplate::parse(&config.storage.ticket_path_template)?);
+    let client = build_client(&cli, &config, filesystem.tickets_path(), &theme)
+        .map_err(|e| FailureKind::Config.wrap(e))?;
+
+    // Resolve query results into ticket IDs
+    let query_ids = if let Some(wiql) = &cli.query {
+        if cli.format.is_none() && !cli.events {
+            progress.status("🔄", "Running WIQL query...");
+        }
+        Some(client.run_query(wiql).await)
+    } else if let Some(query_id) = &cli.saved_query {
+        if cli.format.is_none() && !cli.events {
+            progress.status("🔄", &format!("Running saved query {}...", query_id));
+        }
+        Some(client.run_saved_query(query_id).await)
+    } else {
+        None
+    };
+
+    if let Some(query_ids) = query_ids {
+        let query_ids = match query_ids {
+            Ok(ids) => ids,
+            Err(e) => {
+                // Structured output keeps stdout clean; main reports the error on stderr
+                if cli.format.is_none() && !cli.events {
+                    dashboard.render_error(
+                        "Failed to run query",
+                        &e.to_string(),
+                        Some("Check the WIQL syntax or saved query ID, and that your PAT can read queries")
+                    );
+                }
+                return Err(FailureKind::Fetch.wrap(e));
+            }
+        };
+
+        for id in query_ids {
+            if !ticket_ids.contains(&id) {
+                ticket_ids.push(id);
+            }
+        }
+
+        if ticket_ids.is_empty() {
+            if cli.format.is_some() {
+                // An empty list is valid JSON and YAML alike
+                println!("[]");
+            } else if !cli.events {
+                progress.status("⚠️", "Query returned no work items");
+            }
+            return Ok(());
+        }
+    }
+
+    // Structured output replaces the whole save/generate pipeline
+    if let Some(format) = cli.format {
+        return print_work_items(&client, &ticket_ids, cli.revision, format).await;
+    }
+
+    let ticket_list = ticket_ids
+        .iter()
+        .map(|id| format!("#{}", id))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if cli.verbose {
+        card.render_header(
+            &format!("🚀 Starting Bakery v{}", env!("CARGO_PKG_VERSION")),
+            &format!("Azure DevOps scraper for ticket {}", ticket_list)
+        );
+
+        card.render_two_column(vec![
+            ("Organization", config.azure_devops.organization.clone()),
+            ("Project", config.azure_devops.project.clone()),
+            ("Storage", if cli.output_dir.is_some() {
+                output_directory.clone()
+            } else if config.storage.local_baking {
+                "Local baking enabled".to_string()
+            } else {
+                config.get_effective_base_directory()
+            }),
+        ]);
+    } else if !cli.quiet() {
+        // Concise output for normal mode (skip in print/events mode)
+        let label = if ticket_ids.len() == 1 { "work item" } else { "work items" };
+        progress.status("🔄", &format!("Fetching {} {}...", label, ticket_list));
+    }
+
+    let openspec_manager = OpenSpecManager::new(&output_directory, &config.storage.openspec_subdir)
+        .with_http_options(http_options(&config));
+
+    // Ensure directory structure exists
+    filesystem.ensure_base_structure()?;
+
+    let ctx = BakeContext {
+        cli: &cli,
+        config: &config,
+        filesystem: &filesystem,
+        openspec_manager: &openspec_manager,
+        theme: &theme,
+        dashboard: &dashboard,
+        card: &card,
+        badge: &badge,
+        progress: &progress,
+        prompt_template: prompt_template.as_ref(),
+        client: Some(&client),
+    };
+
+    cli.emit(Event::FetchStarted { work_item_ids: &ticket_ids });
+
+    // Work items whose saved copy is still current are reported, not re-fetched
+    let up_to_date = if cli.force || cli.revision.is_some() || cli.since.is_some() || cli.dry_run {
+        HashMap::new()
+    } else {
+        find_up_to_date(&client, &filesystem, &ticket_ids).await
+    };
+    for ticket_id in &ticket_ids {
+        if let Some(&revision) = up_to_date.get(ticket_id) {
+            report_up_to_date(&cli, &progress, *ticket_id, revision);
+        }
+    }
+    let fetch_ids: Vec<u32> = ticket_ids.iter().copied().filter(|id| !up_to_date.contains_key(id)).collect();
+
+    // Fetch work items in batch-sized chunks, then save each one independently
+    let mut failed: Vec<(u32, anyhow::Error)> = Vec::new();
+    for chunk in fetch_ids.chunks(api::MAX_BATCH_SIZE) {
+        for (ticket_id, result) in fetch_work_items(&client, chunk, cli.revision).await {
+            let outcome = match result {
+                Ok(mut work_item) => {
+                    if cli.include_parents {
+                        work_item.parents = client.get_parent_chain(&work_item).await;
+                    }
+                    bake_work_item(&ctx, &work_item).await
+                }
+                Err(e) => {
+                    if !cli.events {
+                        render_fetch_error(&dashboard, ticket_id, &e, cli.verbose);
+                    }
+                    Err(FailureKind::Fetch.wrap(e))
+                }
+            };
+
+            if let Err(e) = outcome {
+                cli.emit(Event::WorkItemFailed { work_item_id: ticket_id, error: e.to_string() });
+                failed.push((ticket_id, e));
+            }
+        }
+    }
+
+    // Unchanged tickets are not fetched again, but one without a change yet
+    // (after --dry-run or a failed AI run) still gets its plan from the saved files
+    let saved_filesystem = filesystem.clone().with_markdown_content(true);
+    let saved_ctx = BakeContext { filesystem: &saved_filesystem, ..ctx };
+    for ticket_id in ticket_ids.iter().copied().filter(|id| up_to_date.contains_key(id)) {
+        if let Err(e) = plan_up_to_date(&saved_ctx, ticket_id).await {
+            cli.emit(Event::WorkItemFailed { work_item_id: ticket_id, error: e.to_string() });
+            failed.push((ticket_id, e));
+        }
+    }
+
+    if ticket_ids.len() > 1 && !cli.events {
+        print_batch_summary(ticket_ids.len(), &failed, cli.print);
+    }
+
+    // Keep the overview of everything scraped so far up to date
+    if ticket_ids.len() > 1 {
+        match filesystem.write_index() {
+            Ok((index_path, _)) if !cli.quiet() => progress.status("📇", &format!("Updated {}", index_path)),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Could not update the ticket index: {}", e),
+        }
+    }
+
+    cli.emit(Event::Done {
+        total: ticket_ids.len(),
+        succeeded: ticket_ids.len() - failed.len(),
+        failed: failed.len(),
+    });
+
+    match failed.len() {
+        0 => Ok(()),
+        1 if ticket_ids.len() == 1 => Err(failed.remove(0).1),
+        count => {
+            let error = anyhow::anyhow!("{} of {} work items failed", count, ticket_ids.len());
+            // Exit with the code of the earliest stage that failed for any of them
+            Err(match failed.iter().filter_map(|(_, e)| failure_kind(e)).min() {
+                Some(kind) => kind.wrap(error),
+                None => error,
+            })
+        }
+    }
+}
+
+/// Previously saved work items whose revision still matches Azure DevOps, mapped to that revision
+///
+/// Failing to check is not an error: everything is simply fetched again.
+async fn find_up_to_date(client: &AzureDevOpsClient, filesystem: &FileSystemOrganizer, ids: &[u32]) -> HashMap<u32, u32> {
+    let saved: HashMap<u32, u32> = ids
+        .iter()
+        .filter_map(|&id| filesystem.saved_revision(id).map(|rev| (id, rev)))
+        .collect();
+    if saved.is_empty() {
+        return HashMap::new();
+    }
+
+    let saved_ids: Vec<u32> = saved.keys().copied().collect();
+    match client.get_revisions(&saved_ids).await {
+        Ok(current) => saved
+            .into_iter()
+            .filter(|(id, rev)| current.get(id) == Some(rev))
+            .collect(),
+        Err(e) => {
+            tracing::debug!("Could not check revisions, fetching everything: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Generate the plan for an up-to-date ticket from its saved files, unless it already has a change
+async fn plan_up_to_date(ctx: &BakeContext<'_>, ticket_id: u32) -> Result<()> {
+    let openspec = &ctx.config.openspec;
+    if ctx.cli.no_openspec || !openspec.auto_generate || !ctx.openspec_manager.changes_for_ticket(ticket_id).is_empty() {
+        return Ok(());
+    }
+
+    let work_item = ctx.filesystem.load_work_item(ticket_id)?;
+    if !openspec.generates_for_type(&work_item.work_item_type) {
+        return Ok(());
+    }
+    let ticket_path = ctx
+        .filesystem
+        .find_ticket_dir(ticket_id)
+        .ok_or_else(|| anyhow::anyhow!("Work item #{} has not been scraped", ticket_id))?;
+
+    if !ctx.cli.quiet() {
+        ctx.progress.status("🔄", &format!("#{} has no OpenSpec change yet, planning from saved files...", ticket_id));
+    }
+    generate_plan(ctx, &work_item, &ticket_path.to_string_lossy()).await
+}
+
+fn report_up_to_date(cli: &Cli, progress: &Progress, ticket_id: u32, revision: u32) {
+    if cli.events {
+        cli.emit(Event::WorkItemUpToDate { work_item_id: ticket_id, revision });
+    } else if cli.print {
+        println!("\n--- BAKERY OUTPUT ---");
+        println!("work_item_id: {}", ticket_id);
+        println!("revision: {}", revision);
+        println!("status: up_to_date");
+    } else {
+        progress.status("✅", &format!("#{} up to date (rev {}), use --force to re-fetch", ticket_id, revision));
+    }
+}
+
+/// Explain why a work item couldn't be fetched, with a hint matching the failure
+///
+/// The raw HTTP status, body and URL of API errors are only shown with `--verbose`.
+fn render_fetch_error(dashboard: &Dashboard, ticket_id: u32, error: &anyhow::Error, verbose: bool) {
+    let Some(api_error) = error.downcast_ref::<api::ApiError>() else {
+        dashboard.render_error(
+            "Failed to fetch work item",
+            &format!("Could not retrieve work item #{}: {}", ticket_id, error),
+            Some("Check your network connection, PAT token, and that the work item exists")
+        );
+        return;
+    };
+
+    let (title, hint) = match api_error {
+        api::ApiError::WorkItemNotFound { .. } => (
+            "Work item not found",
+            "Check the work item ID, and that your PAT can read the project it belongs to",
+        ),
+        api::ApiError::AccessDenied { status: 401, .. } => (
+            "Authentication failed",
+            "Your token was rejected: check that the PAT is valid, not expired, and has Work Items (Read) scope",
+        ),
+        api::ApiError::AccessDenied { .. } => (
+            "Access denied",
+            "Your token is valid but lacks access to this work item: check its scopes and project permissions",
+        ),
+    };
+
+    let message = if verbose {
+        format!("{}\n{}", api_error, api_error.details())
+    } else {
+        api_error.to_string()
+    };
+    dashboard.render_error(title, &message, Some(hint));
+}
+
+/// Fetch a chunk of work items, at a specific revision when `--revision` was given
+async fn fetch_work_items(client: &AzureDevOpsClient, ids: &[u32], revision: Option<u32>) -> Vec<(u32, Result<models::WorkItem>)> {
+    match (revision, ids) {
+        (Some(rev), [id]) => vec![(*id, client.get_work_item_revision(*id, rev).await)],
+        _ => client.get_work_items(ids).await,
+    }
+}
+
+/// Fetch work items and write them to stdout as JSON/YAML (an object for one ID, a list for several)
+///
+/// Errors go to stderr so stdout stays parseable; any failed fetch makes the command fail.
+async fn print_work_items(client: &AzureDevOpsClient, ticket_ids: &[u32], revision: Option<u32>, format: OutputFormat) -> Result<()> {
+    let mut work_items = Vec::new();
+    let mut failed = 0;
+
+    for chunk in ticket_ids.chunks(api::MAX_BATCH_SIZE) {
+        for (ticket_id, result) in fetch_work_items(client, chunk, revision).await {
+            match result {
+                Ok(work_item) => work_items.push(work_item),
+                Err(e) => {
+                    eprintln!("{}: Could not retrieve work item #{}: {}", "Error".red().bold(), ticket_id, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    let output = match (format, work_items.as_slice()) {
+        (OutputFormat::Json, [work_item]) if ticket_ids.len() == 1 => serde_json::to_string_pretty(work_item)?,
+        (OutputFormat::Json, _) => serde_json::to_string_pretty(&work_items)?,
+        (OutputFormat::Yaml, [work_item]) if ticket_ids.len() == 1 => serde_yaml::to_string(work_item)?,
+        (OutputFormat::Yaml, _) => serde_yaml::to_string(&work_items)?,
+    };
+
+    if !work_items.is_empty() || ticket_ids.len() > 1 {
+        println!("{}", output.trim_end());
+    }
+
+    match failed {
+        0 => Ok(()),
+        count => Err(FailureKind::Fetch.wrap(anyhow::anyhow!("{} of {} work items failed", count, ticket_ids.len()))),
+    }
+}
+
+/// Shared configuration and UI components used while baking each work item
+struct BakeContext<'a> {
+    cli: &'a Cli,
+    config: &'a BakeryConfig,
+    filesystem: &'a FileSystemOrganizer,
+    openspec_manager: &'a OpenSpecManager,
+    theme: &'a Theme,
+    dashboard: &'a Dashboard,
+    card: &'a Card,
+    badge: &'a Badge,
+    progress: &'a Progress,
+    prompt_template: Option<&'a models::PromptTemplate>,
+    /// Azure DevOps client, for writing back to the work item; `None` when working offline
+    client: Option<&'a AzureDevOpsClient>,
+}
+
+/// Save a fetched work item and generate its OpenSpec plan
+async fn bake_work_item(ctx: &BakeContext<'_>, work_item: &models::WorkItem) -> Result<()> {
+    let cli = ctx.cli;
+    let config = ctx.config;
+    let ticket_id = work_item.id;
+
+    cli.emit(Event::WorkItemFetched {
+        work_item_id: ticket_id,
+        title: &work_item.title,
+        work_item_type: &work_item.work_item_type,
+        state: &work_item.state,
+    });
+    for attachment in work_item.attachments.iter().filter(|a| !a.skipped && !a.local_path.is_empty()) {
+        cli.emit(Event::AttachmentDownloaded {
+            work_item_id: ticket_id,
+            filename: &attachment.filename,
+            path: &attachment.local_path,
+            size: attachment.size,
+        });
+    }
+
+    // Display work item info
+    if cli.verbose {
+        ctx.dashboard.render_work_item_summary(work_item);
+    } else if !cli.quiet() {
+        let status_badge = ctx.badge.state(&work_item.state);
+        let type_badge = ctx.badge.work_item_type(&work_item.work_item_type);
+        ctx.progress.status("✓", &format!("{} {} {}", work_item.title, status_badge, type_badge));
+    }
+    if !cli.quiet() && (cli.verbose || ctx.theme.mode == OutputMode::Rich) {
+        ctx.dashboard.render_attachment_list(&work_item.attachments);
+    }
+
+    // Diff against the previous scrape before it is overwritten
+    let description_changes = if cli.verbose {
+        ctx.filesystem.description_changes(work_item)
+    } else {
+        None
+    };
+
+    // Save work item to file system
+    let ticket_path = ctx.filesystem.save_work_item(work_item).await?;
+
+    if cli.verbose {
+        println!("{} {} {}",
+            "💾".bright_blue(),
+            "Work item saved to:".bright_white(),
+            ticket_path.bright_yellow()
+        );
+        if let Some(changes) = &description_changes {
+            print_description_changes(ctx.theme, changes);
+        }
+    }
+
+    // Generate OpenSpec plan if requested
+    let type_allowed = config.openspec.generates_for_type(&work_item.work_item_type);
+    if !cli.no_openspec && config.openspec.auto_generate && type_allowed {
+        generate_plan(ctx, work_item, &ticket_path).await?;
+    } else {
+        let reason = if cli.no_openspec {
+            "OpenSpec plan generation was skipped".to_string()
+        } else if !config.openspec.auto_generate {
+            "OpenSpec auto-generation is disabled in config".to_string()
+        } else {
+            let reason = format!(
+                "OpenSpec plan generation was skipped: {} is not in openspec.auto_generate_types",
+                work_item.work_item_type
+            );
+            // Unlike the flag and the on/off switch, a type filter can come as a surprise
+            if !cli.verbose && !cli.quiet() {
+                ctx.progress.status("⏭️", &reason);
+            }
+            reason
+        };
+        print_summary(work_item, &ticket_path, &reason, cli.verbose, cli.quiet());
+    }
+
+    Ok(())
+}
+
+/// Build the prompt for a saved work item, run the AI and create its OpenSpec change
+async fn generate_plan(ctx: &BakeContext<'_>, work_item: &models::WorkItem, ticket_path: &str) -> Result<()> {
+    let cli = ctx.cli;
+    let config = ctx.config;
+    let ticket_id = work_item.id;
+
+    // Generate plan data
+    let plan_data = ctx.filesystem.generate_openspec_plan_data(work_item);
+    let prompt = plan_data.generate_prompt(config.openspec.max_prompt_chars, ctx.prompt_template);
+    let prompt_path = save_prompt(ctx, ticket_path, ticket_id, &prompt)?;
+
+    if cli.verbose {
+        println!("{} {} {} {}",
+            "✨".bright_cyan(),
+            "Generated prompt".bright_white(),
+            format!("({} chars)", prompt.len()).bright_cyan(),
+            prompt_path.bright_yellow()
+        );
+    }
+
+    if cli.dry_run {
+        print_dry_run(ctx, work_item, ticket_path, &prompt, &prompt_path);
+        return Ok(());
+    }
+
+    // Show clean AI generation box
+    if !cli.quiet() {
+        let ai_text = if ctx.theme.use_emojis() {
+            format!("{} AI Generating OpenSpec Plan...", "🤖".bright_cyan())
+        } else {
+            ctx.theme.fmt_info("AI Generating OpenSpec Plan...")
+        };
+        ctx.card.render_box(&ai_text, 55);
+    }
+
+    // Ensure OpenSpec is initialized
+    ctx.openspec_manager.ensure_openspec_initialized().await
+        .map_err(|e| FailureKind::Generation.wrap(e))?;
+
+    // Generate plan using AI command
+    match ctx.openspec_manager.generate_plan_with_ai(&prompt, &config.openspec, ctx.progress.spinner("")).await {
+        Ok(plan_content) => {
+            let verb = OpenSpecManager::change_verb(cli.change_verb.as_deref(), Some(&plan_content), &work_item.work_item_type);
+
+            // Save the plan with new filename format
+            let plan_path = ctx.openspec_manager.create_feature_plan_file(
+                &verb,
+                ticket_id,
+                &work_item.title,
+                &plan_content
+            )?;
+
+            // Extract change ID from path for validation
+            let change_id = plan_path.split('/').last()
+                .or_else(|| plan_path.split('\\').last())
+                .unwrap_or("");
+
+            cli.emit(Event::PlanGenerated {
+                work_item_id: ticket_id,
+                change_id,
+                change_path: &plan_path,
+                prompt_path: &prompt_path,
+            });
+
+            if let Some(hook) = config.openspec.post_generate_hook.as_deref().filter(|hook| !hook.trim().is_empty()) {
+                ctx.openspec_manager.run_post_generate_hook(hook, ticket_id, &plan_path, &work_item.title).await;
+            }
+
+            if cli.append_comment {
+                if let Some(client) = ctx.client {
+                    append_change_comment(ctx, client, ticket_id, &plan_path).await;
+                }
+            }
+
+            // Validate and show summary
+            let validation = ctx.openspec_manager.validate_change(change_id);
+            let (status, issues) = match &validation {
+                ValidationOutcome::Passed => ("passed", &[][..]),
+                ValidationOutcome::Failed(issues) => ("failed", issues.as_slice()),
+                ValidationOutcome::Skipped => ("skipped", &[][..]),
+            };
+            cli.emit(Event::ValidationResult { change_id, status, issues });
+
+            if !cli.quiet() {
+                render_validation(ctx.theme, &validation, &format!("openspec validate {} --strict", change_id));
+                ctx.openspec_manager.show_change_summary(change_id);
+            }
+
+            if cli.verbose {
+                println!("{} {} {}",
+                    "📝".bright_green(),
+                    "OpenSpec change created:".bright_white(),
+                    plan_path.bright_yellow()
+                );
+            }
+
+            // Show the path to the change
+            if !cli.quiet() {
+                println!("{} {}",
+                    "📁".bright_cyan(),
+                    plan_path.bright_white()
+                );
+            }
+
+            if cli.open && !cli.quiet() {
+                if let Err(e) = open_in_file_explorer(&plan_path) {
+                    println!("{} Could not open {}: {}",
+                        "⚠️".bright_yellow(),
+                        plan_path,
+                        e
+                    );
+                }
+            }
+
+            // Print summary
+            print_summary(work_item, ticket_path, &plan_path, cli.verbose, cli.quiet());
+
+            // Show next steps
+            if !cli.verbose && !cli.quiet() {
+                println!("\n{} {}  {} {}",
+                    "Next:".bright_white(),
+                    "bakery list".bright_cyan(),
+                    "or".bright_white(),
+                    "openspec view".bright_cyan()
+                );
+            }
+
+            // If print mode, output machine-readable summary
+            if cli.print {
+                println!("\n--- BAKERY OUTPUT ---");
+                println!("work_item_id: {}", work_item.id);
+                println!("work_item_title: {}", work_item.title);
+                println!("ticket_path: {}", ticket_path);
+                println!("change_path: {}", plan_path);
+                println!("prompt_path: {}", prompt_path);
+                match &validation {
+                    ValidationOutcome::Failed(_) => println!("status: validation_failed"),
+                    _ => println!("status: success"),
+                }
+            }
+
+            if let ValidationOutcome::Failed(issues) = &validation {
+                return Err(FailureKind::Validation.wrap(anyhow::anyhow!(
+                    "OpenSpec change {} failed validation ({} issue(s))", change_id, issues.len()
+                )));
+            }
+        }
+        Err(e) if cli.events => {
+            cli.emit(Event::PlanFailed { work_item_id: ticket_id, error: e.to_string(), prompt_path: &prompt_path });
+            return Err(FailureKind::Generation.wrap(e));
+        }
+        Err(e) => {
+            println!("{} Failed to generate OpenSpec plan: {}",
+                "⚠️".bright_yellow(),
+                e
+            );
+            println!("{} {} {}",
+                "💡".bright_blue(),
+                "Prompt saved to".bright_white(),
+                prompt_path.bright_yellow()
+            );
+            if cli.verbose {
+                println!("{} {} {}",
+                    "💡".bright_blue(),
+                    "You can generate it manually with:".bright_white(),
+                    format!("cd {} && claude -p < \"{}\"",
+                        config.get_base_directory(),
+                        prompt_path
+                ).bright_cyan()
+                );
+            }
+            return Err(FailureKind::Generation.wrap(e));
+        }
+    }
+
+    Ok(())
+}
+
+/// Note the created change on the work item; failures only warn, since the plan itself is done
+async fn append_change_comment(ctx: &BakeContext<'_>, client: &AzureDevOpsClient, ticket_id: u32, plan_path: &str) {
+    let text = format!("OpenSpec change created: {}", plan_path);
+    let warning = match client.add_comment(ticket_id, &text).await {
+        Ok(()) => {
+            if ctx.cli.verbose {
+                ctx.progress.status("💬", &format!("Commented on #{} with the change path", ticket_id));
+            }
+            return;
+        }
+        Err(e) => match e.downcast_ref::<api::ApiError>() {
+            Some(api::ApiError::AccessDenied { .. }) => format!(
+                "Could not comment on #{}: your PAT needs the Work Items (Read & write) scope to add comments",
+                ticket_id
+            ),
+            _ => format!("Could not comment on #{}: {}", ticket_id, e),
+        },
+    };
+
+    tracing::debug!("{}", warning);
+    if !ctx.cli.quiet() {
+        println!("{} {}", "⚠️".bright_yellow(), warning);
+    }
+}
+
+/// Open a directory with the platform's file explorer, without waiting for it to close
+fn open_in_file_explorer(path: &str) -> Result<()> {
+    let opener = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    std::process::Command::new(opener)
+        .arg(path)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", opener, e))?;
+
+    Ok(())
+}
+
+/// Show the result of `openspec validate`, listing each issue when it fails
+fn render_validation(theme: &Theme, outcome: &ValidationOutcome, rerun_command: &str) {
+    match outcome {
+        ValidationOutcome::Passed => {
+            println!("{} Validation passed", theme.fmt_success("✓"));
+        }
+        ValidationOutcome::Failed(issues) => {
+            println!("{} {} - run {} for details",
+                theme.fmt_warning("⚠️"),
+                theme.fmt_warning(&format!("Validation found {} issue(s)", issues.len())),
+                theme.fmt_info(rerun_command)
+            );
+            for issue in issues {
+                println!("   {} {}", theme.fmt_muted("•"), issue);
+            }
+        }
+        ValidationOutcome::Skipped => {
+            println!("{} OpenSpec CLI not found - validation skipped", theme.fmt_warning("⚠️"));
+        }
+    }
+}
+
+/// Report what `--dry-run` would have generated without calling the AI
+fn print_dry_run(ctx: &BakeContext<'_>, work_item: &models::WorkItem, ticket_path: &str, prompt: &str, prompt_path: &str) {
+    let verb = OpenSpecManager::change_verb(ctx.cli.change_verb.as_deref(), None, &work_item.work_item_type);
+    let change_id = ctx.openspec_manager.change_id(&verb, work_item.id, &work_item.title);
+
+    if ctx.cli.print {
+        println!("\n--- BAKERY OUTPUT ---");
+        println!("work_item_id: {}", work_item.id);
+        println!("work_item_title: {}", work_item.title);
+        println!("ticket_path: {}", ticket_path);
+        println!("change_id: {}", change_id);
+        println!("prompt_path: {}", prompt_path);
+        println!("prompt_length: {}", prompt.len());
+        println!("status: dry_run");
+        return;
+    }
+
+    if ctx.cli.events {
+        return;
+    }
+
+    println!("{} {} {}",
+        "🧪".bright_cyan(),
+        "Dry run:".bright_white().bold(),
+        "AI generation skipped, no OpenSpec change created".bright_white()
+    );
+    println!("   {} {}", "Prompt:".bright_white(), format!("{} chars ({})", prompt.len(), prompt_path).bright_cyan());
+    println!("   {} {}", "Change ID:".bright_white(), change_id.bright_yellow());
+
+    let reason = format!("Dry run: AI generation skipped (would create {}/changes/{})",
+        ctx.config.storage.openspec_subdir, change_id);
+    print_summary(work_item, ticket_path, &reason, ctx.cli.verbose, ctx.cli.print);
+}
+
+/// Write the prompt to the ticket folder, plus the `--dump-prompt` path if given
+///
+/// Returns the path to report: the `--dump-prompt` file when set, the ticket copy otherwise.
+fn save_prompt(ctx: &BakeContext<'_>, ticket_path: &str, ticket_id: u32, prompt: &str) -> Result<String> {
+    let prompt_path = ctx.filesystem.save_prompt(ticket_path, prompt)?;
+
+    match &ctx.cli.dump_prompt {
+        Some(dump_path) => {
+            let dump_path = dump_path.replace("{id}", &ticket_id.to_string());
+            std::fs::write(&dump_path, prompt)
+                .map_err(|e| anyhow::anyhow!("Failed to write prompt to {}: {}", dump_path, e))?;
+            Ok(dump_path)
+        }
+        None => Ok(prompt_path),
+    }
+}
+
+/// Print what changed in description.md since the last scrape: counts, then the changed lines
+fn print_description_changes(theme: &Theme, changes: &filesystem::DescriptionChanges) {
+    println!("{} {} {}, {}",
+        "📝".bright_blue(),
+        "Description changed since the last scrape:".bright_white(),
+        theme.fmt_success(&format!("+{} added", changes.added.len())),
+        theme.fmt_error(&format!("-{} removed", changes.removed.len()))
+    );
+
+    let lines = changes.removed.iter().map(|line| theme.fmt_error(&format!("  - {}", line)))
+        .chain(changes.added.iter().map(|line| theme.fmt_success(&format!("  + {}", line))));
+    for line in lines.clone().take(MAX_DIFF_LINES) {
+        println!("{}", line);
+    }
+    let hidden = lines.count().saturating_sub(MAX_DIFF_LINES);
+    if hidden > 0 {
+        println!("{}", theme.fmt_muted(&format!("  ... {} more changed line(s)", hidden)));
+    }
+}
+
+/// Print how many work items succeeded/failed in a multi-ticket run
+fn print_batch_summary(total: usize, failed: &[(u32, anyhow::Error)], print_mode: bool) {
+    let succeeded = total - failed.len();
+
+    if print_mode {
+        println!("\n--- BAKERY BATCH SUMMARY ---");
+        println!("total: {}", total);
+        println!("succeeded: {}", succeeded);
+        println!("failed: {}", failed.len());
+        for (id, e) in failed {
+            println!("failed_work_item: {} ({})", id, e);
+        }
+        return;
+    }
+
+    println!("\n{} {}",
+        "📦".bright_magenta(),
+        format!("Baked {} of {} work items", succeeded, total).bright_white().bold()
+    );
+
+    for (id, e) in failed {
+        println!("   {} {} {}",
+            "✗".bright_red(),
+            format!("#{}", id).bright_yellow(),
+            e.to_string().bright_white()
+        );
+    }
+}
+
+/// Proxy and TLS settings from `[azure_devops]`, shared by every HTTP client
+fn http_options(config: &BakeryConfig) -> HttpClientOptions {
+    HttpClientOptions {
+        proxy_url: config.azure_devops.proxy_url.clone(),
+        ca_cert_path: config.azure_devops.ca_cert_path.clone(),
+        accept_invalid_certs: config.azure_devops.accept_invalid_certs,
+    }
+}
+
+/// Resolve credentials and build the API client from the effective config
+fn build_client(cli: &Cli, config: &BakeryConfig, tickets_dir: &str, theme: &Theme) -> Result<AzureDevOpsClient> {
+    // Resolve credentials for the configured auth method
+    let auth = match config.azure_devops.auth_method {
+        // PAT token: CLI override, then keyring, then config, then env
+        AuthMethod::Pat => AzureAuth::Pat(get_pat_token(
+            cli.pat_token.clone(),
+            &config.azure_devops.pat_token,
+            &config.azure_devops.organization,
+        )?),
+        AuthMethod::Bearer => AzureAuth::Bearer(get_bearer_token()?),
+    };
+
+    let client = AzureDevOpsClient::new(
+        config.azure_devops.organization.clone(),
+        config.azure_devops.project.clone(),
+        auth,
+        config.azure_devops.api_version.clone(),
+    )?
+    .with_http_options(&http_options(config))?
+    .with_base_url(&config.azure_devops.base_url)
+    .with_custom_fields(config.azure_devops.custom_fields.clone())
+    .with_max_attachment_size(config.storage.max_attachment_size_bytes)
+    .with_max_image_size(config.storage.max_image_size_bytes)
+    .with_content_type_filter(
+        config.storage.allowed_content_types.clone(),
+        config.storage.blocked_content_types.clone(),
+    )
+    .with_max_retries(config.azure_devops.max_retries)
+    .with_raw_responses(config.storage.save_raw_response)
+    .with_timeouts(config.azure_devops.request_timeout_seconds, config.azure_devops.download_timeout_seconds)
+    .with_downloads(config.storage.download_attachments, config.storage.download_images)
+    .with_comments(config.storage.fetch_comments)
+    .with_comments_since(cli.since)
+    .with_tickets_dir(tickets_dir)
+    .with_ticket_path_template(TicketPathTemplate::parse(&config.storage.ticket_path_template)?)
+    .with_progress(Progress::new(theme.clone()));
+
+    Ok(client)
+}
+
+fn handle_list_command(cli: &Cli, profile: Option<&str>) -> Result<()> {
+    let mut config = load_config(profile)?;
+    apply_cli_overrides(cli, &mut config);
+
+    let openspec_manager = OpenSpecManager::new(&resolve_output_directory(cli, &config), &config.storage.openspec_subdir);
+    let changes = openspec_manager.list_changes();
+
+    if cli.print {
+        for change in &changes {
+            println!("{}", change);
+        }
+        return Ok(());
+    }
+
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+
+    if changes.is_empty() {
+        Card::new(theme, terminal).render("📋 OpenSpec Changes", vec![
+            "No active changes".to_string(),
+            format!("Looked in {}", config.get_effective_openspec_directory()),
+        ]);
+        return Ok(());
+    }
+
+    println!("{}", theme.fmt_highlight(&format!("📋 OpenSpec Changes ({})", changes.len())));
+    let mut table = Table::new(theme, terminal, &["Change", "Details"]);
+    for change in changes {
+        // Both `openspec list` and the on-disk fallback put two or more spaces after the ID
+        match change.split_once("  ") {
+            Some((change_id, details)) => table.add_row(vec![change_id.to_string(), details.trim().to_string()]),
+            None => table.add_row(vec![change]),
+        }
+    }
+    table.render();
+
+    Ok(())
+}
+
+fn handle_index_command(cli: &Cli, profile: Option<&str>) -> Result<()> {
+    let mut config = load_config(profile)?;
+    apply_cli_overrides(cli, &mut config);
+
+    let filesystem = FileSystemOrganizer::new(&resolve_output_directory(cli, &config));
+    let (index_path, count) = filesystem.write_index().map_err(|e| {
+        anyhow::anyhow!("Could not write the ticket index in {}: {}", filesystem.tickets_path(), e)
+    })?;
+
+    if cli.print {
+        println!("{}", index_path);
+        return Ok(());
+    }
+
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let card = Card::new(theme, terminal);
+    card.render("📇 Ticket Index", vec![
+        format!("Tickets: {}", count),
+        format!("Written: {}", index_path),
+    ]);
+
+    Ok(())
+}
+
+fn handle_archive_command(cli: &Cli, profile: Option<&str>, change_id: &str) -> Result<()> {
+    let mut config = load_config(profile)?;
+    apply_cli_overrides(cli, &mut config);
+
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let dashboard = Dashboard::new(theme.clone(), terminal);
+
+    let openspec_manager = OpenSpecManager::new(&resolve_output_directory(cli, &config), &config.storage.openspec_subdir);
+    match openspec_manager.archive_change(change_id) {
+        Ok(archived_path) => {
+            // Re-check the remaining specs now the change is gone
+            let validation = openspec_manager.validate_all();
+            if cli.print {
+                println!("archived_path: {}", archived_path);
+            } else {
+                println!("{} {} {}",
+                    "📦".bright_cyan(),
+                    "Archived to".bright_white(),
+                    archived_path.bright_yellow()
+                );
+                render_validation(&theme, &validation, "openspec validate --all --strict");
+            }
+            match validation {
+                ValidationOutcome::Failed(issues) => Err(FailureKind::Validation.wrap(anyhow::anyhow!(
+                    "OpenSpec validation failed after archiving ({} issue(s))", issues.len()
+                ))),
+                _ => Ok(()),
+            }
+        }
+        Err(e) => {
+            dashboard.render_error(
+                "Archive failed",
+                &e.to_string(),
+                Some("Run 'openspec list' to see the active change IDs")
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Run plan generation again from a ticket's saved files, without contacting Azure DevOps
+///
+/// Handy when the AI failed after a successful scrape, or to try out prompt changes.
+async fn handle_replan_command(cli: &Cli, profile: Option<&str>, ticket_id: u32) -> Result<()> {
+    let mut config = load_config(profile)?;
+    apply_cli_overrides(cli, &mut config);
+
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let dashboard = Dashboard::new(theme.clone(), terminal.clone());
+    let card = Card::new(theme.clone(), terminal.clone());
+    let badge = Badge::new(theme.clone());
+    let progress = Progress::new(theme.clone());
+
+    let prompt_template = config
+        .openspec
+        .prompt_template_path
+        .as_deref()
+        .map(models::PromptTemplate::load)
+        .transpose()
+        .map_err(|e| FailureKind::Config.wrap(e))?;
+
+    let output_directory = resolve_output_directory(cli, &config);
+    let filesystem = FileSystemOrganizer::new(&output_directory)
+        .with_openspec_subdir(&config.storage.openspec_subdir)
+        .with_description_dependencies(config.openspec.dependencies_from_description)
+        .with_prompt_comments(config.openspec.prompt_comments)
+        .with_markdown_content(true)
+        .with_ticket_path_template(TicketPathTemplate::parse(&config.storage.ticket_path_template)?);
+    let loaded = filesystem
+        .load_work_item(ticket_id)
+        .and_then(|work_item| {
+            let ticket_dir = filesystem
+                .find_ticket_dir(ticket_id)
+                .ok_or_else(|| anyhow::anyhow!("Work item #{} has not been scraped", ticket_id))?;
+            Ok((work_item, ticket_dir.to_string_lossy().to_string()))
+        });
+    let (work_item, ticket_path) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            dashboard.render_error(
+                "Ticket not found",
+                &e.to_string(),
+                Some(&format!("Scrape it first with 'bakery -t {}'", ticket_id))
+            );
+            return Err(e);
+        }
+    };
+
+    if !cli.quiet() {
+        progress.status("🔄", &format!("Replanning #{} {} from saved files...", work_item.id, work_item.title));
+        if cli.append_comment {
+            progress.status("⚠️", "--append-comment is ignored: replan doesn't contact Azure DevOps");
+        }
+    }
+
+    let openspec_manager = OpenSpecManager::new(&output_directory, &config.storage.openspec_subdir)
+        .with_http_options(http_options(&config));
+    let ctx = BakeContext {
+        cli,
+        config: &config,
+        filesystem: &filesystem,
+        openspec_manager: &openspec_manager,
+        theme: &theme,
+        dashboard: &dashboard,
+        card: &card,
+        badge: &badge,
+        progress: &progress,
+        prompt_template: prompt_template.as_ref(),
+        client: None,
+    };
+    generate_plan(&ctx, &work_item, &ticket_path).await
+}
+
+/// Show a saved ticket: the work item summary, then description, acceptance criteria and comments
+fn handle_view_command(cli: &Cli, profile: Option<&str>, ticket_id: u32) -> Result<()> {
+    let mut config = load_config(profile)?;
+    apply_cli_overrides(cli, &mut config);
+
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let dashboard = Dashboard::new(theme.clone(), terminal.clone());
+    let card = Card::new(theme.clone(), terminal.clone());
+
+    let filesystem = FileSystemOrganizer::new(&resolve_output_directory(cli, &config))
+        .with_ticket_path_template(TicketPathTemplate::parse(&config.storage.ticket_path_template)?);
+    let work_item = match filesystem.load_work_item(ticket_id) {
+        Ok(work_item) => work_item,
+        Err(e) => {
+            dashboard.render_error(
+                "Ticket not found",
+                &e.to_string(),
+                Some(&format!("Scrape it first with 'bakery -t {}'", ticket_id))
+            );
+            return Err(e);
+        }
+    };
+
+    if cli.print {
+        println!("id: {}", work_item.id);
+        println!("title: {}", work_item.title);
+        println!("type: {}", work_item.work_item_type);
+        println!("state: {}", work_item.state);
+        println!("updated: {}", work_item.updated_date.to_rfc3339());
+        println!("\n{}", work_item.description.trim());
+        for (i, criterion) in work_item.acceptance_criteria.iter().enumerate() {
+            println!("\n{}. {}", i + 1, criterion);
+        }
+        for comment in &work_item.comments {
+            println!("\n{} ({}): {}", comment.author.display_name, comment.created_date.format("%Y-%m-%d"), comment.text.trim());
+        }
+        return Ok(());
+    }
+
+    let wrap_width = if terminal.is_narrow() { 56 } else if terminal.is_wide() { 116 } else { 76 };
+    let wrap = |text: &str| -> Vec<String> {
+        text.lines().flat_map(|line| {
+            if line.trim().is_empty() { vec![String::new()] } else { ui::format::wrap_text(line, wrap_width) }
+        }).collect()
+    };
+
+    dashboard.render_work_item_summary(&work_item);
+    dashboard.render_attachment_list(&work_item.attachments);
+    println!();
+
+    let description = work_item.description.trim();
+    card.render("📄 Description", if description.is_empty() {
+        vec![theme.fmt_muted("No description")]
+    } else {
+        wrap(description)
+    });
+
+    if !work_item.acceptance_criteria.is_empty() {
+        println!();
+        let lines = work_item.acceptance_criteria
+            .iter()
+            .enumerate()
+            .flat_map(|(i, criterion)| wrap(&format!("{}. {}", i + 1, criterion)))
+            .collect();
+        card.render("✅ Acceptance Criteria", lines);
+    }
+
+    if !work_item.comments.is_empty() {
+        println!();
+        let mut lines = Vec::new();
+        for (i, comment) in work_item.comments.iter().enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            lines.push(format!("{} {}",
+                theme.fmt_highlight(&comment.author.display_name),
+                theme.fmt_muted(&comment.created_date.format("%Y-%m-%d %H:%M").to_string())
+            ));
+            lines.extend(wrap(comment.text.trim()));
+        }
+        card.render(&format!("💬 Comments ({})", work_item.comments.len()), lines);
+    }
+
+    Ok(())
+}
+
+/// Remove `Tickets/<id>` (and with `--with-change` its OpenSpec changes) after confirmation
+///
+/// Only folders inside the output directory are ever deleted.
+fn handle_clean_command(cli: &Cli, profile: Option<&str>, ticket_id: u32, with_change: bool, force: bool, dry_run: bool) -> Result<()> {
+    let mut config = load_config(profile)?;
+    apply_cli_overrides(cli, &mut config);
+
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let dashboard = Dashboard::new(theme.clone(), terminal);
+
+    let output_directory = resolve_output_directory(cli, &config);
+    let filesystem = FileSystemOrganizer::new(&output_directory)
+        .with_ticket_path_template(TicketPathTemplate::parse(&config.storage.ticket_path_template)?);
+
+    let mut targets = Vec::new();
+    if let Some(ticket_dir) = filesystem.find_ticket_dir(ticket_id) {
+        targets.push(ticket_dir);
+    }
+    if with_change {
+        targets.extend(OpenSpecManager::new(&output_directory, &config.storage.openspec_subdir).changes_for_ticket(ticket_id));
+    }
+
+    if targets.is_empty() {
+        dashboard.render_error(
+            "Nothing to clean",
+            &format!("No scraped folder for ticket #{} in {}", ticket_id, filesystem.tickets_path()),
+            Some("Run 'bakery index' to see which tickets have been scraped")
+        );
+        return Err(anyhow::anyhow!("Ticket #{} has not been scraped", ticket_id));
+    }
+
+    // Refuse anything that resolves outside the output directory, e.g. through a symlink
+    let base = std::fs::canonicalize(&output_directory)
+        .map_err(|e| anyhow::anyhow!("Could not resolve {}: {}", output_directory, e))?;
+    for target in &targets {
+        let resolved = std::fs::canonicalize(target)
+            .map_err(|e| anyhow::anyhow!("Could not resolve {}: {}", target.display(), e))?;
+        if resolved == base || !resolved.starts_with(&base) {
+            return Err(anyhow::anyhow!("{}: Refusing to delete {}, which is outside {}",
+                "Error".red().bold(),
+                target.display(),
+                output_directory));
+        }
+    }
+
+    let sizes: Vec<u64> = targets.iter().map(|target| directory_size(target)).collect();
+    let total: u64 = sizes.iter().sum();
+
+    for (target, size) in targets.iter().zip(&sizes) {
+        if cli.print {
+            println!("{}", target.display());
+        } else {
+            println!("  {} {}", target.display().to_string().bright_yellow(), theme.fmt_muted(&format!("({})", ui::format_file_size(*size))));
+        }
+    }
+
+    if dry_run {
+        if !cli.print {
+            println!("{} {}", "🔍".bright_blue(), theme.fmt_info(&format!(
+                "Dry run: would delete {} folder(s), freeing {}", targets.len(), ui::format_file_size(total)
+            )));
+        }
+        return Ok(());
+    }
+
+    if !force {
+        let answer = prompt_value(&theme, &format!("Delete {} folder(s)? (y/N)", targets.len()), Some("n"))?;
+        if !answer.eq_ignore_ascii_case("y") && !answer.eq_ignore_ascii_case("yes") {
+            println!("{}", theme.fmt_muted("Nothing deleted"));
+            return Ok(());
+        }
+    }
+
+    for target in &targets {
+        std::fs::remove_dir_all(target)
+            .map_err(|e| anyhow::anyhow!("Could not delete {}: {}", target.display(), e))?;
+    }
+
+    if !cli.print {
+        println!("{} {}", "🧹".bright_green(), theme.fmt_success(&format!(
+            "Deleted {} folder(s), freed {}", targets.len(), ui::format_file_size(total)
+        )));
+    }
+    Ok(())
+}
+
+/// Total size of the files below `path`
+fn directory_size(path: &std::path::Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => directory_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+async fn handle_test_connection_command(cli: &Cli, profile: Option<&str>) -> Result<()> {
+    let mut config = load_config(profile)?;
+    apply_cli_overrides(cli, &mut config);
+
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let dashboard = Dashboard::new(theme.clone(), terminal.clone());
+    let card = Card::new(theme.clone(), terminal);
+    let progress = Progress::new(theme.clone());
+
+    let client = build_client(cli, &config, &config.get_effective_tickets_directory(), &theme)
+        .map_err(|e| FailureKind::Config.wrap(e))?;
+
+    progress.status("🔌", &format!("Testing connection to {}/{}...",
+        config.azure_devops.organization, config.azure_devops.project));
+
+    let (status, url) = match client.test_connection().await {
+        Ok(result) => result,
+        Err(e) => {
+            dashboard.render_error(
+                "Connection failed",
+                &e.to_string(),
+                Some("Check your network connection, proxy settings and base_url")
+            );
+            return Err(e);
+        }
+    };
+
+    if status.is_success() {
+        card.render("✅ Connection OK", vec![
+            format!("Organization: {}", config.azure_devops.organization),
+            format!("Project:      {}", config.azure_devops.project),
+            format!("Status:       HTTP {}", status.as_u16()),
+        ]);
+        return Ok(());
+    }
+
+    let hint = match status.as_u16() {
+        401 => "Your token was rejected: check that the PAT is valid, not expired, and has Work Items (Read) scope",
+        403 => "Your token is valid but lacks access to this project: check its scopes and project permissions",
+        404 => "Organization or project not found: check the organization and project names (and base_url for Azure DevOps Server)",
+        _ => "Unexpected response from Azure DevOps: run with --verbose for details",
+    };
+    let message = format!("HTTP {} {} (URL: {})",
+        status.as_u16(), status.canonical_reason().unwrap_or("Unknown Error"), url);
 
-    /// Azure DevOps organization name (overrides config)
-    #[arg(long)]
-    organization: Option<String>,
+    dashboard.render_error("Connection failed", &message, Some(hint));
+    Err(FailureKind::Config.wrap(anyhow::anyhow!("Connection test failed: {}", message)))
+}
 
-    /// Azure DevOps project name (overrides config)
-    #[arg(long)]
-    project: Option<String>,
+async fn handle_init_command(cli: &Cli, profile: Option<&str>, force: bool) -> Result<()> {
+    use std::io::IsTerminal;
 
-    /// Personal Access Token for authentication (overrides config)
-    #[arg(long)]
-    pat_token: Option<String>,
+    let config_path = BakeryConfig::get_config_path(profile)?;
+    if std::path::Path::new(&config_path).exists() && !force {
+        return Err(anyhow::anyhow!("{}: {} already exists. Run {} to edit it, or {} to start over",
+            "Error".red().bold(),
+            config_path,
+            "bakery config".yellow(),
+            "bakery init --force".yellow()));
+    }
 
-    /// Base directory for storing tickets (overrides config)
-    #[arg(long)]
-    base_directory: Option<String>,
+    let mut config = BakeryConfig::default();
 
-    /// Skip OpenSpec plan generation
-    #[arg(long)]
-    no_openspec: bool,
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let dashboard = Dashboard::new(theme.clone(), terminal.clone());
+    let card = Card::new(theme.clone(), terminal);
+    let progress = Progress::new(theme.clone());
 
-    /// Enable verbose logging
-    #[arg(short, long)]
-    verbose: bool,
+    println!("\n{} {}", "⚙️".bright_magenta(), theme.fmt_highlight("Bakery Setup"));
+    println!("{}\n", theme.fmt_muted(&format!("Writing {}", config_path)));
 
-    /// Print machine-readable output and exit (for LLM integration)
-    #[arg(short, long)]
-    print: bool,
+    config.azure_devops.organization = prompt_value(&theme, "Organization", None)?;
+    config.azure_devops.project = prompt_value(&theme, "Project", None)?;
 
-    /// Enable rich output mode with maximum visual features
-    #[arg(long)]
-    rich: bool,
+    // Hidden input on a terminal; piped answers are read like the other prompts
+    let token_label = "Personal Access Token (leave blank to use AZURE_DEVOPS_PAT)";
+    let token = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(format!("{}: ", theme.fmt_primary(token_label)))?
+    } else {
+        prompt_value(&theme, token_label, Some(""))?
+    };
+    config.azure_devops.pat_token = token.trim().to_string();
 
-    /// Enable compact output mode with minimal information
-    #[arg(long)]
-    compact: bool,
+    let default_base_directory = config.storage.base_directory.clone();
+    config.storage.base_directory = prompt_value(&theme, "Base directory", Some(&default_base_directory))?;
 
-    /// Disable colors in output
-    #[arg(long)]
-    no_color: bool,
+    println!();
+    progress.status("🔌", &format!("Testing connection to {}/{}...",
+        config.azure_devops.organization, config.azure_devops.project));
+
+    let connection = match build_client(cli, &config, &config.get_effective_tickets_directory(), &theme) {
+        Ok(client) => client.test_connection().await.map_err(|e| e.to_string()).and_then(|(status, _)| {
+            if status.is_success() {
+                Ok(())
+            } else {
+                Err(format!("HTTP {} {}", status.as_u16(), status.canonical_reason().unwrap_or("Unknown Error")))
+            }
+        }),
+        Err(e) => Err(e.to_string()),
+    };
+
+    if let Err(message) = connection {
+        dashboard.render_error("Connection failed", &message, Some("Check the organization, project and token; you can fix them later with 'bakery config'"));
+        if !prompt_value(&theme, "Save the config anyway? (y/n)", Some("n"))?.eq_ignore_ascii_case("y") {
+            return Err(anyhow::anyhow!("Setup cancelled, no config written"));
+        }
+    }
+
+    let config_path = config.save(profile)?;
+
+    card.render("✅ Bakery is ready", vec![
+        format!("Config:       {}", config_path),
+        format!("Organization: {}", config.azure_devops.organization),
+        format!("Project:      {}", config.azure_devops.project),
+        format!("Output:       {}", config.get_effective_base_directory()),
+    ]);
+    println!("{} {}", "💡".bright_blue(), theme.fmt_info("Next: bakery -t <TICKET_ID>"));
+
+    Ok(())
 }
 
-#[derive(Parser)]
-enum Commands {
-    /// Open Bakery configuration file
-    Config,
+/// Ask for a value on stdin, returning `default` when the answer is blank
+///
+/// Without a default the question is repeated until something is entered.
+fn prompt_value(theme: &Theme, label: &str, default: Option<&str>) -> Result<String> {
+    use std::io::{BufRead, Write};
+
+    loop {
+        match default {
+            Some(default) => print!("{} {}: ", theme.fmt_primary(label), theme.fmt_muted(&format!("[{}]", default))),
+            None => print!("{}: ", theme.fmt_primary(label)),
+        }
+        std::io::stdout().flush()?;
+
+        let mut line = String::new();
+        if std::io::stdin().lock().read_line(&mut line)? == 0 {
+            return Err(anyhow::anyhow!("No input for {}", label));
+        }
+
+        match (line.trim(), default) {
+            ("", Some(default)) => return Ok(default.to_string()),
+            ("", None) => continue,
+            (answer, _) => return Ok(answer.to_string()),
+        }
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Parse `--change-verb`: a single lowercase word, so the change ID stays valid kebab-case
+fn parse_change_verb(value: &str) -> std::result::Result<String, String> {
+    let verb = value.trim().to_lowercase();
+    if verb.is_empty() || !verb.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!("'{}' is not a single word (e.g. add, fix, update, refactor)", value));
+    }
+    Ok(verb)
+}
 
-    // Initialize logging
-    init_logging(cli.verbose);
+/// Parse `--tickets`: comma-separated IDs and ascending ranges, e.g. `100-110,120,130-132`
+///
+/// Returns the IDs sorted and deduplicated. A range spanning more than
+/// `max_span` IDs is rejected (0 = no limit) so a typo can't start a mass scrape.
+fn parse_ticket_ranges(spec: &str, max_span: u32) -> std::result::Result<Vec<u32>, String> {
+    let parse_id = |value: &str| {
+        value
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("'{}' is not a work item ID", value.trim()))
+    };
 
-    // Handle subcommands early (before loading config for better UX)
-    if let Some(command) = cli.command {
-        match command {
-            Commands::Config => {
-                return handle_config_command();
+    let mut ids = std::collections::BTreeSet::new();
+    for part in spec.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_id(start)?, parse_id(end)?);
+                if start > end {
+                    return Err(format!("range '{}' must be ascending (e.g. {}-{})", part, end, start));
+                }
+                let span = end - start + 1;
+                if max_span > 0 && span > max_span {
+                    return Err(format!(
+                        "range '{}' covers {} work items, more than the limit of {} (azure_devops.max_ticket_range)",
+                        part, span, max_span
+                    ));
+                }
+                ids.extend(start..=end);
+            }
+            None => {
+                ids.insert(parse_id(part)?);
             }
         }
     }
 
-    // Require ticket_id for main functionality
-    let ticket_id = cli.ticket_id.ok_or_else(|| anyhow::anyhow!("{}: Ticket ID is required. Use {} or run '{}' to open configuration",
-        "Error".red().bold(),
-        "-t <TICKET_ID>".yellow(),
-        "bakery config".yellow()))?;
+    if ids.is_empty() {
+        return Err("no work item IDs given".to_string());
+    }
+    Ok(ids.into_iter().collect())
+}
 
-    // Load configuration
-    let mut config = BakeryConfig::load()?;
+/// Parse `--since`: an absolute date/time, or a duration before now such as `30m`, `12h`, `7d` or `2w`
+fn parse_since(value: &str) -> std::result::Result<chrono::DateTime<chrono::Utc>, String> {
+    let value = value.trim();
+
+    if let Ok(date_time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(date_time.with_timezone(&chrono::Utc));
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("expected a date like 2024-01-01 or a duration like 7d, got '{}'", value))?;
+
+    let duration = match unit {
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        "w" => chrono::Duration::weeks(amount),
+        _ => return Err(format!("unknown unit '{}' in '{}': use m, h, d or w", unit, value)),
+    };
+
+    Ok(chrono::Utc::now() - duration)
+}
+
+/// Load the configuration, tagging failures as configuration errors (exit code 2)
+fn load_config(profile: Option<&str>) -> Result<BakeryConfig> {
+    BakeryConfig::load(profile).map_err(|e| FailureKind::Config.wrap(e))
+}
+
+/// Determine the color palette (`--theme` takes precedence over config)
+fn resolve_palette(cli: &Cli, config: &BakeryConfig) -> ThemePalette {
+    cli.theme.unwrap_or(config.openspec.theme)
+}
 
-    // Determine output mode (CLI flags take precedence over config)
-    let output_mode = if cli.print {
+/// Determine output mode (CLI flags take precedence over config)
+fn resolve_output_mode(cli: &Cli, config: &BakeryConfig) -> OutputMode {
+    if cli.print {
         OutputMode::Print
     } else if cli.verbose {
         OutputMode::Verbose
@@ -132,221 +1926,162 @@ async fn main() -> Result<()> {
         OutputMode::Rich
     } else {
         OutputMode::Default
-    };
+    }
+}
 
-    // Initialize UI components
-    let terminal = Terminal::detect();
-    let theme = Theme::new(output_mode, terminal.clone());
-    let dashboard = Dashboard::new(theme.clone(), terminal.clone());
-    let card = Card::new(theme.clone(), terminal.clone());
-    let badge = Badge::new(theme.clone());
-    let progress = Progress::new(theme.clone());
+/// Where this run writes its Tickets and openspec folders
+///
+/// `--output-dir` wins over the configured base directory (and local baking)
+/// without changing the config, so a CI job can keep artifacts in its workspace.
+fn resolve_output_directory(cli: &Cli, config: &BakeryConfig) -> String {
+    match &cli.output_dir {
+        Some(dir) => config::expand_path(dir),
+        None => config.get_effective_base_directory(),
+    }
+}
 
-    // Override config with CLI parameters if provided
-    if let Some(org) = cli.organization {
-        config.azure_devops.organization = org;
+/// Override config with CLI parameters if provided
+fn apply_cli_overrides(cli: &Cli, config: &mut BakeryConfig) {
+    if let Some(org) = &cli.organization {
+        config.azure_devops.organization = org.clone();
     }
-    if let Some(project) = cli.project {
-        config.azure_devops.project = project;
+    if let Some(project) = &cli.project {
+        config.azure_devops.project = project.clone();
     }
-    if let Some(token) = cli.pat_token {
-        config.azure_devops.pat_token = token;
+    if let Some(token) = &cli.pat_token {
+        config.azure_devops.pat_token = token.clone();
     }
-    if let Some(base_dir) = cli.base_directory {
-        config.storage.base_directory = base_dir;
+    if let Some(timeout) = cli.timeout {
+        config.azure_devops.request_timeout_seconds = timeout;
     }
-
-    // Get PAT token (CLI override, then config, then env, then hardcoded)
-    let pat_token = get_pat_token(Some(config.azure_devops.pat_token.clone()))?;
-
-    if cli.verbose {
-        card.render_header(
-            &format!("🚀 Starting Bakery v{}", env!("CARGO_PKG_VERSION")),
-            &format!("Azure DevOps scraper for ticket #{}", ticket_id)
-        );
-
-        card.render_two_column(vec![
-            ("Organization", config.azure_devops.organization.clone()),
-            ("Project", config.azure_devops.project.clone()),
-            ("Storage", if config.storage.local_baking {
-                "Local baking enabled".to_string()
-            } else {
-                config.get_effective_base_directory()
-            }),
-        ]);
-    } else if !cli.print {
-        // Concise output for normal mode (skip in print mode)
-        progress.status("🔄", &format!("Fetching work item #{}...", ticket_id));
+    if let Some(base_dir) = &cli.base_directory {
+        config.storage.base_directory = base_dir.clone();
     }
-
-    // Initialize components
-    let client = AzureDevOpsClient::new(
-        config.azure_devops.organization.clone(),
-        config.azure_devops.project.clone(),
-        pat_token,
-    );
-
-    let filesystem = FileSystemOrganizer::new(&config.get_effective_base_directory());
-    let openspec_manager = OpenSpecManager::new(&config.get_effective_base_directory());
-
-    // Ensure directory structure exists
-    filesystem.ensure_base_structure()?;
-
-    // Fetch work item
-    let work_item = match client.get_work_item(ticket_id).await {
-        Ok(item) => item,
-        Err(e) => {
-            dashboard.render_error(
-                "Failed to fetch work item",
-                &format!("Could not retrieve work item #{}: {}", ticket_id, e),
-                Some("Check your network connection, PAT token, and that the work item exists")
-            );
-            return Err(e);
-        }
-    };
-
-    // Display work item info
-    if cli.verbose {
-        dashboard.render_work_item_summary(
-            work_item.id,
-            &work_item.title,
-            &work_item.state,
-            &work_item.work_item_type,
-            work_item.attachments.len(),
-            work_item.comments.len(),
-            work_item.images.len(),
-            work_item.acceptance_criteria.len(),
-        );
-    } else if !cli.print {
-        let status_badge = badge.state(&work_item.state);
-        let type_badge = badge.work_item_type(&work_item.work_item_type);
-        progress.status("✓", &format!("{} {} {}", work_item.title, status_badge, type_badge));
+    if cli.no_attachments {
+        config.storage.download_attachments = false;
     }
-
-    // Save work item to file system
-    let ticket_path = filesystem.save_work_item(&work_item).await?;
-
-    if cli.verbose {
-        println!("{} {} {}",
-            "💾".bright_blue(),
-            "Work item saved to:".bright_white(),
-            ticket_path.bright_yellow()
-        );
+    if cli.no_images {
+        config.storage.download_images = false;
     }
+    if cli.no_comments {
+        config.storage.fetch_comments = false;
+    }
+}
 
-    // Generate OpenSpec plan if requested
-    if !cli.no_openspec && config.openspec.auto_generate {
-        // Show clean AI generation box
-        if !cli.print {
-            let ai_text = if theme.use_emojis() {
-                format!("{} AI Generating OpenSpec Plan...", "🤖".bright_cyan())
-            } else {
-                theme.fmt_info("AI Generating OpenSpec Plan...")
-            };
-            card.render_box(&ai_text, 55);
-        }
-
-        // Ensure OpenSpec is initialized
-        openspec_manager.ensure_openspec_initialized().await?;
-
-        // Generate plan data
-        let plan_data = filesystem.generate_openspec_plan_data(&work_item);
-        let prompt = plan_data.generate_prompt();
-
-        if cli.verbose {
-            println!("{} {} {}",
-                "✨".bright_cyan(),
-                "Generated prompt".bright_white(),
-                format!("({} chars)", prompt.len()).bright_cyan()
-            );
-        }
-
-        // Generate plan using AI command
-        match openspec_manager.generate_plan_with_ai(&prompt, &config.openspec).await {
-            Ok(plan_content) => {
-                // Save the plan with new filename format
-                let plan_path = openspec_manager.create_feature_plan_file(
-                    ticket_id,
-                    &work_item.title,
-                    &plan_content
-                )?;
-
-                // Extract change ID from path for validation
-                let change_id = plan_path.split('/').last()
-                    .or_else(|| plan_path.split('\\').last())
-                    .unwrap_or("");
-
-                // Validate and show summary
-                openspec_manager.validate_and_summarize(change_id, cli.print)?;
-
-                if cli.verbose {
-                    println!("{} {} {}",
-                        "📝".bright_green(),
-                        "OpenSpec change created:".bright_white(),
-                        plan_path.bright_yellow()
-                    );
-                }
-
-                // Show the path to the change
-                if !cli.print {
-                    println!("{} {}",
-                        "📁".bright_cyan(),
-                        plan_path.bright_white()
-                    );
-                }
+fn handle_show_config_command(cli: &Cli, profile: Option<&str>) -> Result<()> {
+    let config_path = BakeryConfig::get_config_path(profile)?;
+    let mut config = load_config(profile)?;
+
+    // Keys actually present in the file, to tell "file" apart from serde defaults
+    let file_table: toml::Table = std::fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|content| content.parse().ok())
+        .unwrap_or_default();
+    let file_source = |section: &str, key: &str| {
+        let in_file = file_table
+            .get(section)
+            .and_then(|table| table.get(key))
+            .is_some();
+        if in_file { "file" } else { "default" }
+    };
+    let source = |cli_value: bool, section: &str, key: &str| {
+        if cli_value { "cli" } else { file_source(section, key) }
+    };
 
-                // Print summary
-                print_summary(&work_item, &ticket_path, &plan_path, cli.verbose, cli.print);
+    apply_cli_overrides(cli, &mut config);
 
-                // Show next steps
-                if !cli.verbose && !cli.print {
-                    println!("\n{} {}  {} {}",
-                        "Next:".bright_white(),
-                        "openspec list".bright_cyan(),
-                        "or".bright_white(),
-                        "openspec view".bright_cyan()
-                    );
-                }
+    let terminal = Terminal::detect();
+    let theme = Theme::new(resolve_output_mode(cli, &config), terminal.clone())
+        .with_palette(resolve_palette(cli, &config));
+    let card = Card::new(theme, terminal);
+
+    let pat = match config.azure_devops.auth_method {
+        AuthMethod::Pat => match resolve_pat_token(
+            cli.pat_token.clone(),
+            &config.azure_devops.pat_token,
+            &config.azure_devops.organization,
+        ) {
+            Some((token, token_source)) => format!("{}  ({})", redact_token(&token), token_source),
+            None => "(not set)".to_string(),
+        },
+        AuthMethod::Bearer => match get_bearer_token() {
+            Ok(token) => format!("{}  (env)", redact_token(&token)),
+            Err(_) => "(AZURE_DEVOPS_BEARER not set)".to_string(),
+        },
+    };
 
-                // If print mode, output machine-readable summary
-                if cli.print {
-                    println!("\n--- BAKERY OUTPUT ---");
-                    println!("work_item_id: {}", work_item.id);
-                    println!("work_item_title: {}", work_item.title);
-                    println!("ticket_path: {}", ticket_path);
-                    println!("change_path: {}", plan_path);
-                    println!("status: success");
-                }
-            }
-            Err(_) => {
-                println!("{} Failed to generate OpenSpec plan",
-                    "⚠️".bright_yellow()
-                );
-                if cli.verbose {
-                    println!("{} {} {}",
-                        "💡".bright_blue(),
-                        "You can generate it manually with:".bright_white(),
-                        format!("cd {} && claude --non-interactive \"{{prompt}}\"",
-                            config.storage.base_directory.bright_cyan()
-                    ).bright_cyan()
-                    );
-                }
-            }
-        }
-    } else {
-        let reason = if cli.no_openspec {
-            "OpenSpec plan generation was skipped"
-        } else {
-            "OpenSpec auto-generation is disabled in config"
-        };
-        print_summary(&work_item, &ticket_path, reason, cli.verbose, cli.print);
-    }
+    let with_source = |value: String, source: &str| format!("{}  ({})", value, source);
+    let profile_source = if cli.profile.is_some() { "cli" } else { "env" };
+
+    card.render_header("⚙️  Resolved Configuration", &format!("Location: {}", config_path));
+    card.render_two_column(vec![
+        ("Profile", profile.map_or_else(|| "default".to_string(), |name| with_source(name.to_string(), profile_source))),
+        ("Base URL", with_source(config.azure_devops.base_url.clone(), file_source("azure_devops", "base_url"))),
+        ("Organization", with_source(config.azure_devops.organization.clone(), source(cli.organization.is_some(), "azure_devops", "organization"))),
+        ("Project", with_source(config.azure_devops.project.clone(), source(cli.project.is_some(), "azure_devops", "project"))),
+        ("Auth method", with_source(format!("{:?}", config.azure_devops.auth_method).to_lowercase(), file_source("azure_devops", "auth_method"))),
+        ("Token", pat),
+        ("API version", with_source(config.azure_devops.api_version.clone(), file_source("azure_devops", "api_version"))),
+        ("Proxy", with_source(
+            config.azure_devops.proxy_url.clone().unwrap_or_else(|| "(none)".to_string()),
+            file_source("azure_devops", "proxy_url"),
+        )),
+        ("CA certificate", with_source(
+            config.azure_devops.ca_cert_path.clone().unwrap_or_else(|| "(none)".to_string()),
+            file_source("azure_devops", "ca_cert_path"),
+        )),
+        ("Invalid certs", with_source(config.azure_devops.accept_invalid_certs.to_string(), file_source("azure_devops", "accept_invalid_certs"))),
+        ("Max retries", with_source(config.azure_devops.max_retries.to_string(), file_source("azure_devops", "max_retries"))),
+        ("Max ticket range", with_source(config.azure_devops.max_ticket_range.to_string(), file_source("azure_devops", "max_ticket_range"))),
+        ("Request timeout", with_source(
+            format!("{}s", config.azure_devops.request_timeout_seconds),
+            source(cli.timeout.is_some(), "azure_devops", "request_timeout_seconds"),
+        )),
+        ("Download timeout", with_source(
+            format!("{}s", config.azure_devops.download_timeout_seconds),
+            file_source("azure_devops", "download_timeout_seconds"),
+        )),
+        ("Base directory", with_source(config.get_base_directory(), source(cli.base_directory.is_some(), "storage", "base_directory"))),
+        ("Local baking", with_source(config.storage.local_baking.to_string(), file_source("storage", "local_baking"))),
+        ("Log file", with_source(
+            cli.log_file.clone().or(config.storage.log_file.clone()).unwrap_or_else(|| "(none)".to_string()),
+            source(cli.log_file.is_some(), "storage", "log_file"),
+        )),
+        ("Effective base", config.get_effective_base_directory()),
+        ("Tickets", config.get_effective_tickets_directory()),
+        ("OpenSpec", config.get_effective_openspec_directory()),
+        ("AI provider", with_source(config.openspec.ai_provider.as_str().to_string(), file_source("openspec", "ai_provider"))),
+        ("AI command", with_source(config.openspec.ai_command_template.clone(), file_source("openspec", "ai_command_template"))),
+        ("Auto-generate", with_source(config.openspec.auto_generate.to_string(), file_source("openspec", "auto_generate"))),
+        ("Auto-generate types", with_source(
+            config.openspec.auto_generate_types.as_ref().map_or_else(|| "(all)".to_string(), |types| types.join(", ")),
+            file_source("openspec", "auto_generate_types"),
+        )),
+        ("Post-generate hook", with_source(
+            config.openspec.post_generate_hook.clone().unwrap_or_else(|| "(none)".to_string()),
+            file_source("openspec", "post_generate_hook"),
+        )),
+        ("Prompt template", with_source(
+            config.openspec.prompt_template_path.clone().unwrap_or_else(|| "(built-in)".to_string()),
+            file_source("openspec", "prompt_template_path"),
+        )),
+        ("Theme", with_source(resolve_palette(cli, &config).as_str().to_string(), source(cli.theme.is_some(), "openspec", "theme"))),
+    ]);
+    println!();
 
     Ok(())
 }
 
-fn handle_config_command() -> Result<()> {
-    let config_path = BakeryConfig::get_config_path()?;
+/// Show only the last 4 characters of a secret
+fn redact_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    let visible: String = chars[chars.len().saturating_sub(4)..].iter().collect();
+    format!("****{}", visible)
+}
+
+fn handle_config_command(profile: Option<&str>) -> Result<()> {
+    // Ensure config exists
+    let config_path = BakeryConfig::ensure_config_file(profile)?;
 
     println!("\n{} {}",
         "⚙️".bright_magenta(),
@@ -356,9 +2091,12 @@ fn handle_config_command() -> Result<()> {
         "📍".bright_blue(),
         format!("Location: {}", config_path).bright_cyan()
     );
-
-    // Ensure config exists
-    BakeryConfig::load()?;
+    if let Some(name) = profile {
+        println!("{} {}",
+            "👤".bright_blue(),
+            format!("Profile: {}", name).bright_cyan()
+        );
+    }
 
     // Open config file in default editor
     let editor = std::env::var("EDITOR").unwrap_or_else(|_| {
@@ -392,7 +2130,89 @@ fn handle_config_command() -> Result<()> {
     Ok(())
 }
 
-fn init_logging(verbose: bool) {
+fn handle_list_profiles_command(active_profile: Option<&str>) -> Result<()> {
+    let profiles = BakeryConfig::list_profiles()?;
+
+    println!("\n{} {}",
+        "⚙️".bright_magenta(),
+        "Bakery Profiles".bright_white().bold()
+    );
+
+    let default_marker = if active_profile.is_none() { " (active)" } else { "" };
+    println!("   {} {}{}",
+        "•".bright_blue(),
+        "default".bright_white(),
+        default_marker.bright_green()
+    );
+
+    for name in &profiles {
+        let marker = if active_profile == Some(name.as_str()) { " (active)" } else { "" };
+        println!("   {} {}{}",
+            "•".bright_blue(),
+            name.bright_white(),
+            marker.bright_green()
+        );
+    }
+
+    if profiles.is_empty() {
+        println!("{} {}",
+            "💡".bright_blue(),
+            "Create one with: bakery --profile <name> config".bright_cyan()
+        );
+    }
+
+    Ok(())
+}
+
+fn handle_set_token_command(profile: Option<&str>, organization: Option<String>) -> Result<()> {
+    use std::io::{BufRead, IsTerminal};
+
+    let config = load_config(profile)?;
+    let organization = organization.unwrap_or_else(|| config.azure_devops.organization.clone());
+
+    // Prompt with hidden input on a terminal; otherwise read the token from piped stdin
+    let token = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(format!("Personal Access Token for {}: ", organization))?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line
+    };
+    let token = token.trim();
+    if token.is_empty() {
+        return Err(anyhow::anyhow!("{}: no token entered", "Error".red().bold()));
+    }
+
+    credentials::store_pat(&organization, token)?;
+    BakeryConfig::set_pat_token_in_file(profile, credentials::KEYRING_SENTINEL)?;
+
+    println!("{} {}",
+        "✅".bright_green().bold(),
+        format!("Token for {} stored in the OS keyring", organization).bright_green()
+    );
+    println!("{} {}",
+        "💡".bright_blue(),
+        format!("pat_token is now set to \"{}\" in your config file", credentials::KEYRING_SENTINEL).bright_cyan()
+    );
+
+    Ok(())
+}
+
+/// `storage.log_file` from the config file, without creating the file when it doesn't exist yet
+fn configured_log_file(profile: Option<&str>) -> Option<String> {
+    let config_path = BakeryConfig::get_config_path(profile).ok()?;
+    if !std::path::Path::new(&config_path).exists() {
+        return None;
+    }
+    BakeryConfig::load(profile).ok()?.storage.log_file.filter(|path| !path.trim().is_empty())
+}
+
+/// Set up console logging, plus a daily-rotated file log when `log_file` is given
+///
+/// The console follows `--verbose` (or `RUST_LOG`); the file always records DEBUG.
+/// Both go through `redact` so tokens never end up in a log.
+/// Keep the returned guard alive until exit so buffered file output is flushed.
+fn init_logging(verbose: bool, log_to_stderr: bool, log_file: Option<&str>) -> Option<tracing_appender::non_blocking::WorkerGuard> {
     let filter = if verbose {
         tracing::level_filters::LevelFilter::DEBUG
     } else {
@@ -400,34 +2220,112 @@ fn init_logging(verbose: bool) {
         tracing::level_filters::LevelFilter::WARN
     };
 
-    tracing_subscriber::registry()
-        .with(
+    let console_layer = tracing_subscriber::fmt::layer()
+        .with_writer(move || -> Box<dyn std::io::Write> {
+            // Keep stdout clean for --format output
+            if log_to_stderr {
+                Box::new(redact::RedactingWriter::new(std::io::stderr()))
+            } else {
+                Box::new(redact::RedactingWriter::new(std::io::stdout()))
+            }
+        })
+        .with_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(format!("bakery={}", filter)))
-        )
-        .with(tracing_subscriber::fmt::layer())
+        );
+
+    let file_appender = log_file.and_then(|path| match rolling_log_file(path) {
+        Ok(appender) => Some(appender),
+        Err(e) => {
+            eprintln!("{} Could not open log file {}: {}", "⚠️".bright_yellow(), path, e);
+            None
+        }
+    });
+    let (file_layer, guard) = match file_appender {
+        Some(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || redact::RedactingWriter::new(writer.clone()))
+                .with_filter(tracing_subscriber::EnvFilter::new("bakery=debug"));
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(console_layer)
+        .with(file_layer)
         .init();
+
+    guard
 }
 
-fn get_pat_token(provided_token: Option<String>) -> Result<String> {
-    // If token is provided via CLI or env, use it
-    if let Some(token) = provided_token {
-        return Ok(token);
+/// Daily-rotated appender for `path`: each day's log goes to `<file name>.YYYY-MM-DD` next to it
+fn rolling_log_file(path: &str) -> Result<tracing_appender::rolling::RollingFileAppender> {
+    let path = std::path::PathBuf::from(config::expand_path(path));
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not a file path", path.display()))?;
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(std::path::Path::new("."));
+
+    Ok(tracing_appender::rolling::RollingFileAppender::builder()
+        .rotation(tracing_appender::rolling::Rotation::DAILY)
+        .filename_prefix(file_name)
+        .build(directory)?)
+}
+
+/// Find the PAT and where it came from: CLI override, then keyring, then config, then env
+fn resolve_pat_token(cli_token: Option<String>, config_token: &str, organization: &str) -> Option<(String, &'static str)> {
+    let non_empty = |token: String| Some(token.trim().to_string()).filter(|token| !token.is_empty());
+
+    // A token passed on the command line always wins
+    if let Some(token) = cli_token.and_then(non_empty) {
+        return Some((token, "cli"));
     }
 
-    // Try to get from environment variable
-    if let Ok(token) = std::env::var("AZURE_DEVOPS_PAT") {
-        return Ok(token);
+    // Then the OS keyring (see `bakery config set-token`)
+    if let Some(token) = credentials::load_pat(organization) {
+        return Some((token, "keyring"));
     }
 
-    // Use the hardcoded token from the user
-    let hardcoded_token = "D5LJs28TdicqoXw3f1TSnxYsoYN571yhFqh7M0vQQ99GN779DEWyJQQJ99BKACAAAAAbogyCAAASAZDO3lse";
+    // Then a plaintext token from config (ignoring the example placeholder and keyring sentinel)
+    let config_token = config_token.trim();
+    if config_token == credentials::KEYRING_SENTINEL {
+        tracing::debug!("pat_token is set to \"{}\" but no keyring entry was found for {}", credentials::KEYRING_SENTINEL, organization);
+    } else if !config_token.is_empty() && config_token != "your-pat-token-here" {
+        return Some((config_token.to_string(), "file"));
+    }
 
-    println!("{} {}",
-        "⚠️".bright_yellow(),
-        "Using hardcoded PAT token. Consider setting AZURE_DEVOPS_PAT environment variable for better security.".bright_yellow()
-    );
-    Ok(hardcoded_token.to_string())
+    // Try to get from environment variable
+    std::env::var("AZURE_DEVOPS_PAT").ok().and_then(non_empty).map(|token| (token, "env"))
+}
+
+fn get_pat_token(cli_token: Option<String>, config_token: &str, organization: &str) -> Result<String> {
+    resolve_pat_token(cli_token, config_token, organization)
+        .map(|(token, _)| token)
+        .ok_or_else(|| anyhow::anyhow!("{}: no Personal Access Token configured. Set the {} environment variable, pass {}, run {}, or set {} in {}",
+            "Error".red().bold(),
+            "AZURE_DEVOPS_PAT".yellow(),
+            "--pat-token".yellow(),
+            "bakery config set-token".yellow(),
+            "pat_token".yellow(),
+            "~/.bakery/bakery-config.toml".yellow()))
+}
+
+fn get_bearer_token() -> Result<String> {
+    std::env::var("AZURE_DEVOPS_BEARER")
+        .ok()
+        .filter(|token| !token.trim().is_empty())
+        .map(|token| token.trim().to_string())
+        .ok_or_else(|| anyhow::anyhow!("{}: auth_method is \"bearer\" but {} is not set. Get a token with: {}",
+            "Error".red().bold(),
+            "AZURE_DEVOPS_BEARER".yellow(),
+            "az account get-access-token --resource 499b84ac-1321-427f-aa17-267ca6975798".yellow()))
 }
 
 fn print_summary(work_item: &models::WorkItem, ticket_path: &str, plan_path_or_reason: &str, verbose: bool, print_mode: bool) {
@@ -523,4 +2421,38 @@ fn print_summary(work_item: &models::WorkItem, ticket_path: &str, plan_path_or_r
             );
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticket_ranges_are_merged_sorted_and_deduplicated() {
+        assert_eq!(parse_ticket_ranges("100-102,101,105", 0), Ok(vec![100, 101, 102, 105]));
+        assert_eq!(parse_ticket_ranges(" 105 , 100-101,", 0), Ok(vec![100, 101, 105]));
+    }
+
+    #[test]
+    fn descending_ticket_range_is_rejected() {
+        assert_eq!(
+            parse_ticket_ranges("110-100", 0),
+            Err("range '110-100' must be ascending (e.g. 100-110)".to_string())
+        );
+    }
+
+    #[test]
+    fn ticket_range_span_limit() {
+        assert_eq!(parse_ticket_ranges("1-50", 50).map(|ids| ids.len()), Ok(50));
+        assert!(parse_ticket_ranges("1-51", 50).unwrap_err().contains("covers 51 work items, more than the limit of 50"));
+        assert_eq!(parse_ticket_ranges("1-51", 0).map(|ids| ids.len()), Ok(51));
+    }
+
+    #[test]
+    fn empty_or_malformed_ticket_list_is_rejected() {
+        assert_eq!(parse_ticket_ranges("", 0), Err("no work item IDs given".to_string()));
+        assert_eq!(parse_ticket_ranges(" , ,", 0), Err("no work item IDs given".to_string()));
+        assert_eq!(parse_ticket_ranges("12,abc", 0), Err("'abc' is not a work item ID".to_string()));
+        assert_eq!(parse_ticket_ranges("5-", 0), Err("'' is not a work item ID".to_string()));
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Node, Selector};
 
 /// Clean HTML content by removing tags and extracting readable text
 pub fn clean_html_content(html_content: &str) -> String {
@@ -9,37 +9,290 @@ pub fn clean_html_content(html_content: &str) -> String {
         return String::new();
     }
 
-    let fragment = Html::parse_fragment(html_content);
-    let text_selectors = Selector::parse("p, div, li, span, h1, h2, h3, h4, h5, h6").unwrap();
+    // Plain text (e.g. criteria already split out of the AC field) has no tags to strip
+    if !html_content.contains('<') {
+        return decode_html_entities(html_content).trim().to_string();
+    }
 
+    let fragment = Html::parse_fragment(html_content);
     let mut cleaned_text = String::new();
+    render_blocks(fragment.root_element(), &mut cleaned_text);
 
-    // Extract text from relevant elements
-    for element in fragment.select(&text_selectors) {
-        let text = element.text().collect::<String>().trim().to_string();
-        if !text.is_empty() {
-            // Add appropriate formatting based on element type
-            if element.value().name() == "li" {
-                cleaned_text.push_str(&format!("• {}\n", text));
-            } else if element.value().name().starts_with('h') {
-                cleaned_text.push_str(&format!("\n**{}**\n", text));
-            } else {
-                cleaned_text.push_str(&format!("{}\n", text));
-            }
-        }
-    }
+    // The parser already decoded the entities; decoding again would turn a literal
+    // "&amp;" the user typed into "&". Only non-breaking spaces still need replacing.
+    let cleaned_text = cleaned_text.replace('\u{a0}', " ");
 
     // Clean up extra whitespace and format
-    cleaned_text
+    let cleaned_text = cleaned_text
         .lines()
-        .filter(|line| !line.trim().is_empty())
+        .map(str::trim_end)
+        // Keep empty lines: they set headings and tables apart from the text around them
+        .filter(|line| line.is_empty() || !is_blank_block(line))
         .collect::<Vec<_>>()
-        .join("\n")
-        .replace("\n\n\n", "\n\n")
+        .join("\n");
+
+    regex::Regex::new(r"\n{3,}").unwrap()
+        .replace_all(&cleaned_text, "\n\n")
         .trim()
         .to_string()
 }
 
+/// Elements that start a new line of text rather than continuing the current one
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p", "div", "section", "article", "header", "footer", "blockquote", "pre",
+    "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol", "li", "table",
+];
+
+/// Append the text of `element` to `out`, one line per block
+///
+/// Walks the children in document order: inline content is gathered into the
+/// current line, and every nested block ends it. Wrapper blocks (Azure puts the
+/// whole description in a `<div>`) therefore only contribute their own text,
+/// and the tables and lists inside them are rendered once.
+fn render_blocks(element: ElementRef, out: &mut String) {
+    let mut line = String::new();
+    let flush = |line: &mut String, out: &mut String| {
+        let text = line.trim();
+        if !is_blank_block(text) {
+            out.push_str(text);
+            out.push('\n');
+        }
+        line.clear();
+    };
+
+    for child in element.children() {
+        let child = match child.value() {
+            Node::Text(content) => {
+                line.push_str(content);
+                continue;
+            }
+            Node::Element(_) => match ElementRef::wrap(child) {
+                Some(child) => child,
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        let name = child.value().name();
+        if name == "br" {
+            flush(&mut line, out);
+        } else if !BLOCK_ELEMENTS.contains(&name) {
+            match child.value().attr("href") {
+                Some(href) if name == "a" => line.push_str(&markdown_link(&inline_text(child, &|_| false), href)),
+                _ => line.push_str(&inline_text(child, &|_| false)),
+            }
+        } else {
+            flush(&mut line, out);
+            match name {
+                "ul" | "ol" => render_list(child, out),
+                "li" => {
+                    if let Some(item) = html_list_item_to_markdown(child) {
+                        out.push_str(&format!("{}\n", item));
+                    }
+                }
+                "table" => {
+                    let table = html_table_to_markdown(child);
+                    if !table.is_empty() {
+                        out.push_str(&format!("\n{}\n\n", table));
+                    }
+                }
+                heading if heading.starts_with('h') => {
+                    let text = inline_text(child, &|_| false).trim().to_string();
+                    if !is_blank_block(&text) {
+                        out.push_str(&format!("\n**{}**\n", text));
+                    }
+                }
+                _ => render_blocks(child, out),
+            }
+        }
+    }
+
+    flush(&mut line, out);
+}
+
+/// Append every item of a `<ul>`/`<ol>`, each followed by the lists nested in it
+fn render_list(list: ElementRef, out: &mut String) {
+    let is_list = |element: &ElementRef| matches!(element.value().name(), "ul" | "ol");
+
+    for item in list.children().filter_map(ElementRef::wrap).filter(|child| child.value().name() == "li") {
+        if let Some(line) = html_list_item_to_markdown(item) {
+            out.push_str(&format!("{}\n", line));
+        }
+
+        // Lists inside this item (possibly wrapped in a <div>), but not those nested deeper
+        let nested = item.descendants().filter_map(ElementRef::wrap).filter(|nested| {
+            is_list(nested)
+                && nested.ancestors().filter_map(ElementRef::wrap).find(|ancestor| ancestor.value().name() == "li")
+                    .is_some_and(|owner| owner.id() == item.id())
+        });
+        for nested_list in nested {
+            render_list(nested_list, out);
+        }
+    }
+}
+
+/// Whether an extracted block has nothing worth keeping: only whitespace, or a
+/// lone bullet left behind by the editor's empty `<div>`/`<p>`/`<br>` wrappers
+fn is_blank_block(text: &str) -> bool {
+    matches!(text.trim(), "" | "•" | "·" | "-" | "*" | "◦" | "▪")
+}
+
+/// Decode HTML character references (`&amp;`, `&#39;`, `&#x2014;`, ...) and turn
+/// non-breaking spaces into regular ones
+///
+/// Unknown or malformed references are left as they are.
+fn decode_html_entities(text: &str) -> String {
+    let entity = regex::Regex::new(r"&(#[0-9]{1,7}|#[xX][0-9a-fA-F]{1,6}|[a-zA-Z]{2,8});").unwrap();
+
+    entity
+        .replace_all(text, |caps: &regex::Captures| {
+            let name = &caps[1];
+            let decoded = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => name.strip_prefix('#').and_then(|dec| dec.parse().ok()).and_then(char::from_u32),
+                },
+            };
+            decoded.map_or_else(|| caps[0].to_string(), |c| c.to_string())
+        })
+        .replace('\u{a0}', " ")
+}
+
+/// Text content of an element, with `<a href>` links rendered as Markdown
+///
+/// Child elements for which `skip` returns true are left out entirely.
+fn inline_text(element: ElementRef, skip: &dyn Fn(&ElementRef) -> bool) -> String {
+    let mut text = String::new();
+
+    for child in element.children() {
+        match child.value() {
+            Node::Text(content) => text.push_str(content),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else { continue };
+                if skip(&child) {
+                    continue;
+                }
+
+                let inner = inline_text(child, skip);
+                match child.value().attr("href") {
+                    Some(href) if child.value().name() == "a" => text.push_str(&markdown_link(&inner, href)),
+                    _ => text.push_str(&inner),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    text
+}
+
+/// Format a link as `[text](href)`, or the bare URL when the text adds nothing
+///
+/// Empty and `javascript:` hrefs are dropped, keeping only the text.
+fn markdown_link(text: &str, href: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let href = href.trim();
+
+    if href.is_empty() || href.to_lowercase().starts_with("javascript:") {
+        return text;
+    }
+
+    if text.is_empty() || text == href {
+        href.to_string()
+    } else {
+        format!("[{}]({})", text, href.replace(' ', "%20"))
+    }
+}
+
+/// Render one `<li>` as a Markdown list line
+///
+/// Items of an `<ol>` are numbered (honouring its `start` attribute), other items
+/// get a bullet. Nested lists are indented by two spaces per level; their items
+/// are rendered separately, so their text is left out here.
+fn html_list_item_to_markdown(item: ElementRef) -> Option<String> {
+    let is_list = |element: &ElementRef| matches!(element.value().name(), "ul" | "ol");
+
+    let text = inline_text(item, &is_list)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_empty() {
+        return None;
+    }
+
+    let depth = item.ancestors().filter_map(ElementRef::wrap).filter(is_list).count().saturating_sub(1);
+    let indent = "  ".repeat(depth);
+
+    let parent = item.parent().and_then(ElementRef::wrap);
+    let marker = match parent {
+        Some(list) if list.value().name() == "ol" => {
+            let start = list.value().attr("start").and_then(|start| start.trim().parse::<i64>().ok()).unwrap_or(1);
+            let position = item
+                .prev_siblings()
+                .filter_map(ElementRef::wrap)
+                .filter(|sibling| sibling.value().name() == "li")
+                .count() as i64;
+            format!("{}.", start + position)
+        }
+        _ => "•".to_string(),
+    };
+
+    Some(format!("{}{} {}", indent, marker, text))
+}
+
+/// Render an HTML table as a GitHub-flavored Markdown table
+///
+/// The first row is the header (GFM requires one). Short rows are padded with
+/// empty cells so every row has the same number of columns.
+fn html_table_to_markdown(table: ElementRef) -> String {
+    let row_selector = Selector::parse("tr").unwrap();
+
+    let rows: Vec<Vec<String>> = table
+        .select(&row_selector)
+        .map(|row| {
+            row.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| {
+                    inline_text(cell, &|_| false)
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .replace('|', "\\|")
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|cells| !cells.is_empty())
+        .collect();
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let format_row = |cells: &[String]| {
+        let padded = (0..columns).map(|idx| cells.get(idx).map_or("", String::as_str));
+        format!("| {} |", padded.collect::<Vec<_>>().join(" | "))
+    };
+
+    let mut lines = vec![format_row(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    lines.extend(rows[1..].iter().map(|row| format_row(row)));
+    lines.join("\n")
+}
+
 /// Clean a vector of HTML/Markdown content strings
 pub fn clean_text_content_list(content_list: &[String]) -> Vec<String> {
     content_list
@@ -48,15 +301,55 @@ pub fn clean_text_content_list(content_list: &[String]) -> Vec<String> {
         .collect()
 }
 
+/// Device names Windows won't allow as a file or folder name, whatever the extension
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul",
+    "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+    "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Kebab-case slug of the first 8 words of a title, safe as a file or folder name on every OS
+///
+/// Keeps letters, digits and dashes; never ends in a dot, dash or space, and
+/// reserved Windows names (CON, PRN, ...) get an `-item` suffix. Empty when the
+/// title has nothing usable, so callers should combine it with the ticket ID.
+pub fn title_slug(title: &str) -> String {
+    let slug = title
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace() || *c == '-')
+        .collect::<String>()
+        .split_whitespace()
+        .take(8) // Limit to 8 words
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = slug.trim_matches(|c: char| c == '-' || c == '.' || c.is_whitespace());
+
+    if WINDOWS_RESERVED_NAMES.contains(&slug) {
+        format!("{}-item", slug)
+    } else {
+        slug.to_string()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkItem {
     pub id: u32,
+    /// Revision number the fields were read from
+    pub revision: u32,
     pub title: String,
     pub description: String,
     pub acceptance_criteria: Vec<String>,
     pub comments: Vec<Comment>,
     pub attachments: Vec<Attachment>,
     pub images: Vec<ImageReference>,
+    pub links: Vec<WorkItemLink>,
+    /// Pull requests linked to the work item
+    #[serde(default)]
+    pub pull_requests: Vec<PullRequestRef>,
+    /// Ancestors fetched with `--include-parents`, nearest parent first
+    #[serde(default)]
+    pub parents: Vec<ParentWorkItem>,
     pub created_date: DateTime<Utc>,
     pub updated_date: DateTime<Utc>,
     pub created_by: User,
@@ -65,6 +358,17 @@ pub struct WorkItem {
     pub work_item_type: String,
     pub area_path: String,
     pub iteration_path: String,
+    /// Start date of the work item's iteration, when the iteration has dates
+    #[serde(default)]
+    pub iteration_start: Option<DateTime<Utc>>,
+    /// Finish date of the work item's iteration, when the iteration has dates
+    #[serde(default)]
+    pub iteration_end: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    /// Values of the configured custom fields, keyed by reference name
+    pub custom_fields: HashMap<String, serde_json::Value>,
+    /// All raw field values from Azure DevOps, keyed by reference name
+    pub fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,12 +384,20 @@ pub struct Comment {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Attachment {
     pub id: u32,
+    /// Name the file was saved under; may have an extension added from the content type
     pub filename: String,
+    /// Name as reported by Azure DevOps
+    pub original_filename: String,
+    #[serde(alias = "original_url")]
     pub url: String,
     pub local_path: String,
     pub content_type: String,
+    #[serde(alias = "size_bytes")]
     pub size: u64,
     pub created_date: DateTime<Utc>,
+    /// True when the file was intentionally not downloaded (see `skip_reason`)
+    pub skipped: bool,
+    pub skip_reason: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,12 +408,115 @@ pub struct ImageReference {
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub alt_text: Option<String>,
+    /// Set when this image is identical to one already saved for the work item:
+    /// the saved file's path relative to the `images` folder. `local_path` then
+    /// points at that file and no separate copy is stored.
+    #[serde(default)]
+    pub alias_of: Option<String>,
+}
+
+/// A link from a work item to another work item (parent, child, related, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkItemLink {
+    /// ID of the linked work item
+    pub id: u32,
+    /// Azure link type reference name, e.g. `System.LinkTypes.Hierarchy-Reverse`
+    pub link_type: String,
+    /// Friendly link name from the relation attributes, e.g. "Parent" or "Child"
+    pub name: Option<String>,
+    pub url: String,
+}
+
+impl WorkItemLink {
+    /// Build a link from a relation that points at another work item
+    ///
+    /// Returns `None` for non-work-item relations such as attachments and hyperlinks.
+    pub fn from_relation(relation: &AzureRelation) -> Option<Self> {
+        if relation.rel == "AttachedFile" || relation.rel == "Hyperlink" || relation.rel == "ArtifactLink" {
+            return None;
+        }
+
+        let (path, id) = relation.url.trim_end_matches('/').rsplit_once('/')?;
+        if !path.to_lowercase().ends_with("/_apis/wit/workitems") {
+            return None;
+        }
+
+        Some(Self {
+            id: id.parse().ok()?,
+            link_type: relation.rel.clone(),
+            name: relation.attributes.as_ref().and_then(|attributes| attributes.name.clone()),
+            url: relation.url.clone(),
+        })
+    }
+
+    /// Whether this link points at the work item's parent
+    pub fn is_parent(&self) -> bool {
+        self.link_type == "System.LinkTypes.Hierarchy-Reverse"
+    }
+
+    /// Whether this link points at one of the work item's children
+    pub fn is_child(&self) -> bool {
+        self.link_type == "System.LinkTypes.Hierarchy-Forward"
+    }
+}
+
+/// An ancestor of a work item, fetched without attachments or comments for prompt context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentWorkItem {
+    pub id: u32,
+    pub title: String,
+    pub work_item_type: String,
+    /// Raw HTML description; not written to metadata.json
+    #[serde(default)]
+    pub description: String,
+}
+
+/// A pull request linked to a work item through an `ArtifactLink` relation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullRequestRef {
+    pub project_id: String,
+    pub repository_id: String,
+    pub pull_request_id: u32,
+    /// Friendly link name from the relation attributes, usually "Pull Request"
+    pub name: Option<String>,
+    /// The `vstfs:///Git/PullRequestId/...` artifact URI
+    pub url: String,
+}
+
+impl PullRequestRef {
+    /// Build a pull request reference from an artifact link
+    ///
+    /// The URI looks like `vstfs:///Git/PullRequestId/{project}%2F{repository}%2F{id}`;
+    /// returns `None` for every other relation, including links to commits and branches.
+    pub fn from_relation(relation: &AzureRelation) -> Option<Self> {
+        const PREFIX: &str = "vstfs:///git/pullrequestid/";
+        if relation.rel != "ArtifactLink" || !relation.url.to_lowercase().starts_with(PREFIX) {
+            return None;
+        }
+
+        let artifact = relation.url[PREFIX.len()..].replace("%2F", "/").replace("%2f", "/");
+        let mut parts = artifact.split('/');
+        let (project_id, repository_id, pull_request_id) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Self {
+            project_id: project_id.to_string(),
+            repository_id: repository_id.to_string(),
+            pull_request_id: pull_request_id.parse().ok()?,
+            name: relation.attributes.as_ref().and_then(|attributes| attributes.name.clone()),
+            url: relation.url.clone(),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub display_name: String,
     pub email: String,
+    /// Not written to the saved ticket files
+    #[serde(default)]
     pub url: String,
 }
 
@@ -117,6 +532,37 @@ pub struct AzureWorkItemResponse {
     pub url: String,
     #[serde(rename = "_links")]
     pub links: serde_json::Value,
+    /// The untouched JSON this was parsed from, kept only when raw responses are saved
+    #[serde(skip)]
+    pub raw: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AzureWorkItemBatchResponse {
+    /// Entries are null for IDs omitted by the batch error policy; parsed with
+    /// `AzureDevOpsClient::parse_work_item` so the raw JSON can be kept
+    pub value: Vec<Option<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AzureWiqlResponse {
+    /// Populated by flat queries
+    #[serde(rename = "workItems", default)]
+    pub work_items: Vec<AzureWorkItemReference>,
+    /// Populated by tree and one-hop link queries instead of `workItems`
+    #[serde(rename = "workItemRelations", default)]
+    pub work_item_relations: Vec<AzureWorkItemLinkReference>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AzureWorkItemReference {
+    pub id: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AzureWorkItemLinkReference {
+    pub source: Option<AzureWorkItemReference>,
+    pub target: Option<AzureWorkItemReference>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -134,10 +580,29 @@ pub struct AzureRelationAttributes {
     pub authorized_date: Option<String>,
 }
 
+/// A node of the project's iteration tree, from `wit/classificationnodes/Iterations`
+#[derive(Debug, Deserialize)]
+pub struct AzureClassificationNode {
+    /// Missing for iterations without dates
+    #[serde(default)]
+    pub attributes: Option<AzureIterationAttributes>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AzureIterationAttributes {
+    #[serde(rename = "startDate")]
+    pub start_date: Option<DateTime<Utc>>,
+    #[serde(rename = "finishDate")]
+    pub finish_date: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AzureCommentsResponse {
     pub count: u32,
     pub value: Vec<AzureComment>,
+    /// Present when more pages of comments are available
+    #[serde(rename = "continuationToken")]
+    pub continuation_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -156,6 +621,9 @@ pub struct AzureComment {
 pub struct AzureUser {
     pub displayName: String,
     pub url: String,
+    /// Sign-in name: the email address for Entra ID accounts, `DOMAIN\user` on-prem
+    #[serde(rename = "uniqueName", default)]
+    pub unique_name: Option<String>,
     #[serde(rename = "_links")]
     pub links: serde_json::Value,
 }
@@ -171,34 +639,190 @@ pub struct OpenSpecPlanData {
     pub complexity: String,
     pub dependencies: Vec<String>,
     pub estimated_effort: Option<String>,
+    /// Iteration name and dates, e.g. "Sprint 5 (2024-06-03 to 2024-06-14)"
+    pub iteration: Option<String>,
     pub attachments_count: usize,
     pub comments_count: usize,
+    /// Latest cleaned comments, oldest first, as "Author (date): text"
+    pub recent_comments: Vec<String>,
+    /// Ancestors as "Type #id: title - summary", nearest parent first
+    pub parent_context: Vec<String>,
     pub has_images: bool,
+    /// OpenSpec folder relative to the project (`storage.openspec_subdir`), named in the built-in prompt
+    pub openspec_dir: String,
+}
+
+/// Placeholders a custom prompt template must contain, or the AI never sees the ticket
+const REQUIRED_PROMPT_PLACEHOLDERS: &[&str] = &["description", "acceptance_criteria"];
+
+/// User-supplied prompt text loaded from `openspec.prompt_template_path`
+///
+/// Placeholders: `{ticket_number}`, `{ticket_title}`, `{description}`,
+/// `{acceptance_criteria}`, and optionally `{discussion}`, `{parent_context}`,
+/// `{iteration}` and `{estimated_effort}`; the optional ones are empty when the
+/// work item has no such data. Any other text in braces is left as is.
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    text: String,
+}
+
+impl PromptTemplate {
+    /// Read the template at `path`, checking it has the required placeholders
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let path = crate::config::expand_path(path);
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("could not read '{}': {}", path, e))?;
+
+        let missing: Vec<String> = REQUIRED_PROMPT_PLACEHOLDERS
+            .iter()
+            .filter(|name| !text.contains(&format!("{{{}}}", name)))
+            .map(|name| format!("{{{}}}", name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!("'{}' is missing the {} placeholder(s)", path, missing.join(", ")));
+        }
+
+        Ok(Self { text })
+    }
+
+    /// Substitute `{name}` placeholders in one pass, so values containing braces stay untouched
+    fn render(&self, values: &[(&str, &str)]) -> String {
+        let mut rendered = String::with_capacity(self.text.len());
+        let mut rest = self.text.as_str();
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let placeholder = values
+                .iter()
+                .find(|(name, _)| after.starts_with(name) && after[name.len()..].starts_with('}'));
+            match placeholder {
+                Some((name, value)) => {
+                    rendered.push_str(value);
+                    rest = &after[name.len() + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = after;
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
 }
 
 impl OpenSpecPlanData {
-    pub fn generate_prompt(&self) -> String {
+    /// Build the AI prompt, keeping the ticket content within `max_content_chars` (0 = no limit)
+    ///
+    /// When the description, acceptance criteria and comments together are over
+    /// budget, the oldest comments are dropped first and then the description is
+    /// shortened; the prompt then says that content was truncated.
+    /// A custom `template` replaces the built-in prompt text.
+    pub fn generate_prompt(&self, max_content_chars: usize, template: Option<&PromptTemplate>) -> String {
         // Debug logging to see what we're working with
         tracing::debug!("generate_prompt: ticket_description length: {}", self.ticket_description.len());
-        tracing::debug!("generate_prompt: ticket_description preview: {}", &self.ticket_description[..self.ticket_description.len().min(100)]);
+        tracing::debug!("generate_prompt: ticket_description preview: {}", self.ticket_description.chars().take(100).collect::<String>());
 
-        // The description should already be cleaned from generate_openspec_plan_data()
-        let cleaned_acceptance_criteria = clean_text_content_list(&self.acceptance_criteria);
+        // The description and criteria should already be cleaned by generate_openspec_plan_data()
+        let cleaned_acceptance_criteria = &self.acceptance_criteria;
 
         tracing::debug!("generate_prompt: Using pre-cleaned description of length: {}", self.ticket_description.len());
 
-        format!(
+        let mut description = self.ticket_description.clone();
+        let mut recent_comments = self.recent_comments.clone();
+
+        let criteria_chars: usize = cleaned_acceptance_criteria.iter().map(|ac| ac.chars().count()).sum();
+        let content_chars = |description: &str, comments: &[String]| {
+            description.chars().count() + criteria_chars + comments.iter().map(|c| c.chars().count()).sum::<usize>()
+        };
+
+        let original_chars = content_chars(&description, &recent_comments);
+        let truncated = max_content_chars > 0 && original_chars > max_content_chars;
+
+        if truncated {
+            while !recent_comments.is_empty() && content_chars(&description, &recent_comments) > max_content_chars {
+                recent_comments.remove(0);
+            }
+
+            let over_budget = content_chars(&description, &recent_comments).saturating_sub(max_content_chars);
+            if over_budget > 0 {
+                let room = description.chars().count().saturating_sub(over_budget);
+                description = crate::ui::format::truncate_to_paragraph(&description, room);
+            }
+
+            tracing::warn!("Prompt content for #{} truncated from {} to {} chars (max_prompt_chars = {})",
+                self.ticket_number, original_chars, content_chars(&description, &recent_comments), max_content_chars);
+        }
+
+        let acceptance_criteria = if cleaned_acceptance_criteria.is_empty() {
+            "No explicit acceptance criteria specified".to_string()
+        } else {
+            cleaned_acceptance_criteria
+                .iter()
+                .enumerate()
+                .map(|(i, ac)| format!("{}. {}", i + 1, ac))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let discussion = if recent_comments.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\n**Discussion Context:**\nRecent comments on the work item (oldest first); they may clarify or override the description.\n{}\n",
+                recent_comments
+                    .iter()
+                    .map(|comment| format!("- {}", comment))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        };
+        let parent_context = if self.parent_context.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\n**Parent Context:**\nThe work items this ticket belongs to, nearest first; use them to understand the wider goal, but plan only this ticket.\n{}\n",
+                self.parent_context
+                    .iter()
+                    .map(|parent| format!("- {}", parent))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        };
+        let truncation_note = if truncated {
+            "\n_Note: the ticket content above was truncated to fit the prompt size limit; \
+             the full text is saved in the ticket folder._\n"
+        } else {
+            ""
+        };
+
+        if let Some(template) = template {
+            let ticket_number = self.ticket_number.to_string();
+            let iteration = self.iteration.as_deref().unwrap_or_default();
+            let estimated_effort = self.estimated_effort.as_deref().unwrap_or_default();
+            return template.render(&[
+                ("ticket_number", &ticket_number),
+                ("ticket_title", &self.ticket_title),
+                ("description", &description),
+                ("acceptance_criteria", &acceptance_criteria),
+                ("discussion", &discussion),
+                ("parent_context", &parent_context),
+                ("iteration", iteration),
+                ("estimated_effort", estimated_effort),
+            ]) + truncation_note;
+        }
+
+        let prompt = format!(
             "You are creating a comprehensive OpenSpec implementation plan for the following Azure DevOps work item.
 Follow the complete OpenSpec methodology with proper three-stage workflow, directory structures, and spec formatting.
 
 **Ticket #{}: {}**
-
+{}
 **Description:**
 {}
 
 **Acceptance Criteria:**
 {}
-
+{}
 IMPORTANT OUTPUT FORMAT:
 Your response should contain ONLY the actual content, NOT markdown formatting examples or instructions.
 
@@ -246,6 +870,9 @@ OPTIONALLY, if this requires new capabilities or modifications to existing ones,
 ## REMOVED Requirements
 [Only if removing requirements]
 
+If the deltas touch more than one capability, put a `### Capability: [capability-name]` header
+(kebab-case) before each capability's ADDED/MODIFIED/REMOVED sections.
+
 DO NOT include markdown code fences, examples, or meta-instructions in your output.
 DO NOT start your response with ```markdown or any code fence.
 Output the actual plan content directly.
@@ -327,6 +954,9 @@ Include design.md only if ANY of these apply:
 - Security, performance, or migration complexity
 - Ambiguity that benefits from technical decisions
 
+When included, put it under a `## Design` heading with `### Context`, `### Decisions` and
+`### Risks / Trade-offs` subsections so it can be saved as design.md.
+
 ### 6. Three-Stage Workflow Plan
 **Stage 1: Creating Changes**
 - Scaffold all files under openspec/changes/[change-id]/
@@ -369,33 +999,26 @@ Include design.md only if ANY of these apply:
 Generate a complete, practical OpenSpec plan following this methodology. Focus on what needs to be built, how it will be tested, and how the change will be managed through the full OpenSpec workflow.",
             self.ticket_number,
             self.ticket_title,
-            self.ticket_description,
-            if cleaned_acceptance_criteria.is_empty() {
-                "No explicit acceptance criteria specified".to_string()
-            } else {
-                cleaned_acceptance_criteria
-                    .iter()
-                    .enumerate()
-                    .map(|(i, ac)| format!("{}. {}", i + 1, ac))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            }
-        )
-    }
-
-    pub fn generate_filename(&self) -> String {
-        // Create a concise title-based filename
-        let concise_title = self.ticket_title
-            .chars()
-            .filter(|c| c.is_alphanumeric() || c.is_whitespace() || *c == '-')
-            .collect::<String>()
-            .split_whitespace()
-            .take(8) // Limit to 8 words
-            .map(|word| word.to_lowercase())
-            .collect::<Vec<_>>()
-            .join("-");
-
-        format!("{}-{}.md", self.ticket_number, concise_title)
+            self.estimated_effort
+                .as_ref()
+                .map(|effort| format!("\n**Estimated Effort:** {}\n", effort))
+                .unwrap_or_default()
+                + &self.iteration
+                    .as_ref()
+                    .map(|iteration| format!("\n**Iteration:** {}\n", iteration))
+                    .unwrap_or_default(),
+            description,
+            acceptance_criteria,
+            discussion + &parent_context + truncation_note
+        );
+
+        // The built-in text names the default folder; point it at the configured one
+        match self.openspec_dir.as_str() {
+            "openspec" => prompt,
+            dir => prompt
+                .replace("openspec/changes/", &format!("{}/changes/", dir))
+                .replace("openspec/project.md", &format!("{}/project.md", dir)),
+        }
     }
 }
 
@@ -440,6 +1063,18 @@ impl From<AzureWorkItemResponse> for WorkItem {
             .unwrap_or("")
             .to_string();
 
+        // Tags are stored as a single semicolon-separated string
+        let tags = fields
+            .get("System.Tags")
+            .and_then(|v| v.as_str())
+            .map(|tags| {
+                tags.split(';')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let created_date = fields
             .get("System.CreatedDate")
             .and_then(|v| v.as_str())
@@ -478,16 +1113,27 @@ impl From<AzureWorkItemResponse> for WorkItem {
             });
 
         // Extract acceptance criteria from description or custom field
-        let acceptance_criteria = extract_acceptance_criteria(&description);
+        // Prefer the dedicated Acceptance Criteria field; many teams leave it empty
+        // and write criteria into the description instead
+        let acceptance_criteria = fields
+            .get("Microsoft.VSTS.Common.AcceptanceCriteria")
+            .and_then(|v| v.as_str())
+            .map(split_acceptance_criteria)
+            .filter(|criteria| !criteria.is_empty())
+            .unwrap_or_else(|| extract_acceptance_criteria(&description));
 
         Self {
             id: azure_item.id,
+            revision: azure_item.revision,
             title,
             description,
             acceptance_criteria,
             comments: Vec::new(), // Will be populated separately
             attachments: Vec::new(), // Will be populated from relations
             images: Vec::new(), // Will be extracted from description
+            links: Vec::new(), // Will be populated from relations
+            pull_requests: Vec::new(), // Will be populated from relations
+            parents: Vec::new(), // Only fetched with --include-parents
             created_date,
             updated_date,
             created_by,
@@ -496,10 +1142,26 @@ impl From<AzureWorkItemResponse> for WorkItem {
             work_item_type,
             area_path,
             iteration_path,
+            iteration_start: None, // Looked up from the iteration's classification node
+            iteration_end: None,
+            tags,
+            custom_fields: HashMap::new(), // Populated from the configured field list
+            fields,
         }
     }
 }
 
+/// Split the HTML of the Acceptance Criteria field into one criterion per list item or paragraph
+fn split_acceptance_criteria(html: &str) -> Vec<String> {
+    let list_marker = regex::Regex::new(r"^(?:[•*-]|\d+[.)])\s+").unwrap();
+
+    clean_html_content(html)
+        .lines()
+        .map(|line| list_marker.replace(line.trim(), "").trim().to_string())
+        .filter(|criterion| !criterion.is_empty())
+        .collect()
+}
+
 fn extract_acceptance_criteria(description: &str) -> Vec<String> {
     // Look for acceptance criteria patterns in the description
     let ac_patterns = [
@@ -537,4 +1199,193 @@ fn extract_acceptance_criteria(description: &str) -> Vec<String> {
     }
 
     Vec::new()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_becomes_markdown_table() {
+        let html = "<table><tr><th>Name</th><th>Value</th></tr><tr><td>timeout</td><td>30</td></tr></table>";
+        assert_eq!(
+            clean_html_content(html),
+            "| Name | Value |\n| --- | --- |\n| timeout | 30 |"
+        );
+    }
+
+    #[test]
+    fn table_in_azure_div_wrapper_is_rendered_once() {
+        let html = "<div><table><tr><th>A</th><th>B</th></tr><tr><td>1</td><td>2</td></tr></table></div>";
+        assert_eq!(clean_html_content(html), "| A | B |\n| --- | --- |\n| 1 | 2 |");
+    }
+
+    #[test]
+    fn ragged_table_rows_are_padded() {
+        let html = "<table><tr><td>a</td><td>b</td><td>c</td></tr><tr><td>1</td></tr></table>";
+        assert_eq!(clean_html_content(html), "| a | b | c |\n| --- | --- | --- |\n| 1 |  |  |");
+    }
+
+    #[test]
+    fn table_cells_escape_pipes() {
+        let html = "<table><tr><td>a|b</td></tr></table>";
+        assert_eq!(clean_html_content(html), "| a\\|b |\n| --- |");
+    }
+
+    #[test]
+    fn text_around_a_table_keeps_its_order() {
+        let html = "<div>Before<table><tr><td>x</td></tr></table>After</div>";
+        assert_eq!(clean_html_content(html), "Before\n\n| x |\n| --- |\n\nAfter");
+    }
+
+    #[test]
+    fn ordered_list_items_are_numbered() {
+        let html = "<ol><li>first</li><li>second</li></ol>";
+        assert_eq!(clean_html_content(html), "1. first\n2. second");
+    }
+
+    #[test]
+    fn ordered_list_honours_start() {
+        let html = "<ol start=\"4\"><li>four</li><li>five</li></ol>";
+        assert_eq!(clean_html_content(html), "4. four\n5. five");
+    }
+
+    #[test]
+    fn nested_mixed_lists_are_indented() {
+        let html = "<ol><li>one<ul><li>sub a</li><li>sub b<ol><li>deep</li></ol></li></ul></li><li>two</li></ol>";
+        assert_eq!(
+            clean_html_content(html),
+            "1. one\n  • sub a\n  • sub b\n    1. deep\n2. two"
+        );
+    }
+
+    #[test]
+    fn nested_list_in_azure_div_wrapper_is_rendered_once() {
+        let html = "<div><ol><li>one<ul><li>sub</li></ul></li><li>two</li></ol></div>";
+        assert_eq!(clean_html_content(html), "1. one\n  • sub\n2. two");
+    }
+
+    #[test]
+    fn list_nested_in_a_div_inside_an_item_keeps_its_order() {
+        let html = "<ul><li>parent<div><ul><li>child</li></ul></div></li><li>sibling</li></ul>";
+        assert_eq!(clean_html_content(html), "• parent\n  • child\n• sibling");
+    }
+
+    #[test]
+    fn title_slug_keeps_the_first_eight_words() {
+        assert_eq!(
+            title_slug("Add SSO login to the admin portal for all tenants: phase 1"),
+            "add-sso-login-to-the-admin-portal-for"
+        );
+    }
+
+    #[test]
+    fn title_slug_keeps_unicode_letters_and_drops_symbols() {
+        assert_eq!(title_slug("Café menü: 日本語 support 🚀"), "café-menü-日本語-support");
+        assert_eq!(title_slug("🚀🎉 !!!"), "");
+    }
+
+    #[test]
+    fn title_slug_trims_trailing_dots_and_dashes() {
+        assert_eq!(title_slug("Fix login..."), "fix-login");
+        assert_eq!(title_slug("- Fix login -"), "fix-login");
+        assert_eq!(title_slug("v2.0."), "v20");
+    }
+
+    #[test]
+    fn title_slug_renames_reserved_windows_names() {
+        assert_eq!(title_slug("CON"), "con-item");
+        assert_eq!(title_slug("lpt1."), "lpt1-item");
+        assert_eq!(title_slug("Console"), "console");
+    }
+
+    #[test]
+    fn acceptance_criteria_from_div_wrapped_list() {
+        let html = "<div><ul><li>a</li><li>b</li></ul></div>";
+        assert_eq!(split_acceptance_criteria(html), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn acceptance_criteria_from_numbered_paragraphs() {
+        let html = "<div><div>1. Users can sign in</div><div>2) Errors are shown</div><div><br></div></div>";
+        assert_eq!(split_acceptance_criteria(html), vec!["Users can sign in", "Errors are shown"]);
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        assert_eq!(clean_html_content("<p>Tom &amp; Jerry</p>"), "Tom & Jerry");
+        assert_eq!(clean_html_content("Tom &amp; Jerry"), "Tom & Jerry");
+        assert_eq!(clean_html_content("<p>it&#39;s&nbsp;&lt;here&gt;</p>"), "it's <here>");
+    }
+
+    fn plan_data(iteration: Option<&str>, estimated_effort: Option<&str>) -> OpenSpecPlanData {
+        OpenSpecPlanData {
+            ticket_number: 42,
+            ticket_title: "Speed up login".to_string(),
+            ticket_description: "Login takes 8s.".to_string(),
+            acceptance_criteria: vec!["Login under 1s".to_string()],
+            priority: "2".to_string(),
+            complexity: "Medium".to_string(),
+            dependencies: Vec::new(),
+            estimated_effort: estimated_effort.map(str::to_string),
+            iteration: iteration.map(str::to_string),
+            attachments_count: 0,
+            comments_count: 0,
+            recent_comments: Vec::new(),
+            parent_context: Vec::new(),
+            has_images: false,
+            openspec_dir: "openspec".to_string(),
+        }
+    }
+
+    #[test]
+    fn template_fills_iteration_and_effort() {
+        let template = PromptTemplate {
+            text: "#{ticket_number} {ticket_title}\nSprint: {iteration}\nEffort: {estimated_effort}\n{description}\n{acceptance_criteria}".to_string(),
+        };
+
+        let prompt = plan_data(Some("Sprint 5 (2024-06-03 to 2024-06-14)"), Some("5 points")).generate_prompt(0, Some(&template));
+        assert_eq!(
+            prompt,
+            "#42 Speed up login\nSprint: Sprint 5 (2024-06-03 to 2024-06-14)\nEffort: 5 points\nLogin takes 8s.\n1. Login under 1s"
+        );
+
+        // Missing data leaves the placeholders empty rather than in the prompt
+        let prompt = plan_data(None, None).generate_prompt(0, Some(&template));
+        assert!(prompt.starts_with("#42 Speed up login\nSprint: \nEffort: \n"), "{}", prompt);
+    }
+
+    #[test]
+    fn long_description_is_cut_to_the_budget_at_a_paragraph_break() {
+        let mut data = plan_data(None, None);
+        data.ticket_description = (0..70).map(|i| format!("Paragraph {:02}: {}", i, "x".repeat(986))).collect::<Vec<_>>().join("\n\n");
+        data.recent_comments = vec!["Ada (2024-06-04): profiled it".to_string()];
+        let template = PromptTemplate { text: "{description}".to_string() };
+
+        let description = data.generate_prompt(60_000, Some(&template));
+        let description = description.split("\n_Note:").next().unwrap();
+        let criteria_chars = "Login under 1s".len();
+        let content_chars = description.chars().count() + criteria_chars;
+
+        assert!(content_chars <= 60_000, "{} chars", content_chars);
+        assert!(content_chars > 60_000 - 1_002, "only {} chars kept", content_chars);
+        assert!(description.contains("Paragraph 58:") && !description.contains("Paragraph 59:"));
+    }
+
+    #[test]
+    fn azure_description_blob_collapses_to_clean_markdown() {
+        let html = "<div><div><div></div><p>As a user&nbsp;I want to sign in faster.</p><div><br></div>\
+<div><b>Notes</b><br>Seen on&nbsp;Chrome<br><br></div><div>&nbsp;</div>\
+<ul><li>Cache the token</li><li></li><li>&nbsp;</li><li>Skip the <i>second</i> redirect</li></ul>\
+<div><div><br></div></div><p></p></div></div>";
+        assert_eq!(
+            clean_html_content(html),
+            "As a user I want to sign in faster.\nNotes\nSeen on Chrome\n• Cache the token\n• Skip the second redirect"
+        );
+    }
+
+    #[test]
+    fn escaped_entities_are_decoded_only_once() {
+        assert_eq!(clean_html_content("<p>write &amp;amp; for &amp;</p>"), "write &amp; for &");
+    }
+}
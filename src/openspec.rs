@@ -1,24 +1,73 @@
 use anyhow::{anyhow, Result};
+use crate::ai::{self, AiTimeout};
+use crate::api::HttpClientOptions;
 use crate::config::OpenSpecConfig;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tracing::{debug, info, warn, error};
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::ProgressBar;
 use std::time::Duration;
 use colored::Colorize;
 
+/// Pause between AI command attempts
+const AI_RETRY_DELAY_MS: u64 = 2000;
+
+/// Shortest AI output that can hold the proposal/tasks sections
+const MIN_PLAN_LENGTH: usize = 100;
+
+/// Verbs accepted at the start of a change ID suggested by the AI
+const CHANGE_VERBS: &[&str] = &["add", "update", "fix", "refactor", "remove", "improve", "migrate", "deprecate"];
+
+/// Result of running `openspec validate`
+pub enum ValidationOutcome {
+    Passed,
+    /// Validation failed; one entry per reported issue
+    Failed(Vec<String>),
+    /// The openspec CLI isn't installed
+    Skipped,
+}
+
 pub struct OpenSpecManager {
+    /// Folder the openspec CLI runs in: the parent of `openspec_dir`
     base_path: String,
+    /// The OpenSpec folder itself, `{base}/{openspec_subdir}`
+    openspec_dir: String,
+    /// Proxy and TLS settings for the HTTP AI providers
+    http_options: HttpClientOptions,
 }
 
 impl OpenSpecManager {
-    pub fn new(base_path: &str) -> Self {
+    /// Manage the OpenSpec folder at `{base_path}/{openspec_subdir}`
+    pub fn new(base_path: &str, openspec_subdir: &str) -> Self {
+        let openspec_dir = format!("{}/{}", base_path, openspec_subdir.trim_matches(['/', '\\']));
+        let cli_root = Path::new(&openspec_dir)
+            .parent()
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or_else(|| base_path.to_string());
+
         Self {
-            base_path: base_path.to_string(),
+            base_path: cli_root,
+            openspec_dir,
+            http_options: HttpClientOptions::default(),
         }
     }
 
+    /// Reach the OpenAI/Ollama providers through these proxy and TLS settings
+    pub fn with_http_options(mut self, options: HttpClientOptions) -> Self {
+        self.http_options = options;
+        self
+    }
+
+    /// Whether the openspec CLI can work on `openspec_dir`
+    ///
+    /// The CLI always uses a folder named `openspec` in its working directory, so
+    /// a subdir with another final name (e.g. "specs") is managed without it:
+    /// folders are created directly, validation is skipped and changes are listed from disk.
+    fn cli_supported(&self) -> bool {
+        Path::new(&self.openspec_dir).file_name().is_some_and(|name| name == "openspec")
+    }
+
     /// Get the correct openspec command for the current platform
     fn get_openspec_command(&self) -> String {
         if cfg!(windows) {
@@ -30,19 +79,28 @@ impl OpenSpecManager {
     }
 
     pub async fn ensure_openspec_initialized(&self) -> Result<()> {
-        let openspec_dir = format!("{}/openspec", self.base_path);
+        let openspec_dir = &self.openspec_dir;
 
-        if Path::new(&openspec_dir).exists() {
+        if Path::new(openspec_dir).exists() {
             info!("OpenSpec is already initialized at {}", openspec_dir);
 
             // Update OpenSpec instructions to ensure they're current
-            self.run_openspec_update()?;
+            if self.cli_supported() {
+                self.run_openspec_update()?;
+            }
+
+            return Ok(());
+        }
 
+        if !self.cli_supported() {
+            info!("Creating OpenSpec folder {} (not named 'openspec', so without 'openspec init')", openspec_dir);
+            fs::create_dir_all(format!("{}/changes", openspec_dir))?;
+            fs::create_dir_all(format!("{}/specs", openspec_dir))?;
             return Ok(());
         }
 
         info!("Initializing OpenSpec at {}", openspec_dir);
-        self.run_openspec_init(&openspec_dir).await
+        self.run_openspec_init(openspec_dir).await
     }
 
     fn run_openspec_update(&self) -> Result<()> {
@@ -104,132 +162,102 @@ impl OpenSpecManager {
         }
     }
 
-    pub async fn generate_plan_with_ai(&self, prompt: &str, config: &OpenSpecConfig) -> Result<String> {
+    /// Run the configured AI provider on `prompt`, showing `spinner` while it works
+    pub async fn generate_plan_with_ai(&self, prompt: &str, config: &OpenSpecConfig, spinner: ProgressBar) -> Result<String> {
         debug!("Generating OpenSpec plan using AI command with prompt length: {}", prompt.len());
 
-        // Create a minimal spinner
-        let spinner = ProgressBar::new_spinner();
-        spinner.set_style(
-            ProgressStyle::default_spinner()
-                .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
-                .template("{spinner:.cyan} {msg}")
-                .unwrap()
-        );
-        spinner.enable_steady_tick(Duration::from_millis(100));
-        spinner.set_message("");
-
-        // Replace {prompt} placeholder in the command template
-        let command_with_prompt = config.ai_command_template.replace("{prompt}", prompt);
+        let provider = ai::create_provider(config, &self.http_options)?;
 
-        debug!("Executing AI command: {}", command_with_prompt);
-        debug!("AI command template: {}", config.ai_command_template);
-        debug!("Prompt preview (first 200 chars): {}", &prompt[..prompt.len().min(200)]);
+        debug!("Generating plan with {}", provider.name());
+        debug!("Prompt preview (first 200 chars): {}", prompt.chars().take(200).collect::<String>());
         debug!("Full prompt length: {} chars", prompt.len());
         debug!("FULL PROMPT CONTENT:\n{}", prompt);
 
-        // Use temp file approach - best for long/multi-line prompts with special characters
-        let output_result = {
-            if cfg!(windows) {
-                // Windows: Write prompt to temp file and use PowerShell to execute
-                let temp_dir = std::env::temp_dir();
-                let prompt_file = temp_dir.join(format!("bakery_prompt_{}.txt", std::process::id()));
-                let script_file = temp_dir.join(format!("bakery_script_{}.ps1", std::process::id()));
-
-                use std::io::Write;
-
-                // Write the prompt to a temp file
-                std::fs::write(&prompt_file, prompt)
-                    .map_err(|e| anyhow!("Failed to write prompt file: {}", e))?;
-
-                // Create PowerShell script that reads the prompt and passes to claude via stdin
-                let ps_script = format!(
-                    r#"Get-Content -Path '{}' -Raw | claude.cmd --print
-"#,
-                    prompt_file.display().to_string().replace("\\", "\\\\")
-                );
-
-                std::fs::write(&script_file, ps_script)
-                    .map_err(|e| anyhow!("Failed to write PowerShell script: {}", e))?;
-
-                debug!("Executing PowerShell script: {}", script_file.display());
-
-                let output = Command::new("powershell.exe")
-                    .args(&[
-                        "-NoProfile",
-                        "-NonInteractive",
-                        "-ExecutionPolicy", "Bypass",
-                        "-File", script_file.to_str().unwrap()
-                    ])
-                    .output()
-                    .map_err(|e| anyhow!("Failed to execute PowerShell script: {}", e))?;
-
-                // Clean up temp files
-                let _ = std::fs::remove_file(&prompt_file);
-                let _ = std::fs::remove_file(&script_file);
-
-                Ok(output)
-            } else {
-                // Unix: Use heredoc approach
-                let temp_file_str = format!("/tmp/bakery_prompt_{}.txt", std::process::id());
-                std::fs::create_dir_all("/tmp")
-                    .map_err(|e| anyhow!("Failed to create temp directory: {}", e))?;
-
-                use std::io::Write;
-                let mut file = std::fs::File::create(&temp_file_str)
-                    .map_err(|e| anyhow!("Failed to create temp file: {}", e))?;
-
-                // Write heredoc wrapper script
-                let heredoc_script = format!(
-                    r#"claude -p <<'EOF'
-{}
-EOF
-"#,
-                    prompt
-                );
-
-                file.write_all(heredoc_script.as_bytes())
-                    .map_err(|e| anyhow!("Failed to write heredoc script: {}", e))?;
-                file.flush().map_err(|e| anyhow!("Failed to flush temp file: {}", e))?;
-                drop(file); // Explicitly drop the file handle to release the lock
-
-                Command::new("sh")
-                    .arg(&temp_file_str)
-                    .output()
-                    .map_err(|e| anyhow!("Failed to execute heredoc script: {}", e))
+        // Re-run the provider when it fails or returns something that can't be a plan
+        let attempts = config.ai_max_retries + 1;
+        let timeout = Duration::from_secs(config.ai_timeout_seconds);
+        let mut last_failure = String::new();
+
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                spinner.set_message(format!("Retrying AI generation ({}/{})...", attempt, attempts));
+                tokio::time::sleep(Duration::from_millis(AI_RETRY_DELAY_MS)).await;
             }
-        };
 
-        let output = output_result?;
+            let plan = match provider.generate(prompt, timeout).await {
+                Ok(plan) => plan,
+                Err(e) if e.downcast_ref::<AiTimeout>().is_some() => {
+                    // Don't retry a hung provider; it would most likely hang again
+                    spinner.finish_with_message(e.to_string());
+                    return Err(anyhow!("{} (raise ai_timeout_seconds in config if plans need longer)", e));
+                }
+                Err(e) => {
+                    error!("{} attempt {}/{} failed: {}", provider.name(), attempt, attempts, e);
+                    last_failure = e.to_string();
+                    continue;
+                }
+            };
+
+            debug!("{} attempt {}/{} completed", provider.name(), attempt, attempts);
+
+            if !Self::looks_like_plan(&plan) {
+                warn!("{} returned {} bytes, too short to contain a plan", provider.name(), plan.trim().len());
+                last_failure = format!("output was empty or missing plan sections ({} bytes)", plan.trim().len());
+            } else {
+                spinner.finish_and_clear();
+                debug!("OpenSpec plan generated successfully");
+                return Ok(plan);
+            }
+        }
 
         spinner.finish_and_clear();
+        Err(anyhow!("{} failed after {} attempt(s); last failure: {}", provider.name(), attempts, last_failure))
+    }
+
+    /// Whether AI output is long enough to plausibly contain the expected markdown sections
+    fn looks_like_plan(output: &str) -> bool {
+        let output = output.trim();
+        output.len() >= MIN_PLAN_LENGTH && output.contains("## ")
+    }
 
-        // Process the output
-        let exit_code = output.status.code().unwrap_or(-1);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        debug!("AI command completed");
-        debug!("Exit code: {}", exit_code);
-        debug!("Stdout length: {} bytes", stdout.len());
-        debug!("Stderr length: {} bytes", stderr.len());
-        debug!("Stdout content: {}", stdout);
-        debug!("Stderr content: {}", stderr);
-
-        if output.status.success() {
-            debug!("OpenSpec plan generated successfully");
-            Ok(stdout.to_string())
+    /// Change ID for a ticket, built from a verb, its number and title (kebab-case, verb-led)
+    ///
+    /// The ticket number keeps IDs unique even when two titles share their first words.
+    pub fn change_id(&self, verb: &str, ticket_id: u32, title: &str) -> String {
+        match self.sanitize_filename(title) {
+            slug if slug.is_empty() => format!("{}-{}", verb, ticket_id),
+            slug => format!("{}-{}-{}", verb, ticket_id, slug),
+        }
+    }
+
+    /// Verb that starts the change ID
+    ///
+    /// An explicit `--change-verb` wins, then the verb of the "Change ID" the AI
+    /// proposed in `plan_content`, then the work item type (Bug → `fix`).
+    /// Falls back to `add`.
+    pub fn change_verb(override_verb: Option<&str>, plan_content: Option<&str>, work_item_type: &str) -> String {
+        if let Some(verb) = override_verb {
+            return verb.to_string();
+        }
+
+        let suggested = plan_content.and_then(|plan| {
+            let change_id_line = regex::Regex::new(r"(?im)change[ -]?id\W*?[:=]\W*([a-z]+)-[a-z0-9]").unwrap();
+            change_id_line.captures(plan).map(|caps| caps[1].to_lowercase())
+        });
+        if let Some(verb) = suggested.filter(|verb| CHANGE_VERBS.contains(&verb.as_str())) {
+            return verb;
+        }
+
+        if work_item_type.eq_ignore_ascii_case("bug") {
+            "fix".to_string()
         } else {
-            error!("AI command failed with exit code {}", exit_code);
-            error!("Stderr: {}", stderr);
-            error!("Stdout: {}", stdout);
-            Err(anyhow!("AI command failed with exit code {}: {}", exit_code, stderr))
+            "add".to_string()
         }
     }
 
-    pub fn create_feature_plan_file(&self, ticket_id: u32, plan_title: &str, plan_content: &str) -> Result<String> {
-        // Generate change ID from ticket number and title (kebab-case, verb-led)
-        let change_id = format!("add-{}-{}", ticket_id, self.sanitize_filename(plan_title));
-        let change_dir = format!("{}/openspec/changes/{}", self.base_path, change_id);
+    pub fn create_feature_plan_file(&self, verb: &str, ticket_id: u32, plan_title: &str, plan_content: &str) -> Result<String> {
+        let change_id = self.change_id(verb, ticket_id, plan_title);
+        let change_dir = format!("{}/changes/{}", self.openspec_dir, change_id);
 
         // Create the change directory structure
         fs::create_dir_all(&change_dir)?;
@@ -244,6 +272,13 @@ EOF
         let tasks_content = self.extract_tasks_section(&plan_content);
         fs::write(&tasks_path, tasks_content)?;
 
+        // Create design.md only when the AI wrote a design section
+        if let Some(design_content) = self.extract_design_section(plan_content, plan_title) {
+            let design_path = format!("{}/design.md", change_dir);
+            fs::write(&design_path, design_content)?;
+            info!("Created design doc at {}", design_path);
+        }
+
         // Create spec deltas if present in plan_content
         self.create_spec_deltas(&change_dir, &plan_content)?;
 
@@ -252,70 +287,285 @@ EOF
         Ok(change_dir)
     }
 
-    pub fn validate_and_summarize(&self, change_id: &str, print_mode: bool) -> Result<()> {
-        // Validate the created change proposal
-        self.validate_change(change_id, print_mode)?;
+    /// Active changes, one line each
+    ///
+    /// Uses `openspec list` when the CLI is installed; otherwise reads the
+    /// `# Change:` title from each `openspec/changes/*/proposal.md`.
+    pub fn list_changes(&self) -> Vec<String> {
+        if !self.cli_supported() {
+            return self.list_changes_from_disk();
+        }
 
-        // Show change summary if validation passed
-        if !print_mode {
-            self.show_change_summary(change_id);
+        let openspec_cmd = self.get_openspec_command();
+        let output = Command::new(&openspec_cmd)
+            .arg("list")
+            .current_dir(&self.base_path)
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => {
+                return String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && *line != "Changes:")
+                    .map(str::to_string)
+                    .collect();
+            }
+            Ok(output) => {
+                debug!("openspec list failed: {}", String::from_utf8_lossy(&output.stderr));
+            }
+            Err(e) => {
+                debug!("Could not run openspec list: {}", e);
+            }
         }
 
-        Ok(())
+        self.list_changes_from_disk()
     }
 
-    fn validate_change(&self, change_id: &str, print_mode: bool) -> Result<()> {
-        debug!("Validating OpenSpec change: {}", change_id);
+    fn list_changes_from_disk(&self) -> Vec<String> {
+        let changes_dir = format!("{}/changes", self.openspec_dir);
+        let Ok(entries) = fs::read_dir(&changes_dir) else {
+            return Vec::new();
+        };
 
-        let openspec_cmd = self.get_openspec_command();
-        let output = Command::new(&openspec_cmd)
-            .args(&["validate", change_id, "--strict"])
+        let mut changes: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir() && entry.file_name() != "archive")
+            .map(|entry| {
+                let change_id = entry.file_name().to_string_lossy().to_string();
+                let title = fs::read_to_string(entry.path().join("proposal.md"))
+                    .ok()
+                    .and_then(|proposal| {
+                        proposal
+                            .lines()
+                            .find_map(|line| line.strip_prefix("# "))
+                            .map(|heading| heading.trim_start_matches("Change:").trim().to_string())
+                    });
+
+                match title {
+                    Some(title) if !title.is_empty() => format!("{}  {}", change_id, title),
+                    _ => change_id,
+                }
+            })
+            .collect();
+
+        changes.sort();
+        changes
+    }
+
+    /// Active change directories created for a ticket, i.e. `openspec/changes/<verb>-<ticket_id>[-<title>]`
+    pub fn changes_for_ticket(&self, ticket_id: u32) -> Vec<PathBuf> {
+        let changes_dir = format!("{}/changes", self.openspec_dir);
+        let Ok(entries) = fs::read_dir(&changes_dir) else {
+            return Vec::new();
+        };
+
+        let id = ticket_id.to_string();
+        let mut changes: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir() && entry.file_name() != "archive")
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let mut parts = name.split('-');
+                parts.next().is_some_and(|verb| !verb.is_empty() && verb.chars().all(|c| c.is_ascii_lowercase()))
+                    && parts.next() == Some(id.as_str())
+            })
+            .map(|entry| entry.path())
+            .collect();
+
+        changes.sort();
+        changes
+    }
+
+    /// Move `openspec/changes/<id>` to `openspec/changes/archive/<date>-<id>`
+    ///
+    /// Returns the archived directory.
+    pub fn archive_change(&self, change_id: &str) -> Result<String> {
+        if change_id.is_empty() || change_id == "archive" || change_id.contains(['/', '\\']) || change_id.starts_with('.') {
+            return Err(anyhow!("Invalid change ID \"{}\"", change_id));
+        }
+
+        let changes_dir = format!("{}/changes", self.openspec_dir);
+        let change_dir = format!("{}/{}", changes_dir, change_id);
+        if !Path::new(&change_dir).is_dir() {
+            return Err(anyhow!("Change \"{}\" not found in {}", change_id, changes_dir));
+        }
+
+        let archive_dir = format!("{}/archive", changes_dir);
+        let archived_dir = format!("{}/{}-{}", archive_dir, chrono::Local::now().format("%Y-%m-%d"), change_id);
+        if Path::new(&archived_dir).exists() {
+            return Err(anyhow!("Archive destination {} already exists", archived_dir));
+        }
+
+        fs::create_dir_all(&archive_dir)?;
+        fs::rename(&change_dir, &archived_dir)
+            .map_err(|e| anyhow!("Failed to move {} to {}: {}", change_dir, archived_dir, e))?;
+        info!("Archived OpenSpec change {} to {}", change_id, archived_dir);
+
+        Ok(archived_dir)
+    }
+
+    /// Run the configured `post_generate_hook` through the shell for a created change
+    ///
+    /// The hook gets BAKERY_TICKET_ID, BAKERY_CHANGE_PATH and BAKERY_TITLE in its
+    /// environment. Its output is logged; a failure is only a warning.
+    pub async fn run_post_generate_hook(&self, hook: &str, ticket_id: u32, change_path: &str, title: &str) {
+        debug!("Running post-generate hook: {}", hook);
+
+        let mut command = if cfg!(windows) {
+            let mut command = tokio::process::Command::new("powershell.exe");
+            command.args(["-NoProfile", "-NonInteractive", "-Command", hook]);
+            command
+        } else {
+            let mut command = tokio::process::Command::new("sh");
+            command.args(["-c", hook]);
+            command
+        };
+
+        let output = command
             .current_dir(&self.base_path)
-            .output();
+            .env("BAKERY_TICKET_ID", ticket_id.to_string())
+            .env("BAKERY_CHANGE_PATH", change_path)
+            .env("BAKERY_TITLE", title)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await;
 
         match output {
             Ok(output) => {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 let stderr = String::from_utf8_lossy(&output.stderr);
-
+                if !stdout.trim().is_empty() {
+                    info!("Post-generate hook output: {}", stdout.trim());
+                }
                 if output.status.success() {
-                    debug!("OpenSpec validation passed for {}", change_id);
-                    if !print_mode {
-                        println!("{} Validation passed",
-                            "✓".bright_green()
-                        );
+                    if !stderr.trim().is_empty() {
+                        debug!("Post-generate hook stderr: {}", stderr.trim());
                     }
-                    Ok(())
+                    debug!("Post-generate hook finished for #{}", ticket_id);
                 } else {
-                    debug!("OpenSpec validation failed for {}", change_id);
-                    debug!("Validation stdout: {}", stdout);
-                    debug!("Validation stderr: {}", stderr);
-
-                    // Show concise validation failure
-                    if !print_mode {
-                        println!("{} Validation issues found - run {} for details",
-                            "⚠️".bright_yellow(),
-                            format!("openspec validate {} --strict", change_id).bright_cyan()
-                        );
-                    }
-
-                    Ok(())
+                    warn!("Post-generate hook exited with {}: {}", output.status, stderr.trim());
                 }
             }
+            Err(e) => warn!("Could not run post-generate hook '{}': {}", hook, e),
+        }
+    }
+
+    /// Run `openspec validate <id> --strict` on a created change
+    pub fn validate_change(&self, change_id: &str) -> ValidationOutcome {
+        debug!("Validating OpenSpec change: {}", change_id);
+        self.run_validate(&[change_id, "--strict"])
+    }
+
+    /// Run `openspec validate --all --strict` over the whole tree
+    pub fn validate_all(&self) -> ValidationOutcome {
+        self.run_validate(&["--all", "--strict", "--no-interactive"])
+    }
+
+    fn run_validate(&self, args: &[&str]) -> ValidationOutcome {
+        if !self.cli_supported() {
+            debug!("Skipping openspec validate: {} isn't a folder named 'openspec'", self.openspec_dir);
+            return ValidationOutcome::Skipped;
+        }
+
+        let openspec_cmd = self.get_openspec_command();
+        let run = |json: bool| {
+            let mut command = Command::new(&openspec_cmd);
+            command.arg("validate").args(args);
+            if json {
+                command.arg("--json");
+            }
+            command.current_dir(&self.base_path).output()
+        };
+
+        // Prefer structured output; older CLIs reject --json, so fall back to plain text
+        let output = match run(true) {
+            Ok(output) if String::from_utf8_lossy(&output.stderr).contains("unknown option") => run(false),
+            output => output,
+        };
+
+        match output {
+            Ok(output) if output.status.success() => ValidationOutcome::Passed,
+            Ok(output) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                debug!("Validation stdout: {}", stdout);
+                debug!("Validation stderr: {}", stderr);
+                ValidationOutcome::Failed(Self::parse_validation_issues(&stdout, &stderr))
+            }
             Err(e) => {
                 debug!("Failed to run openspec validate: {}", e);
-                if !print_mode {
-                    println!("{} OpenSpec CLI not found - validation skipped",
-                        "⚠️".bright_yellow()
-                    );
-                }
-                Ok(())
+                ValidationOutcome::Skipped
             }
         }
     }
 
-    fn show_change_summary(&self, change_id: &str) {
+    /// Turn validator output into one line per issue
+    ///
+    /// Reads the `--json` report (`items[].issues[]` with level/path/message) when
+    /// available, otherwise keeps the lines that look like issues.
+    fn parse_validation_issues(stdout: &str, stderr: &str) -> Vec<String> {
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(stdout.trim()) {
+            let items = match json.get("items").and_then(|items| items.as_array()) {
+                Some(items) => items.clone(),
+                None => vec![json],
+            };
+
+            let issues: Vec<String> = items
+                .iter()
+                .flat_map(|item| {
+                    let item_id = item.get("id").and_then(|id| id.as_str()).unwrap_or("");
+                    item.get("issues")
+                        .and_then(|issues| issues.as_array())
+                        .cloned()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(move |issue| {
+                            let level = issue.get("level").and_then(|l| l.as_str()).unwrap_or("ERROR");
+                            let message = issue.get("message").and_then(|m| m.as_str()).unwrap_or("");
+                            let path = issue.get("path").and_then(|p| p.as_str()).unwrap_or("");
+                            let location = if path.is_empty() { item_id.to_string() } else { format!("{}/{}", item_id, path) };
+                            if location.is_empty() {
+                                format!("[{}] {}", level, message)
+                            } else {
+                                format!("[{}] {}: {}", level, location, message)
+                            }
+                        })
+                })
+                .collect();
+
+            if !issues.is_empty() {
+                return issues;
+            }
+        }
+
+        let lines: Vec<&str> = stdout
+            .lines()
+            .chain(stderr.lines())
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        let issue_lines: Vec<String> = lines
+            .iter()
+            .filter(|line| {
+                line.contains("ERROR") || line.contains("WARNING") || line.starts_with('✗') || line.starts_with("- ")
+            })
+            .map(|line| line.trim_start_matches(['✗', '-', ' ']).to_string())
+            .collect();
+
+        if issue_lines.is_empty() {
+            lines.into_iter().map(str::to_string).collect()
+        } else {
+            issue_lines
+        }
+    }
+
+    pub fn show_change_summary(&self, change_id: &str) {
         debug!("Showing summary for change: {}", change_id);
+        if !self.cli_supported() {
+            return;
+        }
 
         // Try to get JSON output for structured display
         let openspec_cmd = self.get_openspec_command();
@@ -374,24 +624,16 @@ EOF
         let mut impact = String::new();
 
         // Try to find sections in the AI response
-        if let Some(why_start) = plan_content.find("## Why") {
-            if let Some(why_end) = plan_content[why_start..].find("\n## ") {
-                why_section = plan_content[why_start + 6..why_start + why_end].trim().to_string();
-            }
+        if let Some(section) = find_section(plan_content, &["why"]) {
+            why_section = section.body(plan_content).to_string();
         }
 
-        if let Some(what_start) = plan_content.find("## What") {
-            if let Some(what_end) = plan_content[what_start..].find("\n## ") {
-                what_changes = plan_content[what_start + 7..what_start + what_end].trim().to_string();
-            }
+        if let Some(section) = find_section(plan_content, &["what"]) {
+            what_changes = section.body(plan_content).to_string();
         }
 
-        if let Some(impact_start) = plan_content.find("## Impact") {
-            if let Some(impact_end) = plan_content[impact_start..].find("\n## ") {
-                impact = plan_content[impact_start + 9..impact_start + impact_end].trim().to_string();
-            } else {
-                impact = plan_content[impact_start + 9..].trim().to_string();
-            }
+        if let Some(section) = find_section(plan_content, &["impact"]) {
+            impact = section.body(plan_content).to_string();
         }
 
         // Fallback: use the entire content if sections not found
@@ -413,20 +655,20 @@ EOF
     }
 
     fn extract_tasks_section(&self, plan_content: &str) -> String {
-        // Try to extract tasks from the AI-generated content
-        if let Some(tasks_start) = plan_content.find("## Tasks") {
-            if let Some(tasks_end) = plan_content[tasks_start..].find("\n## ") {
-                return plan_content[tasks_start..tasks_start + tasks_end].to_string();
-            } else {
-                return plan_content[tasks_start..].to_string();
-            }
-        }
-
-        // If no tasks section found, look for Implementation or checklist items
-        if let Some(impl_start) = plan_content.find("## Implementation") {
-            if let Some(impl_end) = plan_content[impl_start..].find("\n## ") {
-                return plan_content[impl_start..impl_start + impl_end].to_string();
-            }
+        // Try to extract tasks from the AI-generated content, falling back to an
+        // Implementation section. Numbered "## 1. Category" headings that follow at
+        // the same level belong to the task list.
+        let tasks = find_section(plan_content, &["tasks", "implementation tasks"])
+            .or_else(|| find_section(plan_content, &["implementation"]));
+        if let Some(section) = tasks {
+            let sections = markdown_sections(plan_content);
+            let end = sections
+                .iter()
+                .filter(|next| next.start >= section.end && next.level <= section.level)
+                .take_while(|next| next.level == section.level && next.title.starts_with(|c: char| c.is_ascii_digit()))
+                .last()
+                .map_or(section.end, |last| last.end);
+            return plan_content[section.start..end].trim_end().to_string();
         }
 
         // Fallback: create a basic tasks structure
@@ -436,6 +678,17 @@ EOF
         )
     }
 
+    /// Extract a `## Design` / `### Technical Design` section, if the AI wrote one
+    fn extract_design_section(&self, plan_content: &str, plan_title: &str) -> Option<String> {
+        let section = find_section(plan_content, &["design", "technical design"])?;
+        let design = section.body(plan_content);
+        if design.is_empty() {
+            return None;
+        }
+
+        Some(format!("# Design: {}\n\n{}\n", plan_title, design))
+    }
+
     fn create_spec_deltas(&self, change_dir: &str, plan_content: &str) -> Result<()> {
         // Look for spec sections in the AI-generated content
         // This is optional - only create if the AI generated proper spec deltas
@@ -448,25 +701,33 @@ EOF
             let specs_dir = format!("{}/specs", change_dir);
             fs::create_dir_all(&specs_dir)?;
 
-            // For now, create a generic capability spec
-            // In the future, we could parse multiple capabilities from the AI response
-            let spec_path = format!("{}/feature/spec.md", specs_dir);
-            fs::create_dir_all(format!("{}/feature", specs_dir))?;
-
-            // Extract only the spec delta sections
-            let mut spec_content = String::new();
-            for section in ["## ADDED Requirements", "## MODIFIED Requirements", "## REMOVED Requirements"] {
-                if let Some(section_start) = plan_content.find(section) {
-                    if let Some(section_end) = plan_content[section_start..].find("\n## ") {
-                        spec_content.push_str(&plan_content[section_start..section_start + section_end]);
-                        spec_content.push_str("\n\n");
-                    } else {
-                        spec_content.push_str(&plan_content[section_start..]);
-                    }
+            // One spec per capability header (e.g. "### Capability: auth" or "## Spec: user-login"),
+            // falling back to a single generic "feature" capability when there are none
+            let mut capabilities = self.split_capabilities(plan_content);
+            if capabilities.is_empty() {
+                capabilities.push(("feature".to_string(), plan_content));
+            }
+
+            // Merge repeated headers for the same capability into one spec
+            let mut specs: Vec<(String, String)> = Vec::new();
+            for (capability, content) in capabilities {
+                let deltas = self.extract_delta_sections(content);
+                match specs.iter_mut().find(|(name, _)| *name == capability) {
+                    Some((_, existing)) => existing.push_str(&deltas),
+                    None => specs.push((capability, deltas)),
                 }
             }
 
-            if !spec_content.is_empty() {
+            for (capability, spec_content) in specs {
+                if spec_content.is_empty() {
+                    debug!("No delta sections found for capability {}", capability);
+                    continue;
+                }
+
+                let capability_dir = format!("{}/{}", specs_dir, capability);
+                fs::create_dir_all(&capability_dir)?;
+
+                let spec_path = format!("{}/spec.md", capability_dir);
                 fs::write(&spec_path, spec_content)?;
                 info!("Created spec delta at {}", spec_path);
             }
@@ -475,15 +736,258 @@ EOF
         Ok(())
     }
 
+    /// Split the AI response at capability headers into (capability, content) pairs
+    fn split_capabilities<'a>(&self, plan_content: &'a str) -> Vec<(String, &'a str)> {
+        let header_regex = regex::Regex::new(r"(?mi)^#{2,3}[ \t]+(?:capability|spec):[ \t]*(.+?)[ \t]*$")
+            .expect("valid capability header regex");
+
+        let headers: Vec<(usize, usize, String)> = header_regex
+            .captures_iter(plan_content)
+            .filter_map(|caps| {
+                let whole = caps.get(0)?;
+                let name = self.sanitize_filename(caps.get(1)?.as_str().trim_matches('`'));
+                (!name.is_empty()).then_some((whole.start(), whole.end(), name))
+            })
+            .collect();
+
+        headers
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, content_start, name))| {
+                let content_end = headers.get(idx + 1).map_or(plan_content.len(), |(start, _, _)| *start);
+                (name.clone(), &plan_content[*content_start..content_end])
+            })
+            .collect()
+    }
+
+    /// Extract only the ADDED/MODIFIED/REMOVED delta sections from `content`
+    fn extract_delta_sections(&self, content: &str) -> String {
+        let mut spec_content = String::new();
+        for section in ["## ADDED Requirements", "## MODIFIED Requirements", "## REMOVED Requirements"] {
+            if let Some(section_start) = content.find(section) {
+                if let Some(section_end) = content[section_start..].find("\n## ") {
+                    spec_content.push_str(&content[section_start..section_start + section_end]);
+                    spec_content.push_str("\n\n");
+                } else {
+                    spec_content.push_str(&content[section_start..]);
+                }
+            }
+        }
+        spec_content
+    }
+
     fn sanitize_filename(&self, title: &str) -> String {
-        title
-            .chars()
-            .filter(|c| c.is_alphanumeric() || c.is_whitespace() || *c == '-')
-            .collect::<String>()
-            .split_whitespace()
-            .take(8) // Limit to 8 words
-            .map(|word| word.to_lowercase())
-            .collect::<Vec<_>>()
-            .join("-")
-    }
-}
\ No newline at end of file
+        crate::models::title_slug(title)
+    }
+}
+
+/// A markdown heading and the span of text it owns
+struct MarkdownSection<'a> {
+    /// Number of `#` characters
+    level: usize,
+    title: &'a str,
+    /// Byte offset of the heading line
+    start: usize,
+    /// Byte offset just past the heading line
+    body_start: usize,
+    /// Byte offset of the next heading of the same or a higher level (or the end of the text)
+    end: usize,
+}
+
+impl MarkdownSection<'_> {
+    /// Text under the heading, trimmed
+    fn body<'c>(&self, content: &'c str) -> &'c str {
+        content[self.body_start..self.end].trim()
+    }
+}
+
+/// Split markdown into its ATX headings (`#` to `######`), ignoring fenced code blocks
+fn markdown_sections(content: &str) -> Vec<MarkdownSection<'_>> {
+    let mut sections: Vec<MarkdownSection> = Vec::new();
+    let mut offset = 0;
+    let mut in_fence = false;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_end();
+        if trimmed.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if level == 0 || level > 6 {
+            continue;
+        }
+        let rest = &trimmed[level..];
+        if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+            continue;
+        }
+
+        sections.push(MarkdownSection {
+            level,
+            title: rest.trim().trim_end_matches('#').trim(),
+            start: line_start,
+            body_start: offset,
+            end: content.len(),
+        });
+    }
+
+    // Each section ends where the next heading of the same or a higher level starts
+    for idx in 0..sections.len() {
+        let level = sections[idx].level;
+        if let Some(end) = sections[idx + 1..].iter().find(|next| next.level <= level).map(|next| next.start) {
+            sections[idx].end = end;
+        }
+    }
+
+    sections
+}
+
+/// First heading, at any level, whose title starts with one of `names`
+///
+/// Matching ignores case, emphasis markers, leading numbering ("2. Tasks") and
+/// punctuation, so "## Why?", "### Tasks" and "## What Changes" all match.
+fn find_section<'a>(content: &'a str, names: &[&str]) -> Option<MarkdownSection<'a>> {
+    markdown_sections(content).into_iter().find(|section| {
+        let words = heading_words(section.title);
+        names.iter().any(|name| {
+            let name_words = heading_words(name);
+            !name_words.is_empty() && words.starts_with(&name_words)
+        })
+    })
+}
+
+/// Lowercase words of a heading, without numbering and punctuation
+fn heading_words(title: &str) -> Vec<String> {
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let numbering = words.iter().take_while(|word| word.chars().all(|c| c.is_ascii_digit())).count();
+    words[numbering..].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_sharing_eight_words_get_distinct_change_ids() {
+        let manager = OpenSpecManager::new("unused", "openspec");
+        let first = manager.change_id("add", 101, "Add SSO login to the admin portal for all tenants");
+        let second = manager.change_id("add", 102, "Add SSO login to the admin portal for internal staff");
+
+        assert_eq!(first, "add-101-add-sso-login-to-the-admin-portal-for");
+        assert_eq!(second, "add-102-add-sso-login-to-the-admin-portal-for");
+    }
+
+    const PLAN_WITH_DESIGN: &str = "## Why\nLogin is slow.\n\n## What Changes\n- Cache sessions\n\n\
+## Design\n### Context\nSessions live in Postgres.\n\n### Decisions\nUse Redis.\n\n\
+## Tasks\n- [ ] 1.1 Add cache\n";
+
+    #[test]
+    fn design_section_becomes_design_md() {
+        let manager = OpenSpecManager::new("unused", "openspec");
+        assert_eq!(
+            manager.extract_design_section(PLAN_WITH_DESIGN, "Speed up login").as_deref(),
+            Some("# Design: Speed up login\n\n### Context\nSessions live in Postgres.\n\n### Decisions\nUse Redis.\n")
+        );
+
+        let technical = "## Why\nSlow.\n\n### Technical Design\nAdd a cache layer.\n\n## Tasks\n- [ ] 1.1 Do it\n";
+        assert_eq!(
+            manager.extract_design_section(technical, "Cache").as_deref(),
+            Some("# Design: Cache\n\nAdd a cache layer.\n")
+        );
+    }
+
+    #[test]
+    fn no_design_md_without_a_design_section() {
+        let manager = OpenSpecManager::new("unused", "openspec");
+        let plan = "## Why\nLogin is slow.\n\n## What Changes\n- Cache sessions\n\n## Tasks\n- [ ] 1.1 Add cache\n";
+        assert_eq!(manager.extract_design_section(plan, "Speed up login"), None);
+        // An empty section isn't worth a file either
+        assert_eq!(manager.extract_design_section("## Design\n\n## Tasks\n- [ ] 1.1 x\n", "x"), None);
+    }
+
+    #[test]
+    fn change_folder_gets_design_md_only_when_the_plan_has_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = OpenSpecManager::new(&dir.path().to_string_lossy(), "openspec");
+        let without = PLAN_WITH_DESIGN.replace("## Design", "## Notes");
+
+        let with_design = manager.create_feature_plan_file("add", 1, "With design", PLAN_WITH_DESIGN).unwrap();
+        let without_design = manager.create_feature_plan_file("add", 2, "Without design", &without).unwrap();
+
+        assert!(Path::new(&with_design).join("design.md").is_file());
+        assert!(!Path::new(&without_design).join("design.md").exists());
+        assert!(Path::new(&without_design).join("proposal.md").is_file());
+    }
+
+    #[test]
+    fn sections_are_found_at_any_heading_level() {
+        for plan in [
+            "# Why\nSlow login.\n# Tasks\n- [ ] 1.1 Fix\n",
+            "## Why\nSlow login.\n## Tasks\n- [ ] 1.1 Fix\n",
+            "### Why\nSlow login.\n### Tasks\n- [ ] 1.1 Fix\n",
+        ] {
+            let why = find_section(plan, &["why"]).expect(plan);
+            assert_eq!(why.body(plan), "Slow login.");
+            let tasks = find_section(plan, &["tasks"]).expect(plan);
+            assert_eq!(tasks.body(plan), "- [ ] 1.1 Fix");
+        }
+    }
+
+    #[test]
+    fn headings_with_punctuation_case_and_extra_words_match() {
+        let plan = "## **Why?**  \nSlow login.\n\n## What Changes We Propose\n- Cache\n\n\
+## 2. Implementation Tasks:\n- [ ] 1.1 Add cache\n\n## IMPACT ##\nAuth service\n";
+
+        assert_eq!(find_section(plan, &["why"]).unwrap().body(plan), "Slow login.");
+        assert_eq!(find_section(plan, &["what"]).unwrap().body(plan), "- Cache");
+        assert_eq!(find_section(plan, &["tasks", "implementation tasks"]).unwrap().body(plan), "- [ ] 1.1 Add cache");
+        assert_eq!(find_section(plan, &["impact"]).unwrap().body(plan), "Auth service");
+        // Names match whole leading words only
+        assert!(find_section(plan, &["wha"]).is_none());
+    }
+
+    #[test]
+    fn section_runs_until_a_heading_of_the_same_or_higher_level() {
+        let plan = "### Tasks\n- [ ] 1.1 Cache\n#### Notes\nkeep it small\n### Risks\nNone\n";
+        assert_eq!(
+            find_section(plan, &["tasks"]).unwrap().body(plan),
+            "- [ ] 1.1 Cache\n#### Notes\nkeep it small"
+        );
+    }
+
+    #[test]
+    fn headings_inside_code_fences_are_ignored() {
+        let plan = "## Why\nExample:\n```markdown\n## Tasks\n```\n## What Changes\n- x\n";
+        assert!(find_section(plan, &["tasks"]).is_none());
+        assert_eq!(find_section(plan, &["why"]).unwrap().body(plan), "Example:\n```markdown\n## Tasks\n```");
+    }
+
+    #[test]
+    fn numbered_task_groups_stay_in_tasks_md() {
+        let manager = OpenSpecManager::new("unused", "openspec");
+        let plan = "## Why\nx\n## Tasks\n## 1. Setup\n- [ ] 1.1 a\n## 2. Build\n- [ ] 2.1 b\n## Impact\ny\n";
+        assert_eq!(
+            manager.extract_tasks_section(plan),
+            "## Tasks\n## 1. Setup\n- [ ] 1.1 a\n## 2. Build\n- [ ] 2.1 b"
+        );
+    }
+
+    #[test]
+    fn change_id_falls_back_to_the_ticket_number() {
+        let manager = OpenSpecManager::new("unused", "openspec");
+        assert_eq!(manager.change_id("fix", 7, "???"), "fix-7");
+        assert_eq!(manager.change_id("fix", 7, "NUL"), "fix-7-nul-item");
+    }
+}
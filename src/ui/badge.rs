@@ -116,6 +116,12 @@ impl Badge {
         }
     }
 
+    /// Create a tag badge
+    pub fn tag(&self, tag: &str) -> String {
+        let icon = if self.theme.use_emojis() { "🏷" } else { "#" };
+        self.theme.fmt_accent(&format!("[{} {}]", icon, tag))
+    }
+
     /// Create a validation status badge
     pub fn validation(&self, passed: bool) -> String {
         if passed {
@@ -2,6 +2,7 @@
 
 use super::theme::Theme;
 use super::terminal::Terminal;
+use super::format::display_width;
 
 /// Card component for displaying information in bordered boxes
 pub struct Card {
@@ -32,9 +33,9 @@ impl Card {
         };
 
         // Calculate actual content width
-        let mut content_width = title.len();
+        let mut content_width = display_width(title);
         for line in &lines {
-            let line_width = unicode_width::UnicodeWidthStr::width(line.as_str());
+            let line_width = display_width(line);
             if line_width > content_width {
                 content_width = line_width;
             }
@@ -43,7 +44,7 @@ impl Card {
 
         // Top border with title
         let title_section = format!(" {} ", title);
-        let title_width = unicode_width::UnicodeWidthStr::width(title_section.as_str());
+        let title_width = display_width(&title_section);
         let remaining = if content_width > title_width {
             content_width - title_width
         } else {
@@ -59,7 +60,7 @@ impl Card {
 
         // Content lines
         for line in lines {
-            let line_width = unicode_width::UnicodeWidthStr::width(line.as_str());
+            let line_width = display_width(&line);
             let padding = if content_width > line_width {
                 content_width - line_width
             } else {
@@ -97,7 +98,7 @@ impl Card {
         );
 
         // Center the text
-        let text_width = unicode_width::UnicodeWidthStr::width(text);
+        let text_width = display_width(text);
         let padding_left = (width - text_width - 2) / 2;
         let padding_right = width - text_width - padding_left - 2;
 
@@ -123,7 +124,7 @@ impl Card {
         }
 
         let label_width = pairs.iter()
-            .map(|(label, _)| unicode_width::UnicodeWidthStr::width(*label))
+            .map(|(label, _)| display_width(label))
             .max()
             .unwrap_or(10);
 
@@ -1,9 +1,17 @@
 //! Summary dashboard component
 
+use crate::models::{Attachment, WorkItem};
 use super::theme::Theme;
 use super::terminal::Terminal;
 use super::badge::Badge;
-use super::format::{format_file_size, format_duration, format_time_ago};
+use super::format::{display_width, truncate_to_width, format_file_size, format_duration, format_time_ago, is_recent};
+
+/// Width of the size column in the attachment list, e.g. "1023.9 KB"
+const ATTACHMENT_SIZE_WIDTH: usize = 10;
+/// Widest the content type column of the attachment list gets
+const ATTACHMENT_TYPE_WIDTH: usize = 24;
+/// Longest extension kept when a filename is shortened, e.g. ".xlsx"
+const MAX_KEPT_EXTENSION_WIDTH: usize = 8;
 
 /// Dashboard for displaying summary information
 pub struct Dashboard {
@@ -20,23 +28,16 @@ impl Dashboard {
     }
 
     /// Render a work item summary dashboard
-    pub fn render_work_item_summary(
-        &self,
-        id: u32,
-        title: &str,
-        state: &str,
-        work_item_type: &str,
-        attachments: usize,
-        comments: usize,
-        images: usize,
-        acceptance_criteria: usize,
-    ) {
+    pub fn render_work_item_summary(&self, work_item: &WorkItem) {
         if self.theme.mode == super::theme::OutputMode::Print {
             return;
         }
 
+        let id = work_item.id;
+        let title = work_item.title.as_str();
+
         let box_chars = self.terminal.box_chars();
-        let width = if self.terminal.is_narrow() {
+        let width: usize = if self.terminal.is_narrow() {
             50
         } else if self.terminal.is_wide() {
             90
@@ -49,31 +50,55 @@ impl Dashboard {
         println!("\n{}{}{}",
             box_chars.top_left,
             self.theme.fmt_highlight(&header),
-            box_chars.horizontal.repeat(width - header.len() - 1).to_string() + box_chars.top_right
+            box_chars.horizontal.repeat(width.saturating_sub(display_width(&header) + 2)).to_string() + box_chars.top_right
         );
 
         // Title
-        let title_display = if title.len() > width - 6 {
-            format!("{}...", &title[..width - 9])
-        } else {
-            title.to_string()
-        };
+        let title_display = truncate_to_width(title, width - 4);
         println!("{} {} {}",
             box_chars.vertical,
             self.theme.fmt_primary(&title_display),
-            " ".repeat(width - title_display.len() - 3).to_string() + box_chars.vertical
+            " ".repeat(width.saturating_sub(display_width(&title_display) + 4)).to_string() + box_chars.vertical
         );
 
         // Status line with badges
-        let state_badge = self.badge.state(state);
-        let type_badge = self.badge.work_item_type(work_item_type);
+        let state_badge = self.badge.state(&work_item.state);
+        let type_badge = self.badge.work_item_type(&work_item.work_item_type);
         let status_line = format!("{} {}", state_badge, type_badge);
         println!("{} {} {}",
             box_chars.vertical,
             status_line,
-            " ".repeat(width - status_line.len() - 3).to_string() + box_chars.vertical
+            " ".repeat(width.saturating_sub(display_width(&status_line) + 4)).to_string() + box_chars.vertical
         );
 
+        // Last update, highlighted when it happened within the past day
+        let updated = work_item.updated_date.to_rfc3339();
+        let updated_text = format!("Updated: {}", format_time_ago(&updated));
+        let updated_line = if is_recent(&updated) {
+            self.theme.fmt_success(&updated_text)
+        } else {
+            self.theme.fmt_muted(&updated_text)
+        };
+        println!("{} {} {}",
+            box_chars.vertical,
+            updated_line,
+            " ".repeat(width.saturating_sub(display_width(&updated_line) + 4)).to_string() + box_chars.vertical
+        );
+
+        // Tags row
+        if !work_item.tags.is_empty() {
+            let tag_line = work_item.tags
+                .iter()
+                .map(|tag| self.badge.tag(tag))
+                .collect::<Vec<_>>()
+                .join(" ");
+            println!("{} {} {}",
+                box_chars.vertical,
+                tag_line,
+                " ".repeat(width.saturating_sub(display_width(&tag_line) + 4)).to_string() + box_chars.vertical
+            );
+        }
+
         // Separator
         println!("{}{}{}",
             box_chars.left_join,
@@ -83,17 +108,17 @@ impl Dashboard {
 
         // Content counts
         let content_lines = vec![
-            self.badge.count("attachments", attachments),
-            self.badge.count("comments", comments),
-            self.badge.count("images", images),
-            self.badge.count("acceptance criteria", acceptance_criteria),
+            self.badge.count("attachments", work_item.attachments.len()),
+            self.badge.count("comments", work_item.comments.len()),
+            self.badge.count("images", work_item.images.len()),
+            self.badge.count("acceptance criteria", work_item.acceptance_criteria.len()),
         ];
 
         for line in content_lines {
             println!("{} {} {}",
                 box_chars.vertical,
                 line,
-                " ".repeat(width - line.len() - 3).to_string() + box_chars.vertical
+                " ".repeat(width.saturating_sub(display_width(&line) + 4)).to_string() + box_chars.vertical
             );
         }
 
@@ -105,6 +130,65 @@ impl Dashboard {
         );
     }
 
+    /// Render a bordered list of attachments with their size and content type
+    ///
+    /// Long filenames are shortened; attachments that weren't downloaded are
+    /// shown muted. Nothing is drawn without attachments or in Print mode.
+    pub fn render_attachment_list(&self, attachments: &[Attachment]) {
+        if attachments.is_empty() || self.theme.mode == super::theme::OutputMode::Print {
+            return;
+        }
+
+        let box_chars = self.terminal.box_chars();
+        let width: usize = if self.terminal.is_narrow() {
+            50
+        } else if self.terminal.is_wide() {
+            90
+        } else {
+            70
+        };
+
+        let header = format!(" 📎 Attachments ({}) ", attachments.len());
+        println!("{}{}{}",
+            box_chars.top_left,
+            self.theme.fmt_highlight(&header),
+            box_chars.horizontal.repeat(width.saturating_sub(display_width(&header) + 2)).to_string() + box_chars.top_right
+        );
+
+        let (name_width, type_width) = attachment_column_widths(width);
+
+        for attachment in attachments {
+            let name = shorten_filename(&attachment.filename, name_width);
+            let size = format_file_size(attachment.size);
+            let content_type = truncate_to_width(&attachment.content_type, type_width);
+
+            let row = format!("{}{}  {:>size_width$}  {}{}",
+                name,
+                " ".repeat(name_width.saturating_sub(display_width(&name))),
+                size,
+                content_type,
+                " ".repeat(type_width.saturating_sub(display_width(&content_type))),
+                size_width = ATTACHMENT_SIZE_WIDTH
+            );
+            let row = if attachment.skipped {
+                self.theme.fmt_muted(&row)
+            } else {
+                self.theme.fmt_primary(&row)
+            };
+            println!("{} {} {}",
+                box_chars.vertical,
+                row,
+                " ".repeat(width.saturating_sub(display_width(&row) + 4)).to_string() + box_chars.vertical
+            );
+        }
+
+        println!("{}{}{}",
+            box_chars.bottom_left,
+            box_chars.horizontal.repeat(width - 2),
+            box_chars.bottom_right
+        );
+    }
+
     /// Render OpenSpec generation summary
     pub fn render_openspec_summary(&self, change_path: &str, validation_passed: bool, requirement_count: usize) {
         if self.theme.mode == super::theme::OutputMode::Print {
@@ -158,7 +242,7 @@ impl Dashboard {
     /// Render error card
     pub fn render_error(&self, title: &str, message: &str, suggestion: Option<&str>) {
         let box_chars = self.terminal.box_chars();
-        let width = if self.terminal.is_narrow() {
+        let width: usize = if self.terminal.is_narrow() {
             50
         } else {
             70
@@ -169,7 +253,7 @@ impl Dashboard {
         println!("\n{}{}{}",
             box_chars.top_left,
             self.theme.fmt_error(&header),
-            box_chars.horizontal.repeat(width - header.len() - 1).to_string() + box_chars.top_right
+            box_chars.horizontal.repeat(width.saturating_sub(display_width(&header) + 2)).to_string() + box_chars.top_right
         );
 
         // Error message (wrapped if needed)
@@ -178,7 +262,7 @@ impl Dashboard {
             println!("{} {} {}",
                 box_chars.vertical,
                 self.theme.fmt_primary(&line),
-                " ".repeat(width - line.len() - 3).to_string() + box_chars.vertical
+                " ".repeat(width.saturating_sub(display_width(&line) + 4)).to_string() + box_chars.vertical
             );
         }
 
@@ -194,7 +278,7 @@ impl Dashboard {
             println!("{} {} {}",
                 box_chars.vertical,
                 suggestion_header,
-                " ".repeat(width - 14).to_string() + box_chars.vertical
+                " ".repeat(width.saturating_sub(display_width(&suggestion_header) + 4)).to_string() + box_chars.vertical
             );
 
             let wrapped_sug = super::format::wrap_text(sug, width - 4);
@@ -202,7 +286,7 @@ impl Dashboard {
                 println!("{} {} {}",
                     box_chars.vertical,
                     line,
-                    " ".repeat(width - line.len() - 3).to_string() + box_chars.vertical
+                    " ".repeat(width.saturating_sub(display_width(&line) + 4)).to_string() + box_chars.vertical
                 );
             }
         }
@@ -215,3 +299,90 @@ impl Dashboard {
         );
     }
 }
+
+/// Widths of the filename and content type columns in an attachment list `width` columns wide
+///
+/// The size column sits between them, with two spaces between columns.
+fn attachment_column_widths(width: usize) -> (usize, usize) {
+    let type_width = ATTACHMENT_TYPE_WIDTH.min(width / 4);
+    let name_width = width.saturating_sub(4 + ATTACHMENT_SIZE_WIDTH + type_width + 4);
+    (name_width, type_width)
+}
+
+/// `filename` cut to `max_width` columns, keeping its start and its extension
+fn shorten_filename(filename: &str, max_width: usize) -> String {
+    if display_width(filename) <= max_width {
+        return filename.to_string();
+    }
+
+    match filename.rfind('.').filter(|&dot| dot > 0) {
+        Some(dot) if display_width(&filename[dot..]) <= MAX_KEPT_EXTENSION_WIDTH => {
+            let extension = &filename[dot..];
+            let stem = truncate_to_width(&filename[..dot], max_width.saturating_sub(display_width(extension)));
+            format!("{}{}", stem, extension)
+        }
+        _ => truncate_to_width(filename, max_width),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AzureWorkItemResponse;
+    use crate::ui::theme::OutputMode;
+
+    fn work_item(title: &str) -> WorkItem {
+        let response: AzureWorkItemResponse = serde_json::from_value(serde_json::json!({
+            "id": 7,
+            "rev": 1,
+            "url": "",
+            "_links": {},
+            "fields": { "System.Title": title, "System.State": "Active", "System.WorkItemType": "Bug" }
+        }))
+        .unwrap();
+        WorkItem::from(response)
+    }
+
+    #[test]
+    fn wide_titles_are_truncated_without_panicking() {
+        for width in [20, 50, 120] {
+            let terminal = Terminal { width, ..Terminal::detect() };
+            let dashboard = Dashboard::new(Theme::new(OutputMode::Rich, terminal.clone()), terminal);
+
+            dashboard.render_work_item_summary(&work_item(&"ログイン画面のパフォーマンス改善".repeat(4)));
+            dashboard.render_work_item_summary(&work_item(&"🚀🐛✨ emoji heavy title ".repeat(6)));
+            dashboard.render_work_item_summary(&work_item("Ünïcödé"));
+        }
+    }
+
+    #[test]
+    fn long_attachment_names_keep_their_start_and_extension() {
+        let filename = "customer-export-2024-06-03-final-v2.xlsx";
+        let terminal = Terminal { width: 50, ..Terminal::detect() };
+        assert!(terminal.is_narrow());
+        let dashboard = Dashboard::new(Theme::new(OutputMode::Rich, terminal.clone()), terminal);
+        let attachment = Attachment {
+            id: 1,
+            filename: filename.to_string(),
+            original_filename: filename.to_string(),
+            url: String::new(),
+            local_path: String::new(),
+            content_type: "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+            size: 48_213,
+            created_date: chrono::Utc::now(),
+            skipped: false,
+            skip_reason: None,
+        };
+        dashboard.render_attachment_list(&[attachment]);
+
+        let (name_width, _) = attachment_column_widths(50);
+        let shortened = shorten_filename(filename, name_width);
+        assert_eq!(shortened, "customer-exp....xlsx");
+        assert!(display_width(&shortened) <= name_width);
+
+        let (name_width, _) = attachment_column_widths(70);
+        assert_eq!(shorten_filename(filename, name_width), "customer-export-2024-06-03-....xlsx");
+        assert_eq!(shorten_filename("日本語のファイル名がとても長い資料.pdf", 20), "日本語のファ....pdf");
+        assert_eq!(shorten_filename("trace.txt", 20), "trace.txt");
+    }
+}
@@ -4,33 +4,51 @@ use chrono::{DateTime, Utc, Local, Duration};
 use unicode_width::UnicodeWidthStr;
 
 /// Truncate text intelligently while preserving word boundaries
+///
+/// `max_len` and the "more chars" count are measured in characters, so
+/// multibyte text (accents, CJK, emoji) is never split mid-character.
 pub fn truncate_text(text: &str, max_len: usize) -> String {
-    if text.len() <= max_len {
+    let total_chars = text.chars().count();
+    if total_chars <= max_len {
         return text.to_string();
     }
 
+    // Byte offset of the `max_len`-th character
+    let cutoff = text.char_indices().nth(max_len).map_or(text.len(), |(idx, _)| idx);
+
     // Try to find a word boundary
-    let mut truncate_pos = max_len;
-    if let Some(pos) = text[..max_len].rfind(' ') {
+    let mut truncate_pos = cutoff;
+    if let Some(pos) = text[..cutoff].rfind(' ') {
         truncate_pos = pos;
     }
 
-    let remaining = text.len() - truncate_pos;
+    let remaining = total_chars - text[..truncate_pos].chars().count();
     format!("{}... ({} more chars)", &text[..truncate_pos], remaining)
 }
 
-/// Truncate text to first paragraph or N characters
+/// Truncate text to at most `max_chars` characters, "... (N more chars)" note included
+///
+/// Keeps every whole paragraph that fits; when even the first one doesn't, cuts
+/// at a word boundary instead.
 pub fn truncate_to_paragraph(text: &str, max_chars: usize) -> String {
-    // Find first paragraph break
-    if let Some(pos) = text.find("\n\n") {
-        if pos < max_chars {
-            let remaining = text.len() - pos;
-            return format!("{}\n... ({} more chars)", &text[..pos].trim(), remaining);
-        }
+    let total_chars = text.chars().count();
+    if total_chars <= max_chars {
+        return text.to_string();
     }
 
-    // No paragraph break, use character limit
-    truncate_text(text, max_chars)
+    // Room for the kept text once the longest possible note is accounted for
+    let note_chars = format!("\n... ({} more chars)", total_chars).chars().count();
+    let room = max_chars.saturating_sub(note_chars);
+    let cutoff = text.char_indices().nth(room).map_or(text.len(), |(idx, _)| idx);
+
+    // Last paragraph break that fits
+    match text[..cutoff].rfind("\n\n").map(|pos| text[..pos].trim_end()) {
+        Some(kept) if !kept.is_empty() => {
+            let remaining = total_chars - kept.chars().count();
+            format!("{}\n... ({} more chars)", kept, remaining)
+        }
+        _ => truncate_text(text, room),
+    }
 }
 
 /// Format timestamp as relative time (e.g., "2 hours ago")
@@ -40,7 +58,10 @@ pub fn format_time_ago(timestamp: &str) -> String {
         let now = Utc::now();
         let duration = now.signed_duration_since(dt.with_timezone(&Utc));
 
-        if duration.num_seconds() < 60 {
+        // Clock skew can put timestamps slightly ahead of us; only call out real future dates
+        if duration.num_seconds() < -60 {
+            return "in the future".to_string();
+        } else if duration.num_seconds() < 60 {
             return "just now".to_string();
         } else if duration.num_minutes() < 60 {
             let mins = duration.num_minutes();
@@ -114,6 +135,61 @@ pub fn format_duration(seconds: f64) -> String {
     }
 }
 
+/// Remove ANSI escape sequences (colors, styles), leaving only the visible text
+pub fn strip_ansi(text: &str) -> String {
+    let mut visible = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // CSI sequence: ESC [ parameters... final byte in '@'..='~'
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        visible.push(c);
+    }
+
+    visible
+}
+
+/// Visible width of text in terminal columns, ignoring ANSI escape sequences
+///
+/// Use this instead of `len()` when padding strings that may already be
+/// colorized by `Theme` or `Badge`.
+pub fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(strip_ansi(text).as_str())
+}
+
+/// Cut text to at most `max_width` columns, ending in "..." when shortened
+///
+/// Never splits a multibyte character.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if UnicodeWidthStr::width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let budget = max_width.saturating_sub(3);
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+        if used + char_width > budget {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+
+    format!("{}...", truncated)
+}
+
 /// Pad text to width, respecting Unicode width
 pub fn pad_to_width(text: &str, width: usize) -> String {
     let text_width = UnicodeWidthStr::width(text);
@@ -138,13 +214,16 @@ pub fn center_text(text: &str, width: usize) -> String {
 }
 
 /// Wrap text to specified width
+///
+/// Words are measured by their visible width, so colorized text wraps the same
+/// as plain text; escape sequences are kept in the output.
 pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
     let mut lines = Vec::new();
     let mut current_line = String::new();
     let mut current_width = 0;
 
     for word in text.split_whitespace() {
-        let word_width = UnicodeWidthStr::width(word);
+        let word_width = display_width(word);
 
         if current_width + word_width + 1 > width {
             if !current_line.is_empty() {
@@ -169,3 +248,97 @@ pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
 
     lines
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_text_cuts_at_a_word_boundary_in_accented_text() {
+        assert_eq!(truncate_text("Café crème brûlée", 7), "Café... (13 more chars)");
+    }
+
+    #[test]
+    fn truncate_text_never_splits_multibyte_characters() {
+        assert_eq!(truncate_text("🚀🚀🚀🚀🚀", 2), "🚀🚀... (3 more chars)");
+        assert_eq!(truncate_text("日本語のテキスト", 3), "日本語... (5 more chars)");
+        assert_eq!(truncate_text("ñandú", 5), "ñandú");
+    }
+
+    #[test]
+    fn truncate_to_paragraph_counts_characters() {
+        let text = "Überblick\n\nDétails ici\n\nÜbersicht über alles Weitere";
+        let truncated = truncate_to_paragraph(text, 45);
+        assert_eq!(truncated, "Überblick\n\nDétails ici\n... (30 more chars)");
+        assert!(truncated.chars().count() <= 45);
+    }
+
+    #[test]
+    fn truncate_to_paragraph_keeps_every_paragraph_that_fits() {
+        let text = (0..70).map(|i| format!("Paragraph {:02}: {}", i, "x".repeat(986))).collect::<Vec<_>>().join("\n\n");
+        let truncated = truncate_to_paragraph(&text, 59_986);
+
+        let kept = truncated.chars().count();
+        assert!(kept <= 59_986 && kept > 59_986 - 1_002, "kept {} chars", kept);
+        assert!(truncated.contains("Paragraph 58:") && !truncated.contains("Paragraph 59:"));
+        assert!(truncated.ends_with("\n... (11022 more chars)"), "{}", &truncated[truncated.len() - 40..]);
+    }
+
+    #[test]
+    fn truncate_to_paragraph_falls_back_to_a_word_boundary() {
+        let truncated = truncate_to_paragraph("Un très long premier paragraphe\n\nSuite", 30);
+        assert_eq!(truncated, "Un très... (31 more chars)");
+        assert!(truncated.chars().count() <= 30);
+    }
+
+    #[test]
+    fn truncate_to_width_fits_wide_characters() {
+        let title = "ログイン画面のパフォーマンス改善 🚀🚀";
+        let truncated = truncate_to_width(title, 15);
+        assert_eq!(truncated, "ログイン画面...");
+        assert!(UnicodeWidthStr::width(truncated.as_str()) <= 15);
+        assert_eq!(truncate_to_width("🚀 Launch", 20), "🚀 Launch");
+    }
+
+    #[test]
+    fn colored_line_pads_to_the_same_width_as_a_plain_one() {
+        let plain = "Active 🐛 Bug";
+        let colored = "\u{1b}[32mActive\u{1b}[0m \u{1b}[1;31m🐛 Bug\u{1b}[0m";
+        assert_eq!(display_width(colored), display_width(plain));
+
+        let pad = |line: &str| format!("{}{}|", line, " ".repeat(30 - display_width(line)));
+        assert_eq!(strip_ansi(&pad(colored)), pad(plain));
+        assert_eq!(display_width(&pad(colored)), 31);
+    }
+
+    #[test]
+    fn future_timestamps_are_not_reported_as_past() {
+        let ago = |offset: Duration| format_time_ago(&(Utc::now() + offset).to_rfc3339());
+        assert_eq!(ago(Duration::minutes(5)), "in the future");
+        // Small clock skew between us and the server reads as now
+        assert_eq!(ago(Duration::seconds(20)), "just now");
+        assert_eq!(ago(Duration::minutes(-5)), "5 minutes ago");
+    }
+
+    #[test]
+    fn strip_ansi_keeps_only_the_visible_text() {
+        assert_eq!(strip_ansi("\u{1b}[1;38;5;208mBakery\u{1b}[0m ready"), "Bakery ready");
+        assert_eq!(strip_ansi("\u{1b}[2K\u{1b}[1Gdone ✓"), "done ✓");
+        assert_eq!(strip_ansi("no escapes here"), "no escapes here");
+        // A lone ESC is dropped rather than swallowing the text after it
+        assert_eq!(strip_ansi("a\u{1b}b"), "ab");
+    }
+
+    #[test]
+    fn colored_text_wraps_like_plain_text() {
+        let plain = "Fix slow login on the dashboard";
+        let colored = "\u{1b}[31mFix\u{1b}[0m slow \u{1b}[1mlogin\u{1b}[0m on the \u{1b}[4mdashboard\u{1b}[0m";
+
+        let plain_lines = wrap_text(plain, 10);
+        let colored_lines = wrap_text(colored, 10);
+        assert_eq!(plain_lines, ["Fix slow", "login on", "the", "dashboard"]);
+        assert_eq!(colored_lines.iter().map(|line| strip_ansi(line)).collect::<Vec<_>>(), plain_lines);
+        assert!(colored_lines.iter().all(|line| display_width(line) <= 10));
+        assert_eq!(colored_lines[0], "\u{1b}[31mFix\u{1b}[0m slow");
+    }
+}
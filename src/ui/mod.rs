@@ -6,15 +6,17 @@
 pub mod theme;
 pub mod terminal;
 pub mod card;
+pub mod table;
 pub mod badge;
 pub mod progress;
 pub mod format;
 pub mod dashboard;
 
 // Re-exports for convenience
-pub use theme::{Theme, OutputMode};
+pub use theme::{Theme, OutputMode, ThemePalette};
 pub use terminal::Terminal;
 pub use card::Card;
+pub use table::Table;
 pub use badge::Badge;
 pub use progress::Progress;
 pub use format::{truncate_text, format_time_ago, format_file_size};
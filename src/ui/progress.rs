@@ -2,7 +2,15 @@
 
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
-use super::theme::Theme;
+use super::theme::{Theme, OutputMode};
+use super::terminal::ColorDepth;
+
+/// Braille spinner frames
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Ends of the spinner gradient (cyan to magenta), as RGB
+const GRADIENT_START: (u8, u8, u8) = (0, 200, 255);
+const GRADIENT_END: (u8, u8, u8) = (200, 80, 255);
 
 /// Progress indicator builder
 pub struct Progress {
@@ -12,17 +20,29 @@ pub struct Progress {
 impl Progress {
     /// Create a new progress indicator
     pub fn new(theme: Theme) -> Self {
+        tracing::debug!("Progress styling for {:?} mode at {:?} color depth", theme.mode, theme.color_depth());
         Self { theme }
     }
 
+    /// Rich mode on a truecolor terminal gets gradient and 256-color styling
+    fn use_rich_colors(&self) -> bool {
+        self.theme.mode == OutputMode::Rich && self.theme.color_depth() == ColorDepth::TrueColor
+    }
+
     /// Create a spinner for indeterminate progress
     pub fn spinner(&self, message: &str) -> ProgressBar {
         let pb = ProgressBar::new_spinner();
 
         if self.theme.use_animations() {
-            let style = if self.theme.use_emojis() {
+            let style = if self.theme.use_emojis() && self.use_rich_colors() {
+                let frames = gradient_frames();
                 ProgressStyle::default_spinner()
-                    .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
+                    .tick_strings(&frames.iter().map(String::as_str).collect::<Vec<_>>())
+                    .template("{spinner} {msg}")
+                    .unwrap()
+            } else if self.theme.use_emojis() {
+                ProgressStyle::default_spinner()
+                    .tick_strings(&SPINNER_FRAMES)
                     .template("{spinner:.cyan} {msg}")
                     .unwrap()
             } else {
@@ -48,7 +68,12 @@ impl Progress {
         let pb = ProgressBar::new(len);
 
         if self.theme.use_animations() {
-            let style = if self.theme.use_emojis() {
+            let style = if self.theme.use_emojis() && self.use_rich_colors() {
+                ProgressStyle::default_bar()
+                    .template("{spinner:.48} [{elapsed_precise}] [{bar:40.45/24}] {pos}/{len} {msg}")
+                    .unwrap()
+                    .progress_chars("█▓▒░")
+            } else if self.theme.use_emojis() {
                 ProgressStyle::default_bar()
                     .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
                     .unwrap()
@@ -69,6 +94,32 @@ impl Progress {
         pb
     }
 
+    /// Create a progress bar counting downloaded bytes out of `total_bytes`
+    pub fn download_bar(&self, total_bytes: u64, message: &str) -> ProgressBar {
+        let pb = ProgressBar::new(total_bytes);
+
+        if self.theme.use_animations() {
+            let style = if self.theme.use_emojis() {
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{bar:40.cyan/blue}] {bytes}/{total_bytes} {msg}")
+                    .unwrap()
+                    .progress_chars("█▓▒░")
+            } else {
+                ProgressStyle::default_bar()
+                    .template("[{bar:40}] {bytes}/{total_bytes} {msg}")
+                    .unwrap()
+                    .progress_chars("##-")
+            };
+
+            pb.set_style(style);
+            pb.set_message(message.to_string());
+        } else {
+            pb.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+
+        pb
+    }
+
     /// Show a simple status message
     pub fn status(&self, icon: &str, message: &str) {
         if self.theme.mode == super::theme::OutputMode::Print {
@@ -95,3 +146,28 @@ impl Progress {
         );
     }
 }
+
+/// Spinner frames shaded from `GRADIENT_START` to `GRADIENT_END` and back, using 24-bit color escapes
+///
+/// The final frame is the plain glyph, which indicatif shows once the spinner finishes.
+fn gradient_frames() -> Vec<String> {
+    let steps = SPINNER_FRAMES.len() - 1;
+    let blend = |from: u8, to: u8, t: f32| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+
+    let mut frames: Vec<String> = SPINNER_FRAMES[..steps]
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            // Go out to the far end and back, so the loop has no visible seam
+            let t = 1.0 - ((2 * i) as f32 / steps as f32 - 1.0).abs();
+            let (r, g, b) = (
+                blend(GRADIENT_START.0, GRADIENT_END.0, t),
+                blend(GRADIENT_START.1, GRADIENT_END.1, t),
+                blend(GRADIENT_START.2, GRADIENT_END.2, t),
+            );
+            format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, frame)
+        })
+        .collect();
+    frames.push(SPINNER_FRAMES[steps].to_string());
+    frames
+}
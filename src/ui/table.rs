@@ -0,0 +1,112 @@
+//! Multi-row table component with bordered columns
+
+use super::theme::{Theme, OutputMode};
+use super::terminal::Terminal;
+use super::format::{display_width, strip_ansi, truncate_to_width};
+
+/// Narrowest a column is shrunk to when the table doesn't fit the terminal
+const MIN_COLUMN_WIDTH: usize = 6;
+
+/// Table component for listing rows of data under column headers
+pub struct Table {
+    theme: Theme,
+    terminal: Terminal,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Create an empty table with the given column headers
+    pub fn new(theme: Theme, terminal: Terminal, headers: &[&str]) -> Self {
+        Self {
+            theme,
+            terminal,
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Add a row; missing cells are left blank and extra cells are ignored
+    pub fn add_row(&mut self, cells: Vec<String>) {
+        let mut row: Vec<String> = cells.into_iter().take(self.headers.len()).collect();
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+    }
+
+    /// Render the table
+    ///
+    /// Print and Compact modes get plain tab-separated lines (headers first)
+    /// instead of borders, so the output stays easy to parse.
+    pub fn render(&self) {
+        if matches!(self.theme.mode, OutputMode::Print | OutputMode::Compact) {
+            println!("{}", self.headers.join("\t"));
+            for row in &self.rows {
+                println!("{}", row.iter().map(|cell| strip_ansi(cell)).collect::<Vec<_>>().join("\t"));
+            }
+            return;
+        }
+
+        let box_chars = self.terminal.box_chars();
+        let widths = self.column_widths();
+
+        let border = |left: &str, join: &str, right: &str| {
+            let segments: Vec<String> = widths.iter().map(|w| box_chars.horizontal.repeat(w + 2)).collect();
+            format!("{}{}{}", left, segments.join(join), right)
+        };
+        let line = |cells: &[String], header: bool| {
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| {
+                    let cell = fit_to_width(cell, width);
+                    if header { self.theme.fmt_highlight(&cell) } else { cell }
+                })
+                .collect();
+            let separator = format!(" {} ", box_chars.vertical);
+            format!("{} {} {}", box_chars.vertical, cells.join(&separator), box_chars.vertical)
+        };
+
+        println!("{}", border(box_chars.top_left, box_chars.top_join, box_chars.top_right));
+        println!("{}", line(&self.headers, true));
+        println!("{}", border(box_chars.left_join, box_chars.cross, box_chars.right_join));
+        for row in &self.rows {
+            println!("{}", line(row, false));
+        }
+        println!("{}", border(box_chars.bottom_left, box_chars.bottom_join, box_chars.bottom_right));
+    }
+
+    /// Width of each column: its widest cell, shrinking the widest columns
+    /// until the table fits the terminal
+    fn column_widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| display_width(h)).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(display_width(cell));
+            }
+        }
+
+        // Each column takes its width plus " │ "; the outer borders add 4 more
+        let max_width = (self.terminal.width as usize).max(40);
+        let overhead = widths.len() * 3 + 1;
+        while widths.iter().sum::<usize>() + overhead > max_width {
+            let Some(widest) = widths.iter_mut().filter(|w| **w > MIN_COLUMN_WIDTH).max_by_key(|w| **w) else {
+                break;
+            };
+            *widest -= 1;
+        }
+
+        widths
+    }
+}
+
+/// Pad a cell to exactly `width` columns, truncating it with "..." when too wide
+fn fit_to_width(cell: &str, width: usize) -> String {
+    let cell = if display_width(cell) > width {
+        truncate_to_width(&strip_ansi(cell), width)
+    } else {
+        cell.to_string()
+    };
+    // Wide characters can leave the truncated text a column short
+    let padding = width.saturating_sub(display_width(&cell));
+    format!("{}{}", cell, " ".repeat(padding))
+}
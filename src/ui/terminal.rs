@@ -34,6 +34,7 @@ impl Terminal {
 
         // Check color support
         let (supports_color, color_depth) = Self::detect_color_support();
+        tracing::debug!("Terminal {}x{}, color depth {:?}, unicode {}", width, height, color_depth, supports_unicode);
 
         Self {
             width,
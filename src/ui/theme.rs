@@ -1,6 +1,7 @@
 //! Color theme and output mode configuration
 
-use owo_colors::{OwoColorize, Style, colors::*};
+use owo_colors::{Style, colors::*};
+use serde::{Deserialize, Serialize};
 use super::terminal::{Terminal, ColorDepth};
 
 /// Output mode for different use cases
@@ -20,17 +21,48 @@ pub enum OutputMode {
     NoColor,
 }
 
+/// Set of concrete colors behind the semantic styles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePalette {
+    /// Bright colors for dark backgrounds
+    #[default]
+    Dark,
+    /// Darker colors that stay readable on light backgrounds
+    Light,
+    /// Bold, saturated colors and no grey text
+    HighContrast,
+}
+
+impl ThemePalette {
+    /// Name as written in config
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemePalette::Dark => "dark",
+            ThemePalette::Light => "light",
+            ThemePalette::HighContrast => "high-contrast",
+        }
+    }
+}
+
 /// Theme with semantic colors
 #[derive(Clone)]
 pub struct Theme {
     pub mode: OutputMode,
     terminal: Terminal,
+    palette: ThemePalette,
 }
 
 impl Theme {
     /// Create a new theme with output mode
     pub fn new(mode: OutputMode, terminal: Terminal) -> Self {
-        Self { mode, terminal }
+        Self { mode, terminal, palette: ThemePalette::default() }
+    }
+
+    /// Use a different color palette; NoColor and Print modes stay unstyled
+    pub fn with_palette(mut self, palette: ThemePalette) -> Self {
+        self.palette = palette;
+        self
     }
 
     /// Check if colors should be used
@@ -40,6 +72,15 @@ impl Theme {
             && self.terminal.supports_color
     }
 
+    /// Color depth to style for: the terminal's, or `None` when colors are off
+    pub fn color_depth(&self) -> ColorDepth {
+        if self.use_colors() {
+            self.terminal.color_depth
+        } else {
+            ColorDepth::None
+        }
+    }
+
     /// Check if emojis should be used
     pub fn use_emojis(&self) -> bool {
         self.mode != OutputMode::Print
@@ -52,73 +93,57 @@ impl Theme {
         self.mode == OutputMode::Default || self.mode == OutputMode::Rich
     }
 
+    /// Style for the active palette, or no styling when colors are off
+    fn pick(&self, dark: Style, light: Style, high_contrast: Style) -> Style {
+        if !self.use_colors() {
+            return Style::new();
+        }
+        match self.palette {
+            ThemePalette::Dark => dark,
+            ThemePalette::Light => light,
+            ThemePalette::HighContrast => high_contrast,
+        }
+    }
+
     /// Get success color style
     pub fn success(&self) -> Style {
-        if self.use_colors() {
-            Style::new().bright_green()
-        } else {
-            Style::new()
-        }
+        self.pick(Style::new().bright_green(), Style::new().green(), Style::new().bright_green().bold())
     }
 
     /// Get warning color style
     pub fn warning(&self) -> Style {
-        if self.use_colors() {
-            Style::new().bright_yellow()
-        } else {
-            Style::new()
-        }
+        self.pick(Style::new().bright_yellow(), Style::new().yellow(), Style::new().bright_yellow().bold())
     }
 
     /// Get error color style
     pub fn error(&self) -> Style {
-        if self.use_colors() {
-            Style::new().bright_red()
-        } else {
-            Style::new()
-        }
+        self.pick(Style::new().bright_red(), Style::new().red(), Style::new().bright_red().bold())
     }
 
     /// Get info color style
     pub fn info(&self) -> Style {
-        if self.use_colors() {
-            Style::new().bright_cyan()
-        } else {
-            Style::new()
-        }
+        self.pick(Style::new().bright_cyan(), Style::new().blue(), Style::new().bright_cyan().bold())
     }
 
     /// Get muted/secondary color style
     pub fn muted(&self) -> Style {
-        if self.use_colors() {
-            Style::new().bright_black()
-        } else {
-            Style::new()
-        }
+        self.pick(Style::new().bright_black(), Style::new().bright_black(), Style::new().white())
     }
 
     /// Get accent color style
     pub fn accent(&self) -> Style {
-        if self.use_colors() {
-            Style::new().bright_magenta()
-        } else {
-            Style::new()
-        }
+        self.pick(Style::new().bright_magenta(), Style::new().magenta(), Style::new().bright_magenta().bold())
     }
 
     /// Get primary text color style
     pub fn primary(&self) -> Style {
-        if self.use_colors() {
-            Style::new().bright_white()
-        } else {
-            Style::new()
-        }
+        self.pick(Style::new().bright_white(), Style::new().black(), Style::new().bright_white().bold())
     }
 
     /// Get highlighted text style
     pub fn highlight(&self) -> Style {
         if self.use_colors() {
-            Style::new().bright_white().bold()
+            self.primary().bold()
         } else {
             Style::new().bold()
         }
@@ -141,7 +166,7 @@ impl Theme {
     /// Format success text
     pub fn fmt_success(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_green().to_string()
+            self.success().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -150,7 +175,7 @@ impl Theme {
     /// Format warning text
     pub fn fmt_warning(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_yellow().to_string()
+            self.warning().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -159,7 +184,7 @@ impl Theme {
     /// Format error text
     pub fn fmt_error(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_red().to_string()
+            self.error().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -168,7 +193,7 @@ impl Theme {
     /// Format info text
     pub fn fmt_info(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_cyan().to_string()
+            self.info().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -177,7 +202,7 @@ impl Theme {
     /// Format muted text
     pub fn fmt_muted(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_black().to_string()
+            self.muted().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -186,7 +211,7 @@ impl Theme {
     /// Format primary text
     pub fn fmt_primary(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_white().to_string()
+            self.primary().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -195,7 +220,7 @@ impl Theme {
     /// Format highlighted text
     pub fn fmt_highlight(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_white().bold().to_string()
+            self.highlight().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -204,7 +229,7 @@ impl Theme {
     /// Format accent text
     pub fn fmt_accent(&self, text: &str) -> String {
         if self.use_colors() {
-            text.bright_magenta().to_string()
+            self.accent().style(text).to_string()
         } else {
             text.to_string()
         }
@@ -0,0 +1,233 @@
+//! Runs the `bakery` binary against a mock Azure DevOps server and checks what it saves
+
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PAT: &str = "integration-test-pat";
+
+/// A HOME with a config pointing at `server`
+fn write_config(home: &Path, server: &MockServer) {
+    let bakery_dir = home.join(".bakery");
+    fs::create_dir_all(&bakery_dir).unwrap();
+    fs::write(
+        bakery_dir.join("bakery-config.toml"),
+        format!(
+            r#"[azure_devops]
+base_url = "{}"
+organization = "org"
+project = "Proj"
+pat_token = "{}"
+api_version = "7.1"
+
+[storage]
+base_directory = "{}"
+tickets_subdir = "Tickets"
+openspec_subdir = "openspec"
+local_baking = false
+
+[openspec]
+ai_command_template = "claude -p"
+auto_generate = false
+"#,
+            server.uri(),
+            PAT,
+            home.join("unused").display()
+        ),
+    )
+    .unwrap();
+}
+
+async fn run_bakery(home: &Path, args: &[&str]) -> std::process::Output {
+    tokio::process::Command::new(env!("CARGO_BIN_EXE_bakery"))
+        .args(args)
+        .env("HOME", home)
+        .env("USERPROFILE", home)
+        .env_remove("AZURE_DEVOPS_PAT")
+        .env_remove("HTTPS_PROXY")
+        .env_remove("HTTP_PROXY")
+        .env_remove("https_proxy")
+        .env_remove("http_proxy")
+        .output()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn fetches_and_saves_a_work_item_then_reports_it_up_to_date() {
+    let server = MockServer::start().await;
+    let auth = format!("Basic {}", general_purpose::STANDARD.encode(format!(":{}", PAT)));
+
+    Mock::given(method("GET"))
+        .and(path("/org/_apis/wit/workitems/42"))
+        .and(header("Authorization", auth.as_str()))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": 42,
+            "rev": 3,
+            "url": format!("{}/org/_apis/wit/workItems/42", server.uri()),
+            "_links": {},
+            "fields": {
+                "System.Title": "Speed up login",
+                "System.WorkItemType": "User Story",
+                "System.State": "Active",
+                "System.AreaPath": "Proj",
+                "System.IterationPath": "Proj",
+                "System.CreatedDate": "2024-06-03T10:00:00Z",
+                "System.ChangedDate": "2024-06-04T10:00:00Z",
+                "System.Description": "<div><p>Login takes <b>8s</b> &amp; should take 1s.</p></div>",
+                "Microsoft.VSTS.Common.AcceptanceCriteria": "<ul><li>Login under 1s</li><li>No new errors</li></ul>"
+            },
+            "relations": [{
+                "rel": "AttachedFile",
+                "url": format!("{}/org/_apis/wit/attachments/abc", server.uri()),
+                "attributes": { "name": "trace.txt" }
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/org/_apis/wit/attachments/abc"))
+        .respond_with(ResponseTemplate::new(200).insert_header("Content-Type", "text/plain").set_body_string("slow query"))
+        .mount(&server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/org/_apis/wit/workItems/42/comments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "count": 1,
+            "value": [{
+                "id": 1,
+                "version": 1,
+                "text": "<p>Profiled it, see trace.txt</p>",
+                "createdDate": "2024-06-04T09:00:00Z",
+                "author": { "displayName": "Ada Lovelace", "url": "", "uniqueName": "ada@example.com", "_links": {} }
+            }]
+        })))
+        .mount(&server)
+        .await;
+
+    // The up-to-date check on the second run
+    Mock::given(method("POST"))
+        .and(path("/org/_apis/wit/workitemsbatch"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "count": 1,
+            "value": [{ "id": 42, "rev": 3, "fields": { "System.Id": 42 } }]
+        })))
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &server);
+    let output_dir = home.path().join("out");
+    let output_arg = output_dir.to_string_lossy().to_string();
+
+    let output = run_bakery(home.path(), &["-t", "42", "--no-openspec", "--output-dir", &output_arg]).await;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let ticket_dir = output_dir.join("Tickets/42");
+    let metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(ticket_dir.join("metadata.json")).unwrap()).unwrap();
+    assert_eq!(metadata["revision"], 3);
+    assert_eq!(metadata["title"], "Speed up login");
+
+    let description = fs::read_to_string(ticket_dir.join("description.md")).unwrap();
+    assert!(description.ends_with("## Description\n\nLogin takes 8s & should take 1s."), "{}", description);
+
+    let criteria = fs::read_to_string(ticket_dir.join("acceptance-criteria.md")).unwrap();
+    assert!(criteria.contains("1. Login under 1s\n\n2. No new errors"), "{}", criteria);
+
+    let comment: serde_json::Value = serde_json::from_str(&fs::read_to_string(ticket_dir.join("comments/comment_001.json")).unwrap()).unwrap();
+    assert_eq!(comment["text"], "Profiled it, see trace.txt");
+    assert_eq!(comment["author"]["display_name"], "Ada Lovelace");
+
+    assert_eq!(fs::read_to_string(ticket_dir.join("attachments/trace.txt")).unwrap(), "slow query");
+
+    // Same revision on the server: nothing is fetched again (the work item mock expects one call)
+    let output = run_bakery(home.path(), &["-t", "42", "--no-openspec", "--print", "--output-dir", &output_arg]).await;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("work_item_id: 42\nrevision: 3\nstatus: up_to_date"), "{}", stdout);
+}
+
+#[tokio::test]
+async fn missing_work_item_fails_with_the_fetch_exit_code() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/org/_apis/wit/workitems/404"))
+        .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+            "message": "TF401232: Work item 404 does not exist, or you do not have permissions to read it."
+        })))
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &server);
+    let output_arg = home.path().join("out").to_string_lossy().to_string();
+
+    let output = run_bakery(home.path(), &["-t", "404", "--no-openspec", "--output-dir", &output_arg]).await;
+    assert_eq!(output.status.code(), Some(3), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!home.path().join("out/Tickets/404").exists());
+}
+
+#[tokio::test]
+async fn empty_query_prints_an_empty_list_in_format_mode() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/org/Proj/_apis/wit/wiql"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "workItems": [] })))
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &server);
+
+    let output = run_bakery(home.path(), &["--query", "SELECT [System.Id] FROM WorkItems", "--format", "json"]).await;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let work_items: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(work_items, json!([]));
+}
+
+#[tokio::test]
+async fn failed_query_keeps_stdout_clean_in_format_mode() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/org/Proj/_apis/wit/wiql"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("TF51005: The query references a field that does not exist."))
+        .mount(&server)
+        .await;
+
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &server);
+
+    let output = run_bakery(home.path(), &["--query", "SELECT [Nope] FROM WorkItems", "--format", "json"]).await;
+    assert_eq!(output.status.code(), Some(3), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("TF51005"));
+}
+
+#[tokio::test]
+async fn list_and_archive_use_the_output_dir() {
+    let server = MockServer::start().await;
+    let home = tempfile::tempdir().unwrap();
+    write_config(home.path(), &server);
+    let output_dir = home.path().join("ci-out");
+    let output_arg = output_dir.to_string_lossy().to_string();
+
+    let change_dir = output_dir.join("openspec/changes/add-42-speed-up-login");
+    fs::create_dir_all(&change_dir).unwrap();
+    fs::write(change_dir.join("proposal.md"), "# Change: Speed up login\n\n## Why\nLogin is slow.\n").unwrap();
+    fs::write(change_dir.join("tasks.md"), "- [ ] 1.1 Cache the token\n").unwrap();
+
+    let output = run_bakery(home.path(), &["--print", "--output-dir", &output_arg, "list"]).await;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("add-42-speed-up-login"));
+
+    let output = run_bakery(home.path(), &["--print", "--output-dir", &output_arg, "archive", "add-42-speed-up-login"]).await;
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!change_dir.exists());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&output_dir.join("openspec/changes/archive").to_string_lossy().to_string()));
+}